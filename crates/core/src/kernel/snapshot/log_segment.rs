@@ -17,7 +17,9 @@ use tracing::debug;
 
 use super::parse;
 use crate::kernel::transaction::CommitData;
-use crate::kernel::{arrow::json, ActionType, Metadata, Protocol, Schema, StructType};
+use crate::kernel::{
+    arrow::json, ActionType, DataType, Metadata, Protocol, Schema, StructField, StructType,
+};
 use crate::logstore::LogStore;
 use crate::{DeltaResult, DeltaTableConfig, DeltaTableError};
 
@@ -36,6 +38,19 @@ static DELETION_VECTOR_FILE_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r".*\.bin$").unwrap());
 pub(super) static TOMBSTONE_SCHEMA: LazyLock<StructType> =
     LazyLock::new(|| StructType::new(vec![ActionType::Remove.schema_field().clone()]));
+/// Schema to read domain metadata actions, whose configuration is a string as written by
+/// other writers, unlike the map declared in the log schema
+pub(super) static DOMAIN_METADATA_SCHEMA: LazyLock<StructType> = LazyLock::new(|| {
+    StructType::new(vec![StructField::new(
+        "domainMetadata",
+        StructType::new(vec![
+            StructField::new("domain", DataType::STRING, false),
+            StructField::new("configuration", DataType::STRING, false),
+            StructField::new("removed", DataType::BOOLEAN, false),
+        ]),
+        true,
+    )])
+});
 
 /// Trait to extend a file path representation with delta specific functionality
 ///
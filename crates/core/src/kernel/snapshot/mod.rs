@@ -28,12 +28,12 @@ use object_store::ObjectStore;
 use tracing::warn;
 
 use self::log_segment::{LogSegment, PathExt};
-use self::parse::{read_adds, read_removes};
+use self::parse::{read_adds, read_domain_metadata, read_removes};
 use self::replay::{LogMapper, LogReplayScanner, ReplayStream};
 use self::visitors::*;
 use super::{
-    Action, Add, AddCDCFile, CommitInfo, DataType, Metadata, Protocol, Remove, StructField,
-    Transaction,
+    Action, Add, AddCDCFile, CommitInfo, DataType, DomainMetadata, Metadata, Protocol, Remove,
+    StructField, Transaction,
 };
 use crate::kernel::parse::read_cdf_adds;
 use crate::kernel::transaction::{CommitData, PROTOCOL};
@@ -331,6 +331,38 @@ impl Snapshot {
             .boxed())
     }
 
+    /// Get the latest domain metadata for the given domain.
+    ///
+    /// Returns `None` if the domain does not exist or was removed.
+    pub async fn domain_metadata(
+        &self,
+        store: Arc<dyn ObjectStore>,
+        domain: &str,
+    ) -> DeltaResult<Option<DomainMetadata>> {
+        let log_stream = self.log_segment.commit_stream(
+            store.clone(),
+            &log_segment::DOMAIN_METADATA_SCHEMA,
+            &self.config,
+        )?;
+        let checkpoint_stream = self.log_segment.checkpoint_stream(
+            store,
+            &log_segment::DOMAIN_METADATA_SCHEMA,
+            &self.config,
+        );
+
+        // commits are read newest first, so the first match is the current value
+        let mut stream = log_stream.chain(checkpoint_stream);
+        while let Some(batch) = stream.next().await {
+            if let Some(metadata) = read_domain_metadata(&batch?)?
+                .into_iter()
+                .find(|m| m.domain == domain)
+            {
+                return Ok((!metadata.removed).then_some(metadata));
+            }
+        }
+        Ok(None)
+    }
+
     /// Get the statistics schema of the snapshot
     pub fn stats_schema(&self, table_schema: Option<&StructType>) -> DeltaResult<StructType> {
         let schema = table_schema.unwrap_or_else(|| self.schema());
@@ -613,6 +645,44 @@ impl EagerSnapshot {
         Ok(self.files.iter().flat_map(|b| read_cdf_adds(b)).flatten())
     }
 
+    /// Fill in missing partition values and sizes of remove actions from the active files
+    /// they remove, so the removes carry the extended file metadata.
+    ///
+    /// Values already set on a remove are kept. Returns the sorted paths of removes without
+    /// an active file in the snapshot, which are left unchanged.
+    pub fn complete_removes(&self, removes: &mut [Remove]) -> Vec<String> {
+        let mut pending: HashMap<String, Vec<usize>> = HashMap::new();
+        for (idx, remove) in removes.iter().enumerate() {
+            let has_partition_values =
+                matches!(&remove.partition_values, Some(values) if !values.is_empty());
+            if !has_partition_values || remove.size.is_none() {
+                pending.entry(remove.path.clone()).or_default().push(idx);
+            }
+        }
+
+        for file in self.files() {
+            if pending.is_empty() {
+                break;
+            }
+            let Some(indices) = pending.remove(file.path().as_ref()) else {
+                continue;
+            };
+            let template = file.remove_action(true);
+            for idx in indices {
+                let remove = &mut removes[idx];
+                if !matches!(&remove.partition_values, Some(values) if !values.is_empty()) {
+                    remove.partition_values = template.partition_values.clone();
+                }
+                remove.size = remove.size.or(template.size);
+                remove.extended_file_metadata = Some(true);
+            }
+        }
+
+        let mut unknown = pending.into_keys().collect::<Vec<_>>();
+        unknown.sort();
+        unknown
+    }
+
     /// Iterate over all latest app transactions
     pub fn transactions(&self) -> DeltaResult<impl Iterator<Item = Transaction> + '_> {
         self.transactions
@@ -624,6 +694,15 @@ impl EagerSnapshot {
             ))
     }
 
+    /// Get the latest domain metadata for the given domain
+    pub async fn domain_metadata(
+        &self,
+        store: Arc<dyn ObjectStore>,
+        domain: &str,
+    ) -> DeltaResult<Option<DomainMetadata>> {
+        self.snapshot.domain_metadata(store, domain).await
+    }
+
     /// Advance the snapshot based on the given commit actions
     pub fn advance<'a>(
         &mut self,
@@ -1085,6 +1164,59 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "datafusion")]
+    #[tokio::test]
+    async fn test_complete_removes() {
+        let table = crate::DeltaOps::new_in_memory()
+            .write(vec![crate::writer::test_utils::get_record_batch(
+                None, false,
+            )])
+            .with_partition_columns(["modified"])
+            .await
+            .unwrap();
+        let snapshot = table.snapshot().unwrap().snapshot();
+        let add = snapshot.file_actions().unwrap().next().unwrap();
+
+        let mut removes = vec![
+            Remove {
+                path: add.path.clone(),
+                data_change: true,
+                ..Default::default()
+            },
+            Remove {
+                path: "missing.parquet".to_string(),
+                data_change: true,
+                ..Default::default()
+            },
+        ];
+        let unknown = snapshot.complete_removes(&mut removes);
+        assert_eq!(unknown, vec!["missing.parquet".to_string()]);
+
+        assert_eq!(
+            removes[0].partition_values.as_ref(),
+            Some(&add.partition_values)
+        );
+        assert!(removes[0]
+            .partition_values
+            .as_ref()
+            .unwrap()
+            .contains_key("modified"));
+        assert_eq!(removes[0].size, Some(add.size));
+        assert_eq!(removes[0].extended_file_metadata, Some(true));
+        assert_eq!(removes[1].partition_values, None);
+        assert_eq!(removes[1].size, None);
+
+        // removes overwriting the table carry the values of the files they remove
+        let removes = table.snapshot().unwrap().remove_all_files();
+        assert_eq!(removes.len(), snapshot.files_count());
+        let remove = removes.iter().find(|r| r.path == add.path).unwrap();
+        assert_eq!(
+            remove.partition_values.as_ref(),
+            Some(&add.partition_values)
+        );
+        assert_eq!(remove.size, Some(add.size));
+    }
+
     #[test]
     fn test_partition_schema() {
         let schema = StructType::new(vec![
@@ -7,7 +7,9 @@ use delta_kernel::table_features::{ReaderFeature, WriterFeature};
 use percent_encoding::percent_decode_str;
 
 use crate::kernel::arrow::extract::{self as ex, ProvidesColumnByName};
-use crate::kernel::{Add, AddCDCFile, DeletionVectorDescriptor, Metadata, Protocol, Remove};
+use crate::kernel::{
+    Add, AddCDCFile, DeletionVectorDescriptor, DomainMetadata, Metadata, Protocol, Remove,
+};
 use crate::{DeltaResult, DeltaTableError};
 
 pub(super) fn read_metadata(batch: &dyn ProvidesColumnByName) -> DeltaResult<Option<Metadata>> {
@@ -214,6 +216,35 @@ pub(super) fn read_cdf_adds(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec
     Ok(result)
 }
 
+pub(super) fn read_domain_metadata(
+    batch: &dyn ProvidesColumnByName,
+) -> DeltaResult<Vec<DomainMetadata>> {
+    let mut result = Vec::new();
+
+    if let Some(arr) = ex::extract_and_cast_opt::<StructArray>(batch, "domainMetadata") {
+        // Stop early if all values are null
+        if arr.null_count() == arr.len() {
+            return Ok(result);
+        }
+
+        let domain = ex::extract_and_cast::<StringArray>(arr, "domain")?;
+        let configuration = ex::extract_and_cast::<StringArray>(arr, "configuration")?;
+        let removed = ex::extract_and_cast::<BooleanArray>(arr, "removed")?;
+
+        for idx in 0..arr.len() {
+            if arr.is_valid(idx) {
+                result.push(DomainMetadata {
+                    domain: ex::read_str(domain, idx)?.to_string(),
+                    configuration: ex::read_str(configuration, idx)?.to_string(),
+                    removed: ex::read_bool(removed, idx)?,
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
 pub(super) fn read_removes(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<Remove>> {
     let mut result = Vec::new();
 
@@ -80,4 +80,46 @@ mod tests {
         assert_eq!(app_txns3.get("my-app").map(|t| t.version), Some(3));
         assert_eq!(table3.version(), 1);
     }
+
+    #[tokio::test]
+    async fn test_app_txn_idempotent_commit() {
+        let batch = get_record_batch(None, false);
+        let properties = || {
+            CommitProperties::default().with_application_transaction(Transaction::new("my-app", 1))
+        };
+        let table = DeltaOps::new_in_memory()
+            .write(vec![batch.clone()])
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 0);
+        let stale_table = table.clone();
+
+        let table = DeltaOps::from(table)
+            .write(vec![batch.clone()])
+            .with_commit_properties(properties())
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 1);
+
+        // retrying with the same app transaction does not commit again
+        let table = DeltaOps::from(table)
+            .write(vec![batch.clone()])
+            .with_commit_properties(properties())
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 1);
+
+        // neither does a retry which read the table before the transaction was committed
+        let stale_table = DeltaOps::from(stale_table)
+            .write(vec![batch.clone()])
+            .with_commit_properties(properties())
+            .await
+            .unwrap();
+        assert_eq!(stale_table.version(), 1);
+
+        let latest_version = table.log_store().get_latest_version(0).await.unwrap();
+        assert_eq!(latest_version, 1);
+        let app_txns = table.get_app_transaction_version();
+        assert_eq!(app_txns.get("my-app").map(|t| t.version), Some(1));
+    }
 }
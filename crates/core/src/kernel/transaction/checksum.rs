@@ -0,0 +1,149 @@
+//! Version checksum files summarizing the state of a table version.
+//!
+//! A [`VersionChecksum`] is written as `_delta_log/<version>.crc` next to the commit of the
+//! version. Readers can use it to validate a loaded snapshot or to answer questions like the
+//! size of the table without replaying the log.
+
+use bytes::Bytes;
+use object_store::Error as ObjectStoreError;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::kernel::{EagerSnapshot, Metadata, Protocol};
+use crate::logstore::{checksum_uri_from_version, LogStore};
+use crate::DeltaResult;
+
+/// Summary of the state of a table version, as defined by the Delta protocol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionChecksum {
+    /// Id of the operation which created the version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txn_id: Option<String>,
+    /// Total size of the active files in bytes
+    pub table_size_bytes: i64,
+    /// Number of active files
+    pub num_files: i64,
+    /// Number of metadata actions, always 1
+    pub num_metadata: i64,
+    /// Number of protocol actions, always 1
+    pub num_protocol: i64,
+    /// The table metadata
+    pub metadata: Metadata,
+    /// The table protocol
+    pub protocol: Protocol,
+}
+
+impl VersionChecksum {
+    /// Summarize the state of the snapshot
+    pub fn from_snapshot(snapshot: &EagerSnapshot, operation_id: Option<Uuid>) -> Self {
+        let (num_files, table_size_bytes) = snapshot.files().fold((0, 0), |(count, size), file| {
+            (count + 1, size + file.size())
+        });
+        Self {
+            txn_id: operation_id.map(|id| id.to_string()),
+            table_size_bytes,
+            num_files,
+            num_metadata: 1,
+            num_protocol: 1,
+            metadata: snapshot.metadata().clone(),
+            protocol: snapshot.protocol().clone(),
+        }
+    }
+}
+
+/// Write the checksum file of a table version, replacing any existing one
+pub async fn write_version_checksum(
+    log_store: &dyn LogStore,
+    version: i64,
+    checksum: &VersionChecksum,
+) -> DeltaResult<()> {
+    let bytes = Bytes::from(serde_json::to_vec(checksum)?);
+    log_store
+        .object_store(None)
+        .put(&checksum_uri_from_version(version), bytes.into())
+        .await?;
+    Ok(())
+}
+
+/// Read the checksum file of a table version, if it exists
+pub async fn read_version_checksum(
+    log_store: &dyn LogStore,
+    version: i64,
+) -> DeltaResult<Option<VersionChecksum>> {
+    let store = log_store.object_store(None);
+    match store.get(&checksum_uri_from_version(version)).await {
+        Ok(result) => Ok(Some(serde_json::from_slice(&result.bytes().await?)?)),
+        Err(ObjectStoreError::NotFound { .. }) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::transaction::{CommitBuilder, CommitProperties};
+    use crate::kernel::{Action, Add, DataType, PrimitiveType};
+    use crate::protocol::{DeltaOperation, SaveMode};
+
+    #[tokio::test]
+    async fn test_write_crc() {
+        let table = crate::DeltaOps::new_in_memory()
+            .create()
+            .with_column(
+                "value",
+                DataType::Primitive(PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        let log_store = table.log_store();
+        let operation = DeltaOperation::Write {
+            mode: SaveMode::Append,
+            partition_by: None,
+            predicate: None,
+        };
+        let add = |path: &str, size: i64| {
+            Action::Add(Add {
+                path: path.to_string(),
+                size,
+                data_change: true,
+                ..Default::default()
+            })
+        };
+
+        // checksums are only written when enabled
+        let commit = CommitBuilder::default()
+            .with_actions(vec![add("a.parquet", 100)])
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(commit.version(), 1);
+        assert!(read_version_checksum(log_store.as_ref(), 1)
+            .await
+            .unwrap()
+            .is_none());
+
+        let commit = CommitBuilder::from(CommitProperties::default().with_write_crc(true))
+            .with_actions(vec![add("b.parquet", 200)])
+            .build(Some(&commit.snapshot()), log_store.clone(), operation)
+            .await
+            .unwrap();
+        assert_eq!(commit.version(), 2);
+        let checksum = read_version_checksum(log_store.as_ref(), 2)
+            .await
+            .unwrap()
+            .unwrap();
+        let snapshot = commit.snapshot();
+        assert_eq!(checksum.num_files, 2);
+        assert_eq!(checksum.table_size_bytes, 300);
+        assert_eq!(&checksum.metadata, snapshot.metadata());
+        assert_eq!(&checksum.protocol, snapshot.protocol());
+        assert!(checksum.txn_id.is_some());
+    }
+}
@@ -1,5 +1,5 @@
 //! Helper module to check if a transaction can be committed in case of conflicting commits.
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use super::CommitInfo;
 #[cfg(feature = "datafusion")]
@@ -7,11 +7,11 @@ use crate::delta_datafusion::DataFusionMixins;
 use crate::errors::DeltaResult;
 use crate::kernel::EagerSnapshot;
 use crate::kernel::Transaction;
-use crate::kernel::{Action, Add, Metadata, Protocol, Remove};
+use crate::kernel::{Action, Add, DataType, Metadata, Protocol, Remove};
 use crate::logstore::{get_actions, LogStore};
 use crate::protocol::DeltaOperation;
 use crate::table::config::IsolationLevel;
-use crate::DeltaTableError;
+use crate::{DeltaTableError, DeltaTablePartition, PartitionFilter};
 
 #[cfg(feature = "datafusion")]
 use super::state::AddContainer;
@@ -85,6 +85,19 @@ pub enum CommitConflictError {
     NoMetadata,
 }
 
+/// Details about the winning commit a transaction conflicted with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConflictDetails {
+    /// Version of the winning commit
+    pub winning_version: i64,
+    /// Operation of the winning commit as recorded in its commit info
+    pub winning_operation: Option<String>,
+    /// Paths of the files the conflict was detected on
+    pub conflicting_paths: Vec<String>,
+    /// Partitions touched by the winning commit
+    pub winning_partitions: Vec<BTreeMap<String, Option<String>>>,
+}
+
 /// A struct representing different attributes of current transaction needed for conflict detection.
 #[allow(unused)]
 pub(crate) struct TransactionInfo<'a> {
@@ -106,6 +119,8 @@ pub(crate) struct TransactionInfo<'a> {
     pub(crate) read_snapshot: &'a EagerSnapshot,
     /// Whether the transaction tainted the whole table
     read_whole_table: bool,
+    /// Partition filters all files touched by the transaction match
+    partition_scope: Option<&'a [PartitionFilter]>,
 }
 
 impl<'a> TransactionInfo<'a> {
@@ -137,6 +152,7 @@ impl<'a> TransactionInfo<'a> {
             actions,
             read_snapshot,
             read_whole_table,
+            partition_scope: None,
         })
     }
 
@@ -161,6 +177,7 @@ impl<'a> TransactionInfo<'a> {
             actions,
             read_snapshot,
             read_whole_table,
+            partition_scope: None,
         }
     }
 
@@ -184,6 +201,83 @@ impl<'a> TransactionInfo<'a> {
             actions,
             read_snapshot,
             read_whole_table,
+            partition_scope: None,
+        })
+    }
+
+    /// Restrict the transaction to the partitions matching all of the given filters.
+    ///
+    /// Winning commits which only added or removed files outside of this scope are not
+    /// checked for conflicts, since they can not have touched data the transaction read.
+    pub fn with_partition_scope(mut self, partition_scope: &'a [PartitionFilter]) -> Self {
+        self.partition_scope = Some(partition_scope);
+        self
+    }
+
+    /// Whether the winning commit exclusively changed files outside of the partition scope
+    /// of the transaction.
+    ///
+    /// Commits containing anything besides file actions and commit info, or files whose
+    /// partition values can not be evaluated, are never considered outside of the scope.
+    pub fn outside_partition_scope(&self, summary: &WinningCommitSummary) -> bool {
+        let Some(scope) = self.partition_scope else {
+            return false;
+        };
+        let schema = self.read_snapshot.schema();
+        let partition_types: HashMap<&String, &DataType> = self
+            .read_snapshot
+            .metadata()
+            .partition_columns
+            .iter()
+            .filter_map(|column| {
+                schema
+                    .field(column)
+                    .map(|field| (column, field.data_type()))
+            })
+            .collect();
+        if scope.is_empty()
+            || scope
+                .iter()
+                .any(|filter| !partition_types.contains_key(&filter.key))
+        {
+            return false;
+        }
+
+        let in_scope = |partition_values: &HashMap<String, Option<String>>| {
+            let partitions = partition_values
+                .iter()
+                .map(|(key, value)| {
+                    let data_type = partition_types.get(key)?;
+                    let value = match (value, data_type) {
+                        (None, _) => delta_kernel::expressions::Scalar::Null((*data_type).clone()),
+                        (Some(value), DataType::Primitive(primitive)) => {
+                            primitive.parse_scalar(value).ok()?
+                        }
+                        _ => return None,
+                    };
+                    Some(DeltaTablePartition {
+                        key: key.clone(),
+                        value,
+                    })
+                })
+                .collect::<Option<Vec<_>>>();
+            match partitions {
+                Some(partitions) => scope
+                    .iter()
+                    .all(|filter| filter.match_partitions(&partitions, &partition_types)),
+                None => true,
+            }
+        };
+
+        summary.actions.iter().all(|action| match action {
+            Action::Add(add) => !in_scope(&add.partition_values),
+            Action::Remove(remove) => remove
+                .partition_values
+                .as_ref()
+                .is_some_and(|values| !in_scope(values)),
+            Action::Cdc(cdc) => !in_scope(&cdc.partition_values),
+            Action::CommitInfo(_) => true,
+            _ => false,
         })
     }
 
@@ -226,19 +320,28 @@ impl<'a> TransactionInfo<'a> {
 
 /// Summary of the Winning commit against which we want to check the conflict
 #[derive(Debug)]
-pub(crate) struct WinningCommitSummary {
+pub struct WinningCommitSummary {
+    /// All actions contained in the commit
     pub actions: Vec<Action>,
+    /// The commit info of the commit, if present
     pub commit_info: Option<CommitInfo>,
 }
 
 impl WinningCommitSummary {
+    /// Read the summary of the commit following `read_version`.
+    ///
+    /// Fails if `winning_commit_version` is not the version following `read_version`, or if
+    /// the commit does not exist.
     pub async fn try_new(
         log_store: &dyn LogStore,
         read_version: i64,
         winning_commit_version: i64,
     ) -> DeltaResult<Self> {
-        // NOTE using assert, since a wrong version would right now mean a bug in our code.
-        assert_eq!(winning_commit_version, read_version + 1);
+        if winning_commit_version != read_version + 1 {
+            return Err(DeltaTableError::Generic(format!(
+                "Winning commit version {winning_commit_version} does not follow read version {read_version}"
+            )));
+        }
 
         let commit_log_bytes = log_store.read_commit_entry(winning_commit_version).await?;
         match commit_log_bytes {
@@ -261,6 +364,7 @@ impl WinningCommitSummary {
         }
     }
 
+    /// Metadata actions of the commit
     pub fn metadata_updates(&self) -> Vec<Metadata> {
         self.actions
             .iter()
@@ -272,6 +376,7 @@ impl WinningCommitSummary {
             .collect()
     }
 
+    /// Ids of the applications whose transaction versions were set by the commit
     pub fn app_level_transactions(&self) -> HashSet<String> {
         self.actions
             .iter()
@@ -283,6 +388,7 @@ impl WinningCommitSummary {
             .collect()
     }
 
+    /// Protocol actions of the commit
     pub fn protocol(&self) -> Vec<Protocol> {
         self.actions
             .iter()
@@ -294,6 +400,7 @@ impl WinningCommitSummary {
             .collect()
     }
 
+    /// Files removed by the commit
     pub fn removed_files(&self) -> Vec<Remove> {
         self.actions
             .iter()
@@ -305,6 +412,7 @@ impl WinningCommitSummary {
             .collect()
     }
 
+    /// Files added by the commit
     pub fn added_files(&self) -> Vec<Add> {
         self.actions
             .iter()
@@ -316,6 +424,24 @@ impl WinningCommitSummary {
             .collect()
     }
 
+    /// Distinct partition values of all files added or removed by the commit
+    pub fn touched_partitions(&self) -> Vec<BTreeMap<String, Option<String>>> {
+        let partitions: BTreeSet<_> = self
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::Add(add) => Some(add.partition_values.clone().into_iter().collect()),
+                Action::Remove(remove) => remove
+                    .partition_values
+                    .clone()
+                    .map(|values| values.into_iter().collect()),
+                _ => None,
+            })
+            .collect();
+        partitions.into_iter().collect()
+    }
+
+    /// Files added by the commit if it is a blind append, empty otherwise
     pub fn blind_append_added_files(&self) -> Vec<Add> {
         if self.is_blind_append().unwrap_or(false) {
             self.added_files()
@@ -324,6 +450,7 @@ impl WinningCommitSummary {
         }
     }
 
+    /// Files added by the commit if it is not a blind append, empty otherwise
     pub fn changed_data_added_files(&self) -> Vec<Add> {
         if self.is_blind_append().unwrap_or(false) {
             vec![]
@@ -332,6 +459,7 @@ impl WinningCommitSummary {
         }
     }
 
+    /// Whether the commit info marks the commit as a blind append, if present
     pub fn is_blind_append(&self) -> Option<bool> {
         self.commit_info
             .as_ref()
@@ -339,20 +467,109 @@ impl WinningCommitSummary {
     }
 }
 
+/// Whether the actions only add files to the table, optionally recording app transactions.
+///
+/// Such commits commute with the data changes of other commits and only conflict with
+/// changes to the protocol, the metadata or the app transactions of the table.
+pub(crate) fn only_adds_files(actions: &[Action]) -> bool {
+    actions.iter().all(|action| {
+        matches!(
+            action,
+            Action::Add(_) | Action::CommitInfo(_) | Action::Txn(_)
+        )
+    })
+}
+
+/// A transaction checked for conflicts with a winning commit
+pub struct PendingTransaction<'a> {
+    info: &'a TransactionInfo<'a>,
+}
+
+impl<'a> PendingTransaction<'a> {
+    pub(crate) fn new(info: &'a TransactionInfo<'a>) -> Self {
+        Self { info }
+    }
+
+    /// The actions the transaction wants to commit
+    pub fn actions(&self) -> &[Action] {
+        self.info.actions
+    }
+
+    /// The snapshot the transaction read
+    pub fn read_snapshot(&self) -> &EagerSnapshot {
+        self.info.read_snapshot
+    }
+}
+
+/// Strategy deciding whether a transaction may still be committed after a concurrent
+/// winning commit.
+///
+/// The resolution is invoked once for every version committed between the version the
+/// transaction read and the version it is committed as.
+pub trait ConflictResolution: Send + Sync {
+    /// Check the transaction for conflicts with the winning commit
+    fn resolve(
+        &self,
+        txn: &PendingTransaction<'_>,
+        summary: &WinningCommitSummary,
+        operation: &DeltaOperation,
+    ) -> Result<(), CommitConflictError>;
+}
+
+/// Conflict resolution following the isolation level of the table
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultConflictResolution;
+
+impl ConflictResolution for DefaultConflictResolution {
+    fn resolve(
+        &self,
+        txn: &PendingTransaction<'_>,
+        summary: &WinningCommitSummary,
+        operation: &DeltaOperation,
+    ) -> Result<(), CommitConflictError> {
+        ConflictChecker::new(txn.info, summary, Some(operation)).check_conflicts()
+    }
+}
+
+/// Conflict resolution for append-only ingestion, skipping the file level conflict checks
+/// for transactions that only add files.
+///
+/// Changes to the protocol or the metadata of the table and app transactions committed by
+/// the winning commit are still checked. Other transactions are checked with the
+/// [DefaultConflictResolution].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BlindAppendConflictResolution;
+
+impl ConflictResolution for BlindAppendConflictResolution {
+    fn resolve(
+        &self,
+        txn: &PendingTransaction<'_>,
+        summary: &WinningCommitSummary,
+        operation: &DeltaOperation,
+    ) -> Result<(), CommitConflictError> {
+        let checker = ConflictChecker::new(txn.info, summary, Some(operation));
+        if only_adds_files(txn.actions()) {
+            checker.check_blind_append_conflicts()
+        } else {
+            checker.check_conflicts()
+        }
+    }
+}
+
 /// Checks if a failed commit may be committed after a conflicting winning commit
 pub(crate) struct ConflictChecker<'a> {
     /// transaction information for current transaction at start of check
-    txn_info: TransactionInfo<'a>,
+    txn_info: &'a TransactionInfo<'a>,
     /// Summary of the transaction, that has been committed ahead of the current transaction
-    winning_commit_summary: WinningCommitSummary,
+    winning_commit_summary: &'a WinningCommitSummary,
     /// Isolation level for the current transaction
     isolation_level: IsolationLevel,
 }
 
 impl<'a> ConflictChecker<'a> {
     pub fn new(
-        transaction_info: TransactionInfo<'a>,
-        winning_commit_summary: WinningCommitSummary,
+        transaction_info: &'a TransactionInfo<'a>,
+        winning_commit_summary: &'a WinningCommitSummary,
         operation: Option<&DeltaOperation>,
     ) -> ConflictChecker<'a> {
         let isolation_level = operation
@@ -388,6 +605,12 @@ impl<'a> ConflictChecker<'a> {
     /// `winning_commit_version` and returns an updated [`TransactionInfo`] that represents
     /// the transaction as if it had started while reading the `winning_commit_version`.
     pub fn check_conflicts(&self) -> Result<(), CommitConflictError> {
+        if self
+            .txn_info
+            .outside_partition_scope(self.winning_commit_summary)
+        {
+            return Ok(());
+        }
         self.check_protocol_compatibility()?;
         self.check_no_metadata_updates()?;
         self.check_for_added_files_that_should_have_been_read_by_current_txn()?;
@@ -397,6 +620,73 @@ impl<'a> ConflictChecker<'a> {
         Ok(())
     }
 
+    /// Check a transaction only adding files for conflicts, skipping the checks of the files
+    /// the transaction read or removed.
+    pub fn check_blind_append_conflicts(&self) -> Result<(), CommitConflictError> {
+        self.check_protocol_compatibility()?;
+        self.check_no_metadata_updates()?;
+        self.check_for_updated_application_transaction_ids_that_current_txn_depends_on()?;
+        Ok(())
+    }
+
+    /// Collect details about the winning commit for a conflict detected by `check_conflicts`
+    pub fn conflict_details(
+        &self,
+        winning_version: i64,
+        conflict: &CommitConflictError,
+    ) -> ConflictDetails {
+        let conflicting_paths = match conflict {
+            CommitConflictError::ConcurrentAppend => self
+                .winning_commit_summary
+                .added_files()
+                .into_iter()
+                .map(|add| add.path)
+                .collect(),
+            CommitConflictError::ConcurrentDeleteRead => {
+                let read_paths: HashSet<String> = self
+                    .txn_info
+                    .read_files()
+                    .map(|files| files.map(|f| f.path).collect())
+                    .unwrap_or_default();
+                self.winning_commit_summary
+                    .removed_files()
+                    .into_iter()
+                    .map(|remove| remove.path)
+                    .filter(|path| self.txn_info.read_whole_table() || read_paths.contains(path))
+                    .collect()
+            }
+            CommitConflictError::ConcurrentDeleteDelete => {
+                let txn_deleted: HashSet<&String> = self
+                    .txn_info
+                    .actions
+                    .iter()
+                    .filter_map(|action| match action {
+                        Action::Remove(remove) => Some(&remove.path),
+                        _ => None,
+                    })
+                    .collect();
+                self.winning_commit_summary
+                    .removed_files()
+                    .into_iter()
+                    .map(|remove| remove.path)
+                    .filter(|path| txn_deleted.contains(path))
+                    .collect()
+            }
+            _ => vec![],
+        };
+
+        ConflictDetails {
+            winning_version,
+            winning_operation: self
+                .winning_commit_summary
+                .commit_info
+                .as_ref()
+                .and_then(|info| info.operation.clone()),
+            conflicting_paths,
+            winning_partitions: self.winning_commit_summary.touched_partitions(),
+        }
+    }
+
     /// Asserts that the client is up to date with the protocol and is allowed
     /// to read and write against the protocol set by the committed transaction.
     fn check_protocol_compatibility(&self) -> Result<(), CommitConflictError> {
@@ -708,7 +998,7 @@ mod tests {
             actions: concurrent,
             commit_info: None,
         };
-        let checker = ConflictChecker::new(transaction_info, summary, None);
+        let checker = ConflictChecker::new(&transaction_info, &summary, None);
         checker.check_conflicts()
     }
 
@@ -772,6 +1062,58 @@ mod tests {
         // TODO disjoint transactions
     }
 
+    #[test]
+    #[cfg(feature = "datafusion")]
+    fn test_partition_scope() {
+        use crate::table::state::DeltaTableState;
+
+        let partition_file = |value: &str| {
+            let mut add = simple_add(true, "1", "10");
+            add.partition_values =
+                HashMap::from([("modified".to_string(), Some(value.to_string()))]);
+            add
+        };
+        let file_outside = partition_file("2021-02-02");
+        let file_inside = partition_file("2021-02-01");
+        let mut setup_actions = vec![
+            ActionFactory::protocol(None, None, None::<Vec<_>>, None::<Vec<_>>).into(),
+            ActionFactory::metadata(TestSchemas::simple(), Some(vec!["modified"]), None).into(),
+        ];
+        setup_actions.push(file_outside.clone().into());
+        setup_actions.push(file_inside.clone().into());
+        let state = DeltaTableState::from_actions(setup_actions).unwrap();
+        let actions = vec![];
+        let scope = vec![PartitionFilter::try_from(("modified", "=", "2021-02-01")).unwrap()];
+
+        // the transaction read the whole table, so any concurrent delete conflicts
+        let outside = WinningCommitSummary {
+            actions: vec![ActionFactory::remove(&file_outside, true).into()],
+            commit_info: None,
+        };
+        let transaction_info = TransactionInfo::new(state.snapshot(), None, &actions, true);
+        assert!(matches!(
+            ConflictChecker::new(&transaction_info, &outside, None).check_conflicts(),
+            Err(CommitConflictError::ConcurrentDeleteRead)
+        ));
+
+        // unless the delete happened outside of the partition scope
+        let transaction_info = transaction_info.with_partition_scope(&scope);
+        assert!(transaction_info.outside_partition_scope(&outside));
+        assert!(ConflictChecker::new(&transaction_info, &outside, None)
+            .check_conflicts()
+            .is_ok());
+
+        let inside = WinningCommitSummary {
+            actions: vec![ActionFactory::remove(&file_inside, true).into()],
+            commit_info: None,
+        };
+        assert!(!transaction_info.outside_partition_scope(&inside));
+        assert!(matches!(
+            ConflictChecker::new(&transaction_info, &inside, None).check_conflicts(),
+            Err(CommitConflictError::ConcurrentDeleteRead)
+        ));
+    }
+
     #[tokio::test]
     #[cfg(feature = "datafusion")]
     // tests adopted from https://github.com/delta-io/delta/blob/24c025128612a4ae02d0ad958621f928cda9a3ec/core/src/test/scala/org/apache/spark/sql/delta/OptimisticTransactionSuite.scala#L40-L94
@@ -887,4 +1229,52 @@ mod tests {
 
         // TODO conflicting txns
     }
+
+    #[test]
+    #[cfg(feature = "datafusion")]
+    fn test_blind_append_conflict_resolution() {
+        use crate::table::state::DeltaTableState;
+
+        let state = DeltaTableState::from_actions(init_table_actions()).unwrap();
+        let operation = DeltaOperation::Write {
+            mode: crate::protocol::SaveMode::Append,
+            partition_by: None,
+            predicate: None,
+        };
+        let resolve = |actions: Vec<Action>, concurrent: Vec<Action>| {
+            let transaction_info = TransactionInfo::new(state.snapshot(), None, &actions, true);
+            let summary = WinningCommitSummary {
+                actions: concurrent,
+                commit_info: None,
+            };
+            BlindAppendConflictResolution.resolve(
+                &PendingTransaction::new(&transaction_info),
+                &summary,
+                &operation,
+            )
+        };
+        let append = || -> Action { simple_add(true, "1", "10").into() };
+        let txn = || -> Action { Transaction::new("my-app", 1).into() };
+
+        // the file level checks are skipped
+        assert!(resolve(vec![append()], vec![append()]).is_ok());
+
+        // changes to the metadata and protocol still conflict
+        let metadata = ActionFactory::metadata(TestSchemas::simple(), None::<Vec<&str>>, None);
+        assert!(matches!(
+            resolve(vec![append()], vec![metadata.into()]),
+            Err(CommitConflictError::MetadataChanged)
+        ));
+        let protocol = ActionFactory::protocol(Some(3), Some(7), None::<Vec<_>>, None::<Vec<_>>);
+        assert!(matches!(
+            resolve(vec![append()], vec![protocol.into()]),
+            Err(CommitConflictError::ProtocolChanged(_))
+        ));
+
+        // as do app transactions already committed by the winning commit
+        assert!(matches!(
+            resolve(vec![append(), txn()], vec![append(), txn()]),
+            Err(CommitConflictError::ConcurrentTransaction)
+        ));
+    }
 }
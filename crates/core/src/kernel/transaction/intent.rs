@@ -0,0 +1,387 @@
+//! Write-ahead records of commits, allowing to reconcile commits interrupted by a crash.
+//!
+//! Before the log entry of a commit is written, a [`CommitIntent`] is recorded in the
+//! configured [`CommitIntentStore`], and it is cleared once the commit is finalized. Intents
+//! still pending after a crash can be resolved with [`reconcile_commit_intents`], which
+//! determines whether the commit made it into the log.
+
+use std::fmt::Debug;
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::Error as ObjectStoreError;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::logstore::{LogStoreRef, ObjectStoreRef};
+use crate::{DeltaResult, DeltaTableError};
+
+const DEFAULT_INTENT_PREFIX: &str = "_delta_intents";
+
+/// Record of a commit which is about to be written to the log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitIntent {
+    /// Id of the operation performing the commit
+    pub operation_id: Uuid,
+    /// The table version the commit was based on, `None` for commits creating the table.
+    ///
+    /// The commit will be written as a later version.
+    pub read_version: Option<i64>,
+    /// Number of actions in the commit
+    pub num_actions: usize,
+    /// Size of the log entry in bytes
+    pub size: usize,
+    /// Checksum of the log entry
+    pub checksum: u64,
+}
+
+impl CommitIntent {
+    pub(crate) fn new(
+        operation_id: Uuid,
+        read_version: Option<i64>,
+        num_actions: usize,
+        log_entry: &[u8],
+    ) -> Self {
+        Self {
+            operation_id,
+            read_version,
+            num_actions,
+            size: log_entry.len(),
+            checksum: log_entry_checksum(log_entry),
+        }
+    }
+
+    /// Whether the given log entry is the one written by the commit
+    pub fn matches(&self, log_entry: &[u8]) -> bool {
+        log_entry.len() == self.size && log_entry_checksum(log_entry) == self.checksum
+    }
+}
+
+/// Offset basis of the 64 bit FNV-1a hash
+pub(crate) const FNV1A_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// Continue a 64 bit FNV-1a hash with the given bytes. The hash is stable across processes
+/// and releases, unlike the hashers of the standard library.
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Checksum of the log entry
+fn log_entry_checksum(log_entry: &[u8]) -> u64 {
+    fnv1a(FNV1A_OFFSET_BASIS, log_entry)
+}
+
+/// Durable storage for the intents of in-flight commits
+#[async_trait::async_trait]
+pub trait CommitIntentStore: Debug + Send + Sync {
+    /// Persist the intent before the log entry of the commit is written
+    async fn record(&self, intent: &CommitIntent) -> DeltaResult<()>;
+
+    /// Remove the intent of a finalized commit
+    async fn clear(&self, operation_id: Uuid) -> DeltaResult<()>;
+
+    /// All intents which have not been cleared yet
+    async fn pending(&self) -> DeltaResult<Vec<CommitIntent>>;
+}
+
+/// Intent store which does not record anything, i.e. commits can not be reconciled
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopCommitIntentStore;
+
+#[async_trait::async_trait]
+impl CommitIntentStore for NoopCommitIntentStore {
+    async fn record(&self, _intent: &CommitIntent) -> DeltaResult<()> {
+        Ok(())
+    }
+
+    async fn clear(&self, _operation_id: Uuid) -> DeltaResult<()> {
+        Ok(())
+    }
+
+    async fn pending(&self) -> DeltaResult<Vec<CommitIntent>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Intent store keeping one json file per intent in an object store.
+///
+/// By default the files are written to the `_delta_intents` directory, which is ignored by
+/// readers and vacuum like all directories starting with an underscore.
+#[derive(Debug, Clone)]
+pub struct ObjectStoreCommitIntentStore {
+    store: ObjectStoreRef,
+    prefix: Path,
+}
+
+impl ObjectStoreCommitIntentStore {
+    /// Store intents in the `_delta_intents` directory of the given store, usually the object
+    /// store of the table
+    pub fn new(store: ObjectStoreRef) -> Self {
+        Self {
+            store,
+            prefix: Path::from(DEFAULT_INTENT_PREFIX),
+        }
+    }
+
+    /// Store intents under a custom prefix
+    pub fn with_prefix(mut self, prefix: Path) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    fn intent_path(&self, operation_id: Uuid) -> Path {
+        self.prefix.child(format!("{operation_id}.json"))
+    }
+}
+
+#[async_trait::async_trait]
+impl CommitIntentStore for ObjectStoreCommitIntentStore {
+    async fn record(&self, intent: &CommitIntent) -> DeltaResult<()> {
+        let bytes = Bytes::from(serde_json::to_vec(intent)?);
+        self.store
+            .put(&self.intent_path(intent.operation_id), bytes.into())
+            .await?;
+        Ok(())
+    }
+
+    async fn clear(&self, operation_id: Uuid) -> DeltaResult<()> {
+        match self.store.delete(&self.intent_path(operation_id)).await {
+            Ok(()) | Err(ObjectStoreError::NotFound { .. }) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn pending(&self) -> DeltaResult<Vec<CommitIntent>> {
+        let metas: Vec<_> = self.store.list(Some(&self.prefix)).try_collect().await?;
+        let mut intents = Vec::with_capacity(metas.len());
+        for meta in metas {
+            let bytes = self.store.get(&meta.location).await?.bytes().await?;
+            intents.push(serde_json::from_slice(&bytes)?);
+        }
+        Ok(intents)
+    }
+}
+
+/// Whether the commit of a pending [`CommitIntent`] made it into the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntentResolution {
+    /// The commit was written as the given version
+    Committed(i64),
+    /// The commit never made it into the log and has to be retried by the application
+    NotCommitted,
+    /// Some of the commits following the read version of the intent were already removed
+    /// by the log cleanup, so it can not be determined whether the commit made it
+    Unknown,
+}
+
+/// Outcome of reconciling a pending [`CommitIntent`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconciledIntent {
+    /// The pending intent
+    pub intent: CommitIntent,
+    /// Whether the commit made it into the log
+    pub resolution: IntentResolution,
+}
+
+/// Resolve all pending intents of the store against the log of the table.
+///
+/// For every intent, the commits following its read version are searched for the log entry
+/// of the intent. Intents are cleared once reconciled, so this should only be called when no
+/// commits recording intents in the store are in flight, e.g. when a pipeline restarts.
+pub async fn reconcile_commit_intents(
+    log_store: &LogStoreRef,
+    intent_store: &dyn CommitIntentStore,
+) -> DeltaResult<Vec<ReconciledIntent>> {
+    let pending = intent_store.pending().await?;
+    let mut reconciled = Vec::with_capacity(pending.len());
+    for intent in pending {
+        let first_version = intent.read_version.map_or(0, |version| version + 1);
+        let latest_version = match log_store
+            .get_latest_version(intent.read_version.unwrap_or(0))
+            .await
+        {
+            Ok(version) => version,
+            // the table was never created
+            Err(DeltaTableError::NotATable(_)) => -1,
+            Err(err) => return Err(err),
+        };
+        let mut resolution = IntentResolution::NotCommitted;
+        let mut missing_entries = false;
+        for version in first_version..=latest_version {
+            match log_store.read_commit_entry(version).await? {
+                Some(log_entry) if intent.matches(&log_entry) => {
+                    resolution = IntentResolution::Committed(version);
+                    break;
+                }
+                Some(_) => {}
+                None => missing_entries = true,
+            }
+        }
+        // commits removed by the log cleanup can not be checked
+        if resolution == IntentResolution::NotCommitted
+            && missing_entries
+            && log_store.get_earliest_version(latest_version).await? > first_version
+        {
+            resolution = IntentResolution::Unknown;
+        }
+        debug!(
+            "Reconciled commit intent of operation {}: {resolution:?}",
+            intent.operation_id
+        );
+        intent_store.clear(intent.operation_id).await?;
+        reconciled.push(ReconciledIntent { intent, resolution });
+    }
+    Ok(reconciled)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::kernel::transaction::{CommitBuilder, CommitProperties};
+    use crate::kernel::{DataType, PrimitiveType};
+    use crate::protocol::{DeltaOperation, SaveMode};
+
+    #[tokio::test]
+    async fn test_reconcile_commit_intents() {
+        let table = crate::DeltaOps::new_in_memory()
+            .create()
+            .with_column(
+                "value",
+                DataType::Primitive(PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        let log_store = table.log_store();
+        let intent_store = Arc::new(ObjectStoreCommitIntentStore::new(
+            log_store.object_store(None),
+        ));
+        let operation = DeltaOperation::Write {
+            mode: SaveMode::Append,
+            partition_by: None,
+            predicate: None,
+        };
+        // distinguish the commits, which would otherwise be identical
+        let builder = |commit: &str| {
+            CommitBuilder::from(
+                CommitProperties::default()
+                    .with_metadata([("commit".to_string(), commit.into())])
+                    .with_commit_intent_store(intent_store.clone()),
+            )
+        };
+
+        // finalized commits clear their intent
+        let finalized = builder("finalized")
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 1);
+        assert!(intent_store.pending().await.unwrap().is_empty());
+
+        // crash before the log entry was written
+        let prepared = builder("crashed before write")
+            .build(
+                Some(&finalized.snapshot()),
+                log_store.clone(),
+                operation.clone(),
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        drop(prepared);
+        // crash after the log entry was written, but before the commit was finalized
+        let post_commit = builder("crashed after write")
+            .build(Some(&finalized.snapshot()), log_store.clone(), operation)
+            .into_prepared_commit_future()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(post_commit.version, 2);
+        drop(post_commit);
+        assert_eq!(intent_store.pending().await.unwrap().len(), 2);
+
+        let mut reconciled = reconcile_commit_intents(&log_store, intent_store.as_ref())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|reconciled| reconciled.resolution)
+            .collect::<Vec<_>>();
+        reconciled.sort_by_key(|resolution| *resolution == IntentResolution::NotCommitted);
+        assert_eq!(
+            reconciled,
+            vec![
+                IntentResolution::Committed(2),
+                IntentResolution::NotCommitted
+            ]
+        );
+        assert!(intent_store.pending().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_cleaned_up_commits() {
+        let table = crate::DeltaOps::new_in_memory()
+            .create()
+            .with_column(
+                "value",
+                DataType::Primitive(PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+        let log_store = table.log_store();
+        let operation = DeltaOperation::Write {
+            mode: SaveMode::Append,
+            partition_by: None,
+            predicate: None,
+        };
+        let mut snapshot = table.snapshot().unwrap().clone();
+        for _ in 0..2 {
+            snapshot = CommitBuilder::default()
+                .build(Some(&snapshot), log_store.clone(), operation.clone())
+                .await
+                .unwrap()
+                .snapshot();
+        }
+        let intent_store = ObjectStoreCommitIntentStore::new(log_store.object_store(None));
+        intent_store
+            .record(&CommitIntent::new(Uuid::new_v4(), Some(0), 1, b"{}"))
+            .await
+            .unwrap();
+
+        // the commit may have been version 1, which is gone
+        for version in 0..2 {
+            log_store
+                .object_store(None)
+                .delete(&crate::logstore::commit_uri_from_version(version))
+                .await
+                .unwrap();
+        }
+        let reconciled = reconcile_commit_intents(&log_store, &intent_store)
+            .await
+            .unwrap();
+        assert_eq!(reconciled.len(), 1);
+        assert_eq!(reconciled[0].resolution, IntentResolution::Unknown);
+    }
+
+    #[test]
+    fn test_commit_intent_matches() {
+        let intent = CommitIntent::new(Uuid::new_v4(), Some(0), 1, b"{\"add\":{}}");
+        assert!(intent.matches(b"{\"add\":{}}"));
+        assert!(!intent.matches(b"{\"add\":{ }"));
+        assert!(!intent.matches(b"{\"remove\":{}}"));
+    }
+}
@@ -73,52 +73,112 @@
 //!       │                               │
 //!       └───────────────────────────────┘
 //!</pre>
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 use bytes::Bytes;
 use chrono::Utc;
 use conflict_checker::ConflictChecker;
 use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::StreamExt;
 use object_store::path::Path;
 use object_store::Error as ObjectStoreError;
 use serde_json::Value;
+use tokio::time::Instant;
 use tracing::*;
 use uuid::Uuid;
 
+use self::conflict_checker::{only_adds_files, TransactionInfo};
 use delta_kernel::table_features::{ReaderFeature, WriterFeature};
 use serde::{Deserialize, Serialize};
 
-use self::conflict_checker::{TransactionInfo, WinningCommitSummary};
 use crate::checkpoints::{cleanup_expired_logs_for, create_checkpoint_for};
 use crate::errors::DeltaTableError;
-use crate::kernel::{Action, CommitInfo, EagerSnapshot, Metadata, Protocol, Transaction};
+use crate::kernel::{Action, Add, CommitInfo, EagerSnapshot, Metadata, Protocol, Transaction};
 use crate::logstore::ObjectStoreRef;
-use crate::logstore::{CommitOrBytes, LogStoreRef};
+use crate::logstore::{commit_uri_from_version, get_actions, CommitOrBytes, LogStoreRef};
 use crate::operations::CustomExecuteHandler;
-use crate::protocol::DeltaOperation;
+use crate::protocol::{DeltaOperation, SaveMode};
 use crate::table::config::TableConfig;
 use crate::table::state::DeltaTableState;
-use crate::{crate_version, DeltaResult};
+use crate::{crate_version, DeltaResult, PartitionFilter};
 
-pub use self::conflict_checker::CommitConflictError;
+pub use self::checksum::{read_version_checksum, write_version_checksum, VersionChecksum};
+pub use self::conflict_checker::{
+    BlindAppendConflictResolution, CommitConflictError, ConflictDetails, ConflictResolution,
+    DefaultConflictResolution, PendingTransaction, WinningCommitSummary,
+};
+pub(crate) use self::intent::{fnv1a, FNV1A_OFFSET_BASIS};
+pub use self::intent::{
+    reconcile_commit_intents, CommitIntent, CommitIntentStore, IntentResolution,
+    NoopCommitIntentStore, ObjectStoreCommitIntentStore, ReconciledIntent,
+};
 pub use self::protocol::INSTANCE as PROTOCOL;
 
 #[cfg(test)]
 pub(crate) mod application;
+mod checksum;
 mod conflict_checker;
+mod intent;
 mod protocol;
 #[cfg(feature = "datafusion")]
 pub mod state;
 
 const DELTA_LOG_FOLDER: &str = "_delta_log";
 pub(crate) const DEFAULT_RETRIES: usize = 15;
+const DEFAULT_CHECKPOINT_RETRIES: usize = 3;
+const DEFAULT_CONFLICT_CHECK_CONCURRENCY: usize = 8;
+/// Number of streamed actions validated against the table protocol at once
+const ACTION_STREAM_CHUNK_SIZE: usize = 1024;
+const CHECKPOINT_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+const CHECKPOINT_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Delay before retrying a failed checkpoint, doubling with every attempt up to a cap
+fn checkpoint_retry_backoff(attempt: u32) -> Duration {
+    CHECKPOINT_RETRY_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(CHECKPOINT_RETRY_MAX_BACKOFF)
+}
+
+/// Number of commit retries used unless configured otherwise, can be overridden through
+/// the `DELTARS_MAX_COMMIT_RETRIES` environment variable.
+pub(crate) fn default_max_retries() -> usize {
+    static MAX_RETRIES: OnceLock<usize> = OnceLock::new();
+    *MAX_RETRIES.get_or_init(|| max_retries_from_env("DELTARS_MAX_COMMIT_RETRIES"))
+}
+
+fn max_retries_from_env(var: &str) -> usize {
+    std::env::var(var)
+        .ok()
+        .and_then(|s| match s.parse::<usize>() {
+            Ok(max_retries) => Some(max_retries),
+            Err(err) => {
+                debug!("Invalid {var} {s:?}, falling back on {DEFAULT_RETRIES}: {err}");
+                None
+            }
+        })
+        .unwrap_or(DEFAULT_RETRIES)
+}
 
 #[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CommitMetrics {
     /// Number of retries before a successful commit
     pub num_retries: u64,
+
+    /// Number of actions in the commit, including the commit info
+    pub num_actions: u64,
+
+    /// Size of the serialized commit in bytes
+    pub commit_size_bytes: u64,
+
+    /// Time spent checking for conflicts with concurrent commits, summed across all
+    /// attempts
+    pub conflict_check_duration_ms: u64,
 }
 
 #[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -129,6 +189,12 @@ pub struct PostCommitMetrics {
 
     /// Number of log files cleaned up
     pub num_log_files_cleaned_up: u64,
+
+    /// The error of a checkpoint creation that failed after exhausting all retries
+    pub checkpoint_error: Option<String>,
+
+    /// The error of a failed expired log cleanup
+    pub cleanup_error: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -137,11 +203,193 @@ pub struct Metrics {
     /// Number of retries before a successful commit
     pub num_retries: u64,
 
+    /// Number of actions in the commit, including the commit info
+    pub num_actions: u64,
+
+    /// Size of the serialized commit in bytes
+    pub commit_size_bytes: u64,
+
+    /// Time spent checking for conflicts with concurrent commits, summed across all
+    /// attempts
+    pub conflict_check_duration_ms: u64,
+
     /// Whether a new checkpoint was created as part of this commit
     pub new_checkpoint_created: bool,
 
     /// Number of log files cleaned up
     pub num_log_files_cleaned_up: u64,
+
+    /// The error of a checkpoint creation that failed after exhausting all retries
+    pub checkpoint_error: Option<String>,
+
+    /// The error of a failed expired log cleanup
+    pub cleanup_error: Option<String>,
+
+    /// Whether nothing was written, since the table already contained the app transactions
+    /// of the commit
+    pub already_committed: bool,
+}
+
+/// A stream of actions to be included in a commit
+pub type ActionStream = BoxStream<'static, DeltaResult<Action>>;
+
+/// Function computing additional commit info entries for the committed operation
+pub type CommitInfoFn = Arc<dyn Fn(&DeltaOperation) -> HashMap<String, Value> + Send + Sync>;
+
+/// Keys of the commit info which cannot be set by a [CommitInfoFn]
+const RESERVED_COMMIT_INFO_KEYS: [&str; 10] = [
+    "timestamp",
+    "userId",
+    "userName",
+    "operation",
+    "operationParameters",
+    "readVersion",
+    "isolationLevel",
+    "isBlindAppend",
+    "engineInfo",
+    "clientVersion",
+];
+
+/// Callback invoked with the number of actions serialized so far
+pub type SerializationProgressFn = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// Serializes actions to the lines of a log entry.
+///
+/// Allows emitting actions in the exact form expected by strict readers, e.g. without null
+/// fields or with a stable key order. Every line must be a single line of json which
+/// deserializes to the serialized action, which is verified for every commit.
+pub trait ActionSerializer: std::fmt::Debug + Send + Sync {
+    /// Serialize a single action to a line of the log entry
+    fn serialize(&self, action: &Action) -> Result<String, serde_json::Error>;
+}
+
+/// Serializes actions with their derived [`Serialize`](serde::Serialize) implementation
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultActionSerializer;
+
+impl ActionSerializer for DefaultActionSerializer {
+    fn serialize(&self, action: &Action) -> Result<String, serde_json::Error> {
+        serde_json::to_string(action)
+    }
+}
+
+/// Callback invoked for every attempt to write a commit entry, see
+/// [`CommitBuilder::with_attempt_observer`]
+pub type AttemptObserverFn = Arc<dyn Fn(AttemptInfo) + Send + Sync>;
+
+/// Callback invoked with every finalized commit, see
+/// [`CommitBuilder::with_finalized_observer`]
+pub type FinalizedObserverFn = Arc<dyn Fn(&FinalizedCommit) + Send + Sync>;
+
+/// Result of an attempt to write a commit entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptResult {
+    /// The commit entry was written
+    Ok,
+    /// Another commit already exists at the target version
+    VersionConflict,
+    /// Writing the commit entry failed for any other reason
+    OtherError,
+}
+
+/// Details of an attempt to write a commit entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttemptInfo {
+    /// Number of the attempt, starting at 1
+    pub attempt: usize,
+    /// The table version the commit entry was written for
+    pub version: i64,
+    /// The result of the attempt
+    pub result: AttemptResult,
+}
+
+/// Running tally over the actions consumed from an [`ActionStream`]
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionTally {
+    /// Number of actions consumed, not part of the recorded `operationMetrics`
+    #[serde(skip)]
+    pub num_actions: u64,
+
+    /// Number of files added
+    pub num_added_files: u64,
+
+    /// Number of files removed
+    pub num_removed_files: u64,
+
+    /// Size in bytes of the files added
+    pub num_added_bytes: i64,
+
+    /// Size in bytes of the files removed
+    pub num_removed_bytes: i64,
+
+    /// Number of change data files added, not part of the recorded `operationMetrics`
+    #[serde(skip)]
+    pub num_cdc_files: u64,
+}
+
+impl ActionTally {
+    /// Record a single action in the tally
+    pub fn record(&mut self, action: &Action) {
+        self.num_actions += 1;
+        match action {
+            Action::Add(add) => {
+                self.num_added_files += 1;
+                self.num_added_bytes += add.size;
+            }
+            Action::Remove(remove) => {
+                self.num_removed_files += 1;
+                self.num_removed_bytes += remove.size.unwrap_or_default();
+            }
+            Action::Cdc(_) => self.num_cdc_files += 1,
+            _ => {}
+        }
+    }
+
+    /// Whether the recorded actions only add data, without removing or changing any rows
+    pub fn is_blind_append(&self) -> bool {
+        self.num_removed_files == 0 && self.num_cdc_files == 0
+    }
+}
+
+/// Summary of the files added and removed by a commit, see [`FinalizedCommit::commit_stats`]
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitStats {
+    /// Number of files added
+    pub num_added_files: u64,
+
+    /// Number of files removed
+    pub num_removed_files: u64,
+
+    /// Number of rows in the added files, only counting files with statistics
+    pub num_added_rows: u64,
+}
+
+impl CommitStats {
+    /// Record a single committed action
+    pub fn record(&mut self, action: &Action) {
+        match action {
+            Action::Add(add) => {
+                self.num_added_files += 1;
+                self.num_added_rows += add_num_records(add).unwrap_or_default();
+            }
+            Action::Remove(_) => self.num_removed_files += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Number of records from the json statistics of the file, without parsing the column stats
+fn add_num_records(add: &Add) -> Option<u64> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct NumRecords {
+        num_records: u64,
+    }
+    serde_json::from_str::<NumRecords>(add.stats.as_deref()?)
+        .ok()
+        .map(|stats| stats.num_records)
 }
 
 /// Error raised while commititng transaction
@@ -167,13 +415,33 @@ pub enum TransactionError {
     },
 
     /// Error returned when a commit conflict occurred
-    #[error("Failed to commit transaction: {0}")]
-    CommitConflict(#[from] CommitConflictError),
+    #[error("Failed to commit transaction: {source}")]
+    CommitConflict {
+        /// The detected conflict
+        source: CommitConflictError,
+        /// Details about the winning commit the transaction conflicted with
+        details: Box<ConflictDetails>,
+    },
 
     /// Error returned when maximum number of commit trioals is exceeded
     #[error("Failed to commit transaction: {0}")]
     MaxCommitAttempts(i32),
 
+    /// Error returned when the retry backoff stops retrying after its maximum elapsed time
+    #[error(
+        "Failed to commit transaction, stopped retrying after {elapsed:?} and {attempts} attempts"
+    )]
+    MaxElapsedExceeded {
+        /// Time spent on the commit
+        elapsed: Duration,
+        /// Number of attempts to write the commit entry
+        attempts: usize,
+    },
+
+    /// Error returned when the maximum number of conflict resolutions is exceeded
+    #[error("Failed to commit transaction after resolving conflicts {0} times")]
+    MaxConflictResolutions(usize),
+
     /// The transaction includes Remove action with data change but Delta table is append-only
     #[error(
         "The transaction includes Remove action with data change but Delta table is append-only"
@@ -196,6 +464,108 @@ pub enum TransactionError {
     #[error("Reader features must be specified for reader version >= 3, please specify: {0:?}")]
     ReaderFeaturesRequired(ReaderFeature),
 
+    /// Error returned when the table protocol does not match the expected protocol
+    #[error("Table protocol does not match the expected protocol, expected: {expected:?}, found: {found:?}")]
+    ProtocolMismatch {
+        /// The protocol the commit was prepared for
+        expected: Box<Protocol>,
+        /// The protocol of the table
+        found: Box<Protocol>,
+    },
+
+    /// Error returned when the configuration of a metadata domain does not match the expected value
+    #[error("Precondition on domain {domain} failed, expected: {expected:?}, found: {found:?}")]
+    DomainPreconditionFailed {
+        /// The metadata domain
+        domain: String,
+        /// The expected configuration of the domain
+        expected: Option<String>,
+        /// The current configuration of the domain
+        found: Option<String>,
+    },
+
+    /// Error returned when the timestamp of the latest commit does not satisfy the precondition
+    #[error("Precondition on the timestamp of commit {version} failed, found: {timestamp:?}")]
+    CommitTimestampPreconditionFailed {
+        /// The version of the latest commit
+        version: i64,
+        /// The timestamp of the latest commit, if recorded
+        timestamp: Option<i64>,
+    },
+
+    /// Error returned when a single serialized action exceeds the configured size limit
+    #[error("Action at index {action_index} is too large to commit: {size} bytes")]
+    ActionTooLarge {
+        /// Index of the action within the commit
+        action_index: usize,
+        /// Serialized size of the action in bytes
+        size: usize,
+    },
+
+    /// Error returned when the serialized commit info exceeds the configured size limit
+    #[error("Commit info is too large: {size} bytes, limit: {limit} bytes")]
+    CommitInfoTooLarge {
+        /// Serialized size of the commit info in bytes
+        size: usize,
+        /// The configured limit in bytes
+        limit: usize,
+    },
+
+    /// Error returned when the commit removes files which are not active in the table
+    #[error("Commit removes files which are not part of table version {version}: {paths:?}")]
+    UnknownRemovedFiles {
+        /// The table version the commit was checked against
+        version: i64,
+        /// Paths of the removed files missing from the table
+        paths: Vec<String>,
+    },
+
+    /// Error returned when a custom [`ActionSerializer`] produced a line which does not
+    /// deserialize to the serialized action
+    #[error("Serialized action at index {action_index} does not round-trip: {line}")]
+    InvalidSerializedAction {
+        /// Index of the action within the commit
+        action_index: usize,
+        /// The serialized line
+        line: String,
+    },
+
+    /// Error returned when a commit written to the primary log store could not be written to
+    /// the secondary log store
+    #[error(
+        "Commit was written as version {version} to the primary log store, but writing it to the secondary log store failed: {source}"
+    )]
+    SecondaryCommitFailed {
+        /// The version written to the primary log store
+        version: i64,
+        /// The error of the secondary log store
+        source: Box<TransactionError>,
+    },
+
+    /// Error returned when a commit failed and its temporary commit file was kept for
+    /// inspection, see [`CommitBuilder::with_preserve_tmp_on_error`]
+    #[error("{source} (temporary commit preserved at {path})")]
+    TmpCommitPreserved {
+        /// Location of the temporary commit file
+        path: Path,
+        /// The error the commit failed with
+        source: Box<TransactionError>,
+    },
+
+    /// Error returned when a commit has no actions at all, which would write an empty log
+    /// entry. Commits only recording app transactions or a commit info are not empty.
+    #[error("Cannot commit a transaction without any actions")]
+    EmptyCommit,
+
+    /// Error returned when the commit did not complete within the configured timeout
+    #[error("Commit timed out after {elapsed:?} and {attempts} attempts")]
+    Timeout {
+        /// Time spent on the commit
+        elapsed: Duration,
+        /// Number of attempts to write the commit entry
+        attempts: usize,
+    },
+
     /// The transaction failed to commit due to an error in an implementation-specific layer.
     /// Currently used by DynamoDb-backed S3 log store when database operations fail.
     #[error("Transaction failed: {msg}")]
@@ -207,6 +577,25 @@ pub enum TransactionError {
     },
 }
 
+impl TransactionError {
+    /// Details about the winning commit, if the transaction failed due to a commit conflict
+    pub fn conflict_details(&self) -> Option<&ConflictDetails> {
+        match self {
+            Self::CommitConflict { details, .. } => Some(details),
+            _ => None,
+        }
+    }
+}
+
+impl From<CommitConflictError> for TransactionError {
+    fn from(source: CommitConflictError) -> Self {
+        Self::CommitConflict {
+            source,
+            details: Default::default(),
+        }
+    }
+}
+
 impl From<TransactionError> for DeltaTableError {
     fn from(err: TransactionError) -> Self {
         match err {
@@ -296,23 +685,73 @@ pub struct CommitData {
     pub app_transactions: Vec<Transaction>,
 }
 
+/// A commit is a blind append if it only adds files and does not depend on the existing
+/// data of the table, i.e. appending writes which did not read the table.
+fn is_blind_append(actions: &[Action], operation: &DeltaOperation) -> bool {
+    operation.is_blind_append()
+        && !actions
+            .iter()
+            .any(|action| matches!(action, Action::Remove(_) | Action::Cdc(_)))
+}
+
+/// How to handle a `clientVersion` supplied in the app metadata of a commit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClientVersionPolicy {
+    /// Record both, as `<user value> (delta-rs.<version>)`
+    Combine,
+    /// Keep the user supplied value
+    Respect,
+    /// Replace the user supplied value with the delta-rs version
+    #[default]
+    Overwrite,
+}
+
 impl CommitData {
     /// Create new data to be committed
     pub fn new(
+        actions: Vec<Action>,
+        operation: DeltaOperation,
+        app_metadata: HashMap<String, Value>,
+        app_transactions: Vec<Transaction>,
+    ) -> Self {
+        Self::new_with_client_version_policy(
+            actions,
+            operation,
+            app_metadata,
+            app_transactions,
+            ClientVersionPolicy::default(),
+        )
+    }
+
+    /// Create new data to be committed, resolving a `clientVersion` present in the
+    /// app metadata according to the given policy.
+    ///
+    /// Without a user supplied `clientVersion`, the delta-rs version is always recorded.
+    pub fn new_with_client_version_policy(
         mut actions: Vec<Action>,
         operation: DeltaOperation,
         mut app_metadata: HashMap<String, Value>,
         app_transactions: Vec<Transaction>,
+        client_version_policy: ClientVersionPolicy,
     ) -> Self {
         if !actions.iter().any(|a| matches!(a, Action::CommitInfo(..))) {
             let mut commit_info = operation.get_commit_info();
             commit_info.timestamp = Some(Utc::now().timestamp_millis());
-            app_metadata.insert(
-                "clientVersion".to_string(),
-                Value::String(format!("delta-rs.{}", crate_version())),
-            );
+            let delta_rs_version = format!("delta-rs.{}", crate_version());
+            let client_version = match (app_metadata.get("clientVersion"), client_version_policy) {
+                (Some(user_version), ClientVersionPolicy::Respect) => user_version.clone(),
+                (Some(user_version), ClientVersionPolicy::Combine) => {
+                    let user_version = user_version
+                        .as_str()
+                        .map_or_else(|| user_version.to_string(), str::to_string);
+                    Value::String(format!("{user_version} ({delta_rs_version})"))
+                }
+                _ => Value::String(delta_rs_version),
+            };
+            app_metadata.insert("clientVersion".to_string(), client_version);
             app_metadata.extend(commit_info.info);
             commit_info.info = app_metadata.clone();
+            commit_info.is_blind_append = Some(is_blind_append(&actions, &operation));
             actions.push(Action::CommitInfo(commit_info))
         }
 
@@ -328,651 +767,5442 @@ impl CommitData {
         }
     }
 
-    /// Obtain the byte representation of the commit.
-    pub fn get_bytes(&self) -> Result<bytes::Bytes, TransactionError> {
-        let mut jsons = Vec::<String>::new();
-        for action in &self.actions {
-            let json = serde_json::to_string(action)
-                .map_err(|e| TransactionError::SerializeLogJson { json_err: e })?;
-            jsons.push(json);
+    /// Create new data to be committed without adding a commit info action.
+    ///
+    /// The actions are committed as given apart from the app transactions, so the commit
+    /// only has a commit info if one is passed with the actions.
+    pub fn new_without_commit_info(
+        mut actions: Vec<Action>,
+        operation: DeltaOperation,
+        app_metadata: HashMap<String, Value>,
+        app_transactions: Vec<Transaction>,
+    ) -> Self {
+        for txn in &app_transactions {
+            actions.push(Action::Txn(txn.clone()))
         }
-        Ok(bytes::Bytes::from(jsons.join("\n")))
-    }
-}
-
-#[derive(Clone, Debug, Copy)]
-/// Properties for post commit hook.
-pub struct PostCommitHookProperties {
-    create_checkpoint: bool,
-    /// Override the EnableExpiredLogCleanUp setting, if None config setting is used
-    cleanup_expired_logs: Option<bool>,
-}
-
-#[derive(Clone, Debug)]
-/// End user facing interface to be used by operations on the table.
-/// Enable controlling commit behaviour and modifying metadata that is written during a commit.
-pub struct CommitProperties {
-    pub(crate) app_metadata: HashMap<String, Value>,
-    pub(crate) app_transaction: Vec<Transaction>,
-    max_retries: usize,
-    create_checkpoint: bool,
-    cleanup_expired_logs: Option<bool>,
-}
 
-impl Default for CommitProperties {
-    fn default() -> Self {
-        Self {
-            app_metadata: Default::default(),
-            app_transaction: Vec::new(),
-            max_retries: DEFAULT_RETRIES,
-            create_checkpoint: true,
-            cleanup_expired_logs: None,
+        CommitData {
+            actions,
+            operation,
+            app_metadata,
+            app_transactions,
         }
     }
-}
 
-impl CommitProperties {
-    /// Specify metadata the be committed
-    pub fn with_metadata(
-        mut self,
-        metadata: impl IntoIterator<Item = (String, serde_json::Value)>,
-    ) -> Self {
-        self.app_metadata = HashMap::from_iter(metadata);
-        self
+    /// Consume a stream of actions into the commit.
+    ///
+    /// The `operationMetrics` of the commit info are populated from the running tally
+    /// over the consumed actions, unless they were already provided by the caller.
+    ///
+    /// The consumed actions are kept in the commit data. Use
+    /// [`CommitBuilder::with_action_stream`] to serialize them into the log entry as they
+    /// are consumed instead.
+    pub async fn extend_from_stream(
+        &mut self,
+        mut stream: ActionStream,
+    ) -> DeltaResult<ActionTally> {
+        let mut tally = ActionTally::default();
+        while let Some(action) = stream.next().await {
+            let action = action?;
+            tally.record(&action);
+            self.actions.push(action);
+        }
+        self.record_stream_tally(&tally)?;
+        Ok(tally)
     }
 
-    /// Specify maximum number of times to retry the transaction before failing to commit
-    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
-        self.max_retries = max_retries;
-        self
-    }
+    /// Record the outcome of consuming an action stream in the commit info
+    fn record_stream_tally(&mut self, tally: &ActionTally) -> Result<(), TransactionError> {
+        if !tally.is_blind_append() {
+            self.set_blind_append(false);
+        }
 
-    /// Specify if it should create a checkpoint when the commit interval condition is met
-    pub fn with_create_checkpoint(mut self, create_checkpoint: bool) -> Self {
-        self.create_checkpoint = create_checkpoint;
-        self
+        if !self.app_metadata.contains_key("operationMetrics") {
+            let metrics = serde_json::to_value(tally)
+                .map_err(|err| TransactionError::SerializeLogJson { json_err: err })?;
+            for action in self.actions.iter_mut() {
+                if let Action::CommitInfo(commit_info) = action {
+                    commit_info
+                        .info
+                        .insert("operationMetrics".to_string(), metrics.clone());
+                }
+            }
+            self.app_metadata
+                .insert("operationMetrics".to_string(), metrics);
+        }
+        Ok(())
     }
 
-    /// Add an additional application transaction to the commit
-    pub fn with_application_transaction(mut self, txn: Transaction) -> Self {
-        self.app_transaction.push(txn);
-        self
+    /// Fail if the serialized commit info is larger than `limit` bytes
+    pub fn check_commit_info_size(&self, limit: usize) -> Result<(), TransactionError> {
+        for action in &self.actions {
+            if let Action::CommitInfo(commit_info) = action {
+                let size = serde_json::to_vec(commit_info)
+                    .map_err(|json_err| TransactionError::SerializeLogJson { json_err })?
+                    .len();
+                if size > limit {
+                    return Err(TransactionError::CommitInfoTooLarge { size, limit });
+                }
+            }
+        }
+        Ok(())
     }
 
-    /// Override application transactions for the commit
-    pub fn with_application_transactions(mut self, txn: Vec<Transaction>) -> Self {
-        self.app_transaction = txn;
-        self
+    /// Record the engine embedding delta-rs in the commit info.
+    ///
+    /// The engine is set as `engineInfo` and prepended to the delta-rs version in the
+    /// `clientVersion`, e.g. `myengine/1.2.3 (delta-rs.0.26.0)`.
+    pub fn set_engine_info(&mut self, engine_info: &str) {
+        let delta_rs_version = format!("delta-rs.{}", crate_version());
+        let with_engine = |value: &mut Value| {
+            if let Value::String(client_version) = value {
+                *client_version = client_version.replace(
+                    &delta_rs_version,
+                    &format!("{engine_info} ({delta_rs_version})"),
+                );
+            }
+        };
+        if let Some(client_version) = self.app_metadata.get_mut("clientVersion") {
+            with_engine(client_version);
+        }
+        for action in self.actions.iter_mut() {
+            if let Action::CommitInfo(commit_info) = action {
+                commit_info.engine_info = Some(engine_info.to_string());
+                if let Some(client_version) = commit_info.info.get_mut("clientVersion") {
+                    with_engine(client_version);
+                }
+            }
+        }
     }
 
-    /// Specify if it should clean up the logs when the logRetentionDuration interval is met
-    pub fn with_cleanup_expired_logs(mut self, cleanup_expired_logs: Option<bool>) -> Self {
-        self.cleanup_expired_logs = cleanup_expired_logs;
-        self
+    /// Merge additional entries into the commit info.
+    ///
+    /// Entries already present, e.g. from the app metadata, and keys reserved for the
+    /// commit info itself like `timestamp` or `clientVersion` are not overridden.
+    pub fn extend_commit_info(&mut self, info: HashMap<String, Value>) {
+        let info: HashMap<_, _> = info
+            .into_iter()
+            .filter(|(key, _)| {
+                !RESERVED_COMMIT_INFO_KEYS.contains(&key.as_str())
+                    && !self.app_metadata.contains_key(key)
+            })
+            .collect();
+        for action in self.actions.iter_mut() {
+            if let Action::CommitInfo(commit_info) = action {
+                for (key, value) in info.iter() {
+                    commit_info
+                        .info
+                        .entry(key.clone())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+        self.app_metadata.extend(info);
     }
-}
 
-impl From<CommitProperties> for CommitBuilder {
-    fn from(value: CommitProperties) -> Self {
-        CommitBuilder {
-            max_retries: value.max_retries,
-            app_metadata: value.app_metadata,
-            post_commit_hook: Some(PostCommitHookProperties {
-                create_checkpoint: value.create_checkpoint,
-                cleanup_expired_logs: value.cleanup_expired_logs,
-            }),
-            app_transaction: value.app_transaction,
-            ..Default::default()
+    /// Record whether the commit is a blind append in the commit info, overriding the
+    /// value inferred from the actions.
+    pub fn set_blind_append(&mut self, blind_append: bool) {
+        for action in self.actions.iter_mut() {
+            if let Action::CommitInfo(commit_info) = action {
+                commit_info.is_blind_append = Some(blind_append);
+            }
         }
     }
-}
 
-/// Prepare data to be committed to the Delta log and control how the commit is performed
-pub struct CommitBuilder {
-    actions: Vec<Action>,
-    app_metadata: HashMap<String, Value>,
-    app_transaction: Vec<Transaction>,
-    max_retries: usize,
-    post_commit_hook: Option<PostCommitHookProperties>,
-    post_commit_hook_handler: Option<Arc<dyn CustomExecuteHandler>>,
-    operation_id: Uuid,
-}
+    /// Record the given timestamp in milliseconds since the epoch in the commit info,
+    /// instead of the time the commit data was created
+    pub fn set_commit_timestamp(&mut self, timestamp: i64) {
+        for action in self.actions.iter_mut() {
+            if let Action::CommitInfo(commit_info) = action {
+                commit_info.timestamp = Some(timestamp);
+            }
+        }
+    }
 
-impl Default for CommitBuilder {
-    fn default() -> Self {
-        CommitBuilder {
-            actions: Vec::new(),
-            app_metadata: HashMap::new(),
-            app_transaction: Vec::new(),
-            max_retries: DEFAULT_RETRIES,
-            post_commit_hook: None,
-            post_commit_hook_handler: None,
-            operation_id: Uuid::new_v4(),
+    /// Record the given predicate in the `operationParameters` of the commit info
+    pub fn set_operation_predicate(&mut self, predicate: String) {
+        for action in self.actions.iter_mut() {
+            if let Action::CommitInfo(commit_info) = action {
+                commit_info
+                    .operation_parameters
+                    .get_or_insert_with(HashMap::new)
+                    .insert("predicate".to_string(), Value::String(predicate.clone()));
+            }
         }
     }
-}
 
-impl<'a> CommitBuilder {
-    /// Actions to be included in the commit
-    pub fn with_actions(mut self, actions: Vec<Action>) -> Self {
-        self.actions = actions;
-        self
+    /// Obtain the byte representation of the commit.
+    pub fn get_bytes(&self) -> Result<bytes::Bytes, TransactionError> {
+        self.get_bytes_with_progress(None)
     }
 
-    /// Metadata for the operation performed like metrics, user, and notebook
-    pub fn with_app_metadata(mut self, app_metadata: HashMap<String, Value>) -> Self {
-        self.app_metadata = app_metadata;
-        self
+    /// Obtain the byte representation of the commit, reporting the number of serialized
+    /// actions to the callback every `interval` actions.
+    pub fn get_bytes_with_progress(
+        &self,
+        progress: Option<&(usize, SerializationProgressFn)>,
+    ) -> Result<bytes::Bytes, TransactionError> {
+        self.serialize_actions(progress, None, None)
     }
 
-    /// Maximum number of times to retry the transaction before failing to commit
-    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
-        self.max_retries = max_retries;
-        self
+    /// Obtain the byte representation of the commit using a custom serializer
+    pub fn get_bytes_with_serializer(
+        &self,
+        serializer: &dyn ActionSerializer,
+    ) -> Result<bytes::Bytes, TransactionError> {
+        self.serialize_actions(None, None, Some(serializer))
     }
 
-    /// Specify all the post commit hook properties
-    pub fn with_post_commit_hook(mut self, post_commit_hook: PostCommitHookProperties) -> Self {
-        self.post_commit_hook = Some(post_commit_hook);
-        self
+    /// Size of the log entry of the commit in bytes, as returned by
+    /// [`get_bytes`](Self::get_bytes).
+    ///
+    /// The actions are serialized one at a time without buffering the log entry, so this
+    /// is cheap enough to decide whether a large commit has to be split before writing it.
+    /// Fails like [`get_bytes`](Self::get_bytes) if an action can not be serialized.
+    pub fn estimated_size(&self) -> DeltaResult<usize> {
+        let mut counter = ByteCounter::default();
+        for action in &self.actions {
+            serde_json::to_writer(&mut counter, action)
+                .map_err(|json_err| TransactionError::SerializeLogJson { json_err })?;
+        }
+        // the actions are separated by newlines
+        Ok(counter.0 + self.actions.len().saturating_sub(1))
     }
 
-    /// Propagate operation id to log store
-    pub fn with_operation_id(mut self, operation_id: Uuid) -> Self {
-        self.operation_id = operation_id;
-        self
+    /// Serialize the actions of the commit, failing if any single action is larger than
+    /// `max_action_size` bytes.
+    ///
+    /// The output of a custom serializer is verified to deserialize to the original actions.
+    pub(crate) fn serialize_actions(
+        &self,
+        progress: Option<&(usize, SerializationProgressFn)>,
+        max_action_size: Option<usize>,
+        serializer: Option<&dyn ActionSerializer>,
+    ) -> Result<bytes::Bytes, TransactionError> {
+        let mut writer = LogEntryWriter::new(progress, max_action_size, serializer);
+        for action in &self.actions {
+            writer.push(action)?;
+        }
+        Ok(writer.finish())
+    }
+}
+
+/// Sink counting the bytes written to it
+#[derive(Default)]
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
     }
 
-    /// Set a custom execute handler, for pre and post execution
-    pub fn with_post_commit_hook_handler(
-        mut self,
-        handler: Option<Arc<dyn CustomExecuteHandler>>,
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes actions one at a time into the lines of a log entry
+struct LogEntryWriter<'s> {
+    buffer: Vec<u8>,
+    num_actions: usize,
+    stats: CommitStats,
+    largest_action: usize,
+    progress: Option<&'s (usize, SerializationProgressFn)>,
+    max_action_size: Option<usize>,
+    serializer: Option<&'s dyn ActionSerializer>,
+}
+
+impl<'s> LogEntryWriter<'s> {
+    fn new(
+        progress: Option<&'s (usize, SerializationProgressFn)>,
+        max_action_size: Option<usize>,
+        serializer: Option<&'s dyn ActionSerializer>,
     ) -> Self {
-        self.post_commit_hook_handler = handler;
-        self
+        Self {
+            buffer: Vec::new(),
+            num_actions: 0,
+            stats: CommitStats::default(),
+            largest_action: 0,
+            progress,
+            max_action_size,
+            serializer,
+        }
     }
 
-    /// Prepare a Commit operation using the configured builder
-    pub fn build(
-        self,
-        table_data: Option<&'a dyn TableReference>,
-        log_store: LogStoreRef,
-        operation: DeltaOperation,
-    ) -> PreCommit<'a> {
-        let data = CommitData::new(
-            self.actions,
-            operation,
-            self.app_metadata,
-            self.app_transaction,
+    /// Append the action as the next line of the log entry
+    fn push(&mut self, action: &Action) -> Result<(), TransactionError> {
+        let action_index = self.num_actions;
+        let json = match self.serializer {
+            Some(serializer) => {
+                let json = serializer
+                    .serialize(action)
+                    .map_err(|e| TransactionError::SerializeLogJson { json_err: e })?;
+                let round_trips = !json.contains('\n')
+                    && serde_json::from_str::<Action>(&json).is_ok_and(|read| read == *action);
+                if !round_trips {
+                    return Err(TransactionError::InvalidSerializedAction {
+                        action_index,
+                        line: json,
+                    });
+                }
+                json
+            }
+            None => serde_json::to_string(action)
+                .map_err(|e| TransactionError::SerializeLogJson { json_err: e })?,
+        };
+        if let Some(limit) = self.max_action_size {
+            if json.len() > limit {
+                return Err(TransactionError::ActionTooLarge {
+                    action_index,
+                    size: json.len(),
+                });
+            }
+        }
+        self.largest_action = self.largest_action.max(json.len());
+        if self.num_actions > 0 {
+            self.buffer.push(b'\n');
+        }
+        self.buffer.extend_from_slice(json.as_bytes());
+        self.num_actions += 1;
+        self.stats.record(action);
+        if let Some((interval, callback)) = self.progress {
+            if *interval > 0 && self.num_actions % interval == 0 {
+                callback(self.num_actions);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Bytes {
+        debug!(
+            "Largest serialized action in commit is {} bytes",
+            self.largest_action
         );
-        PreCommit {
-            log_store,
-            table_data,
-            max_retries: self.max_retries,
-            data,
-            post_commit_hook: self.post_commit_hook,
-            post_commit_hook_handler: self.post_commit_hook_handler,
-            operation_id: self.operation_id,
+        Bytes::from(self.buffer)
+    }
+}
+
+/// Policy for delaying commit retries after losing a race for a table version.
+///
+/// The delay before the `n`-th retry is `initial_delay * multiplier^(n - 1)`, capped at
+/// `max_delay`. With jitter enabled a random delay between zero and that value is used
+/// instead. The default policy retries immediately.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryBackoff {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Factor the delay grows by with every retry
+    pub multiplier: f64,
+    /// Upper bound for the delay between two attempts
+    pub max_delay: Duration,
+    /// Randomize the delay to spread out contending writers
+    pub jitter: bool,
+    /// Stop retrying once the commit has been attempted for longer than this
+    pub max_elapsed: Option<Duration>,
+}
+
+impl Default for RetryBackoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::ZERO,
+            multiplier: 1.0,
+            max_delay: Duration::ZERO,
+            jitter: false,
+            max_elapsed: None,
         }
     }
 }
 
-/// Represents a commit that has not yet started but all details are finalized
-pub struct PreCommit<'a> {
-    log_store: LogStoreRef,
-    table_data: Option<&'a dyn TableReference>,
-    data: CommitData,
-    max_retries: usize,
-    post_commit_hook: Option<PostCommitHookProperties>,
-    post_commit_hook_handler: Option<Arc<dyn CustomExecuteHandler>>,
-    operation_id: Uuid,
+impl RetryBackoff {
+    /// Create an exponential backoff policy without jitter or wall-clock limit
+    pub fn new(initial_delay: Duration, multiplier: f64, max_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            multiplier,
+            max_delay,
+            ..Default::default()
+        }
+    }
+
+    /// Randomize the delay between attempts
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Limit the total time spent attempting the commit
+    pub fn with_max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    /// The delay before the given retry, starting at 1 for the first retry
+    pub fn delay(&self, retry: usize) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as usize) as i32;
+        // computed in seconds, as the delay may grow beyond what a `Duration` can hold
+        let secs = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        let delay = if secs.is_nan() || secs <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64(secs)
+                .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+        };
+        if self.jitter && !delay.is_zero() {
+            delay.mul_f64(rand::random::<f64>())
+        } else {
+            delay
+        }
+    }
 }
 
-impl<'a> std::future::IntoFuture for PreCommit<'a> {
-    type Output = DeltaResult<FinalizedCommit>;
-    type IntoFuture = BoxFuture<'a, Self::Output>;
+/// Coalesces the checkpoints of many commits into a single checkpoint created on demand.
+///
+/// Commits configured with a manager via [`CommitProperties::with_deferred_checkpoint`] do not
+/// create checkpoints themselves. Instead, whenever a checkpoint is due, they record the new
+/// table state as pending, replacing any older pending state. Calling [`drain`] then creates a
+/// single checkpoint for the latest pending version, e.g. from a background task or whenever
+/// the writer is idle, which keeps checkpointing out of the latency of individual commits.
+///
+/// Clones of a manager share their pending state, so one manager can be used for all commits
+/// to a table. Pending checkpoints are not persisted: a checkpoint that is never drained is
+/// simply not created.
+///
+/// [`drain`]: DeferredCheckpointManager::drain
+#[derive(Clone, Default)]
+pub struct DeferredCheckpointManager {
+    pending: Arc<std::sync::Mutex<Option<(DeltaTableState, LogStoreRef)>>>,
+}
 
-    fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move { self.into_prepared_commit_future().await?.await?.await })
+impl std::fmt::Debug for DeferredCheckpointManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeferredCheckpointManager")
+            .field("pending_version", &self.pending_version())
+            .finish()
     }
 }
 
-impl<'a> PreCommit<'a> {
-    /// Prepare the commit but do not finalize it
-    pub fn into_prepared_commit_future(self) -> BoxFuture<'a, DeltaResult<PreparedCommit<'a>>> {
-        let this = self;
+impl DeferredCheckpointManager {
+    /// Create a manager without pending checkpoints
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        // Write delta log entry as temporary file to storage. For the actual commit,
-        // the temporary file is moved (atomic rename) to the delta log folder within `commit` function.
-        async fn write_tmp_commit(
-            log_entry: Bytes,
-            store: ObjectStoreRef,
-        ) -> DeltaResult<CommitOrBytes> {
-            let token = uuid::Uuid::new_v4().to_string();
-            let path = Path::from_iter([DELTA_LOG_FOLDER, &format!("_commit_{token}.json.tmp")]);
-            store.put(&path, log_entry.into()).await?;
-            Ok(CommitOrBytes::TmpCommit(path))
+    /// The version which will be checkpointed by the next call to `drain`, if any
+    pub fn pending_version(&self) -> Option<i64> {
+        self.pending
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(state, _)| state.version())
+    }
+
+    /// Record that the given table state should be checkpointed
+    fn signal(&self, state: &DeltaTableState, log_store: &LogStoreRef) {
+        let mut pending = self.pending.lock().unwrap();
+        if pending
+            .as_ref()
+            .is_some_and(|(pending, _)| pending.version() >= state.version())
+        {
+            return;
         }
+        *pending = Some((state.clone(), log_store.clone()));
+    }
 
-        Box::pin(async move {
-            if let Some(table_reference) = this.table_data {
-                PROTOCOL.can_commit(table_reference, &this.data.actions, &this.data.operation)?;
+    /// Create a checkpoint for the latest pending version.
+    ///
+    /// Returns the checkpointed version, or `None` if no checkpoint was pending. If creating
+    /// the checkpoint fails, it stays pending unless a newer version was signaled meanwhile.
+    pub async fn drain(&self) -> DeltaResult<Option<i64>> {
+        let pending = self.pending.lock().unwrap().take();
+        let Some((state, log_store)) = pending else {
+            return Ok(None);
+        };
+        let version = state.version();
+        if let Err(err) = create_checkpoint_for(version, &state, log_store.as_ref(), None).await {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.is_none() {
+                *pending = Some((state, log_store));
             }
-            let log_entry = this.data.get_bytes()?;
+            return Err(err.into());
+        }
+        Ok(Some(version))
+    }
+}
 
-            // With the DefaultLogStore & LakeFSLogstore, we just pass the bytes around, since we use conditionalPuts
-            // Other stores will use tmp_commits
-            let commit_or_bytes = if ["LakeFSLogStore", "DefaultLogStore"]
-                .contains(&this.log_store.name().as_str())
-            {
-                CommitOrBytes::LogBytes(log_entry)
-            } else {
-                write_tmp_commit(
-                    log_entry,
-                    this.log_store.object_store(Some(this.operation_id)),
-                )
-                .await?
-            };
+#[derive(Clone, Debug, Copy)]
+/// Properties for post commit hook.
+pub struct PostCommitHookProperties {
+    create_checkpoint: bool,
+    /// Override the EnableExpiredLogCleanUp setting, if None config setting is used
+    cleanup_expired_logs: Option<bool>,
+    /// Timestamp in milliseconds since the epoch before which logs are expired, if None it
+    /// is derived from the logRetentionDuration setting
+    log_cleanup_cutoff: Option<i64>,
+    /// Number of times to retry creating a checkpoint before giving up
+    checkpoint_retries: usize,
+    /// Fail the commit if creating a checkpoint or cleaning up logs fails
+    fail_on_error: bool,
+    /// Override the checkpoint interval of the table config, if None config setting is used
+    checkpoint_interval: Option<i64>,
+    /// Write a version checksum file for the committed version
+    write_crc: bool,
+}
 
-            Ok(PreparedCommit {
-                commit_or_bytes,
-                log_store: this.log_store,
-                table_data: this.table_data,
-                max_retries: this.max_retries,
-                data: this.data,
-                post_commit: this.post_commit_hook,
-                post_commit_hook_handler: this.post_commit_hook_handler,
-                operation_id: this.operation_id,
-            })
-        })
+impl Default for PostCommitHookProperties {
+    fn default() -> Self {
+        Self {
+            create_checkpoint: true,
+            cleanup_expired_logs: None,
+            log_cleanup_cutoff: None,
+            checkpoint_retries: DEFAULT_CHECKPOINT_RETRIES,
+            fail_on_error: false,
+            checkpoint_interval: None,
+            write_crc: false,
+        }
     }
 }
 
-/// Represents a inflight commit
-pub struct PreparedCommit<'a> {
-    commit_or_bytes: CommitOrBytes,
-    log_store: LogStoreRef,
-    data: CommitData,
-    table_data: Option<&'a dyn TableReference>,
-    max_retries: usize,
-    post_commit: Option<PostCommitHookProperties>,
+impl PostCommitHookProperties {
+    /// Properties of commits built without a post commit hook, which neither create
+    /// checkpoints nor write checksums
+    fn disabled() -> Self {
+        Self {
+            create_checkpoint: false,
+            checkpoint_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Specify how many times to retry creating a checkpoint before giving up.
+    ///
+    /// See [`CommitProperties::with_checkpoint_retries`].
+    pub fn with_checkpoint_retries(mut self, checkpoint_retries: usize) -> Self {
+        self.checkpoint_retries = checkpoint_retries;
+        self
+    }
+
+    /// Number of times creating a checkpoint is retried before giving up
+    pub fn checkpoint_retries(&self) -> usize {
+        self.checkpoint_retries
+    }
+}
+
+/// Options controlling how a commit is prepared, written and finalized.
+///
+/// Set through [CommitProperties] or the [CommitBuilder] and handed on unchanged through
+/// the stages of the commit.
+#[derive(Clone)]
+struct CommitOptions {
+    max_write_retries: usize,
+    max_conflict_resolutions: usize,
+    post_commit_hook: Option<PostCommitHookProperties>,
     post_commit_hook_handler: Option<Arc<dyn CustomExecuteHandler>>,
-    operation_id: Uuid,
+    operation_predicate: Option<String>,
+    conflict_prewarm: usize,
+    conflict_check_concurrency: usize,
+    serialization_progress: Option<(usize, SerializationProgressFn)>,
+    expected_protocol: Option<Protocol>,
+    domain_precondition: Option<DomainPrecondition>,
+    timestamp_precondition: Option<CommitTimestampPrecondition>,
+    partition_scope: Option<Vec<PartitionFilter>>,
+    deferred_checkpoint: Option<DeferredCheckpointManager>,
+    validate_removes: bool,
+    intent_store: Option<Arc<dyn CommitIntentStore>>,
+    attempt_observer: Option<AttemptObserverFn>,
+    finalized_observer: Option<FinalizedObserverFn>,
+    deterministic_tmp_commit: bool,
+    preserve_tmp_on_error: bool,
+    max_action_size: Option<usize>,
+    retry_backoff: RetryBackoff,
+    timeout: Option<Duration>,
+    client_version_policy: ClientVersionPolicy,
+    blind_append: Option<bool>,
+    auto_commit_info: bool,
+    conflict_resolution: Option<Arc<dyn ConflictResolution>>,
+    commit_info_fn: Option<CommitInfoFn>,
+    engine_info: Option<String>,
+    commit_timestamp: Option<i64>,
+    max_commit_info_bytes: Option<usize>,
+    protocol_check: bool,
+    action_serializer: Option<Arc<dyn ActionSerializer>>,
+    secondary_log_store: Option<LogStoreRef>,
 }
 
-impl PreparedCommit<'_> {
-    /// The temporary commit file created
-    pub fn commit_or_bytes(&self) -> &CommitOrBytes {
-        &self.commit_or_bytes
+impl Default for CommitOptions {
+    fn default() -> Self {
+        Self {
+            max_write_retries: default_max_retries(),
+            max_conflict_resolutions: default_max_retries(),
+            post_commit_hook: None,
+            post_commit_hook_handler: None,
+            operation_predicate: None,
+            conflict_prewarm: 0,
+            conflict_check_concurrency: DEFAULT_CONFLICT_CHECK_CONCURRENCY,
+            serialization_progress: None,
+            expected_protocol: None,
+            domain_precondition: None,
+            timestamp_precondition: None,
+            partition_scope: None,
+            deferred_checkpoint: None,
+            validate_removes: false,
+            intent_store: None,
+            attempt_observer: None,
+            finalized_observer: None,
+            deterministic_tmp_commit: false,
+            preserve_tmp_on_error: false,
+            max_action_size: None,
+            retry_backoff: RetryBackoff::default(),
+            timeout: None,
+            client_version_policy: ClientVersionPolicy::default(),
+            blind_append: None,
+            auto_commit_info: true,
+            conflict_resolution: None,
+            commit_info_fn: None,
+            engine_info: None,
+            commit_timestamp: None,
+            max_commit_info_bytes: None,
+            protocol_check: true,
+            action_serializer: None,
+            secondary_log_store: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for CommitOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // callbacks and trait objects without a Debug bound are left out
+        f.debug_struct("CommitOptions")
+            .field("max_write_retries", &self.max_write_retries)
+            .field("max_conflict_resolutions", &self.max_conflict_resolutions)
+            .field("post_commit_hook", &self.post_commit_hook)
+            .field("operation_predicate", &self.operation_predicate)
+            .field("conflict_prewarm", &self.conflict_prewarm)
+            .field(
+                "conflict_check_concurrency",
+                &self.conflict_check_concurrency,
+            )
+            .field("expected_protocol", &self.expected_protocol)
+            .field("domain_precondition", &self.domain_precondition)
+            .field("timestamp_precondition", &self.timestamp_precondition)
+            .field("partition_scope", &self.partition_scope)
+            .field("deferred_checkpoint", &self.deferred_checkpoint)
+            .field("validate_removes", &self.validate_removes)
+            .field("intent_store", &self.intent_store)
+            .field("deterministic_tmp_commit", &self.deterministic_tmp_commit)
+            .field("preserve_tmp_on_error", &self.preserve_tmp_on_error)
+            .field("max_action_size", &self.max_action_size)
+            .field("retry_backoff", &self.retry_backoff)
+            .field("timeout", &self.timeout)
+            .field("client_version_policy", &self.client_version_policy)
+            .field("blind_append", &self.blind_append)
+            .field("auto_commit_info", &self.auto_commit_info)
+            .field("engine_info", &self.engine_info)
+            .field("commit_timestamp", &self.commit_timestamp)
+            .field("max_commit_info_bytes", &self.max_commit_info_bytes)
+            .field("protocol_check", &self.protocol_check)
+            .field("action_serializer", &self.action_serializer)
+            .field("secondary_log_store", &self.secondary_log_store)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug)]
+/// End user facing interface to be used by operations on the table.
+/// Enable controlling commit behaviour and modifying metadata that is written during a commit.
+pub struct CommitProperties {
+    pub(crate) app_metadata: HashMap<String, Value>,
+    pub(crate) app_transaction: Vec<Transaction>,
+    options: CommitOptions,
+}
+
+impl Default for CommitProperties {
+    fn default() -> Self {
+        Self {
+            app_metadata: Default::default(),
+            app_transaction: Vec::new(),
+            options: CommitOptions {
+                post_commit_hook: Some(PostCommitHookProperties::default()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl CommitProperties {
+    fn post_commit_hook(&mut self) -> &mut PostCommitHookProperties {
+        self.options
+            .post_commit_hook
+            .get_or_insert_with(PostCommitHookProperties::default)
+    }
+
+    /// Specify metadata the be committed.
+    ///
+    /// The entries are merged into the metadata specified so far, so defaults can be layered
+    /// with per-commit overrides. For keys specified more than once, by this method or by
+    /// [`add_metadata`](Self::add_metadata), the latest value wins.
+    pub fn with_metadata(
+        mut self,
+        metadata: impl IntoIterator<Item = (String, serde_json::Value)>,
+    ) -> Self {
+        self.app_metadata.extend(metadata);
+        self
+    }
+
+    /// Add a single metadata entry to be committed, replacing an earlier value of the key
+    pub fn add_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.app_metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Specify maximum number of times to retry the transaction before failing to commit.
+    ///
+    /// Sets both the maximum number of write retries and of conflict resolutions.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.options.max_write_retries = max_retries;
+        self.options.max_conflict_resolutions = max_retries;
+        self
+    }
+
+    /// Specify maximum number of times to retry writing the commit entry after losing the
+    /// race for a version to a concurrent writer
+    pub fn with_max_write_retries(mut self, max_write_retries: usize) -> Self {
+        self.options.max_write_retries = max_write_retries;
+        self
+    }
+
+    /// Specify maximum number of times to check the transaction for conflicts with commits
+    /// of concurrent writers.
+    ///
+    /// Every resolution reads all commits since the last one, which makes them expensive
+    /// under heavy contention. Limiting them separately from the write retries allows many
+    /// cheap retries while bounding the cost of the commit.
+    pub fn with_max_conflict_resolutions(mut self, max_conflict_resolutions: usize) -> Self {
+        self.options.max_conflict_resolutions = max_conflict_resolutions;
+        self
+    }
+
+    /// Specify if it should create a checkpoint when the commit interval condition is met
+    pub fn with_create_checkpoint(mut self, create_checkpoint: bool) -> Self {
+        self.post_commit_hook().create_checkpoint = create_checkpoint;
+        self
+    }
+
+    /// Add an additional application transaction to the commit
+    pub fn with_application_transaction(mut self, txn: Transaction) -> Self {
+        self.app_transaction.push(txn);
+        self
+    }
+
+    /// Override application transactions for the commit
+    pub fn with_application_transactions(mut self, txn: Vec<Transaction>) -> Self {
+        self.app_transaction = txn;
+        self
+    }
+
+    /// Specify if it should clean up the logs when the logRetentionDuration interval is met
+    pub fn with_cleanup_expired_logs(mut self, cleanup_expired_logs: Option<bool>) -> Self {
+        self.post_commit_hook().cleanup_expired_logs = cleanup_expired_logs;
+        self
+    }
+
+    /// Expire the logs written before the given timestamp in milliseconds since the epoch
+    /// instead of those older than the logRetentionDuration, e.g. to preserve more history
+    /// during backfills.
+    ///
+    /// Whether logs are cleaned up at all is still controlled by
+    /// [`with_cleanup_expired_logs`](Self::with_cleanup_expired_logs).
+    pub fn with_log_cleanup_cutoff(mut self, cutoff_timestamp: i64) -> Self {
+        self.post_commit_hook().log_cleanup_cutoff = Some(cutoff_timestamp);
+        self
+    }
+
+    /// Specify how many times to retry creating a checkpoint before giving up.
+    ///
+    /// The commit is already durable at that point, so a checkpoint that still fails
+    /// after all retries is logged and reported in the commit metrics. The delay between
+    /// attempts doubles from 100ms up to 10s.
+    pub fn with_checkpoint_retries(mut self, checkpoint_retries: usize) -> Self {
+        self.post_commit_hook().checkpoint_retries = checkpoint_retries;
+        self
+    }
+
+    /// Specify if a failure to create a checkpoint or to clean up expired logs fails the commit.
+    ///
+    /// By default these failures are logged and recorded in the commit metrics instead, since
+    /// the commit itself is already durable when they happen.
+    pub fn with_fail_on_post_commit_error(mut self, fail_on_error: bool) -> Self {
+        self.post_commit_hook().fail_on_error = fail_on_error;
+        self
+    }
+
+    /// Write a version checksum file (`_delta_log/<version>.crc`) summarizing the table state
+    /// after the commit, which other Delta implementations use to speed up loading snapshots.
+    ///
+    /// Like checkpoints, failures to write the file are only logged unless
+    /// [`with_fail_on_post_commit_error`](Self::with_fail_on_post_commit_error) is set.
+    pub fn with_write_crc(mut self, write_crc: bool) -> Self {
+        self.post_commit_hook().write_crc = write_crc;
+        self
+    }
+
+    /// Override the `delta.checkpointInterval` of the table when deciding whether this commit
+    /// creates a checkpoint, without changing the table configuration.
+    ///
+    /// Non-positive intervals are ignored.
+    pub fn with_checkpoint_interval(mut self, checkpoint_interval: i64) -> Self {
+        self.post_commit_hook().checkpoint_interval = Some(checkpoint_interval);
+        self
+    }
+
+    /// Override the predicate recorded in the `operationParameters` of the commit info
+    pub fn with_operation_predicate(mut self, predicate: impl Into<String>) -> Self {
+        self.options.operation_predicate = Some(predicate.into());
+        self
+    }
+
+    /// Preload up to `num_versions` commits that were written after the read version before
+    /// attempting the commit, so conflict resolution does not have to fetch them on retry.
+    ///
+    /// Disabled by default.
+    pub fn with_conflict_prewarm(mut self, num_versions: usize) -> Self {
+        self.options.conflict_prewarm = num_versions;
+        self
+    }
+
+    /// Maximum number of commits fetched concurrently when checking for conflicts with the
+    /// commits written since the read version.
+    ///
+    /// The commits are still checked in order, so the reported conflict does not depend on
+    /// the concurrency. Defaults to 8.
+    pub fn with_conflict_check_concurrency(mut self, concurrency: usize) -> Self {
+        self.options.conflict_check_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Fail the commit if the table protocol differs from the expected protocol, e.g. because
+    /// a concurrent writer upgraded the table.
+    pub fn with_expected_protocol(mut self, protocol: Protocol) -> Self {
+        self.options.expected_protocol = Some(protocol);
+        self
+    }
+
+    /// Only commit if the configuration of the metadata domain equals `expected`,
+    /// where `None` requires the domain to not exist.
+    pub fn with_domain_precondition(
+        mut self,
+        domain: impl Into<String>,
+        expected: Option<String>,
+    ) -> Self {
+        self.options.domain_precondition = Some(DomainPrecondition {
+            domain: domain.into(),
+            expected,
+        });
+        self
+    }
+
+    /// Only commit if the commit info timestamp (in milliseconds since the epoch) of the latest
+    /// table version satisfies `predicate`.
+    ///
+    /// The precondition is evaluated again whenever the transaction is rebased onto newer
+    /// versions while retrying. If the latest commit does not record a timestamp, the
+    /// precondition fails, since it cannot be verified.
+    pub fn with_latest_commit_timestamp_precondition(
+        mut self,
+        predicate: impl Fn(i64) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.options.timestamp_precondition = Some(CommitTimestampPrecondition::new(predicate));
+        self
+    }
+
+    /// Declare that the commit only reads and writes partitions matching all of the given
+    /// filters.
+    ///
+    /// Concurrent commits which exclusively added or removed files outside of this scope
+    /// are then not checked for conflicts, regardless of the isolation level. By default
+    /// all concurrent commits are checked.
+    pub fn with_partition_scope(mut self, partition_scope: Vec<PartitionFilter>) -> Self {
+        self.options.partition_scope = Some(partition_scope);
+        self
+    }
+
+    /// Hand checkpoints due after this commit to the given manager instead of creating them
+    /// as part of the commit.
+    ///
+    /// See [`DeferredCheckpointManager`] for details. By default checkpoints are created inline.
+    pub fn with_deferred_checkpoint(mut self, manager: DeferredCheckpointManager) -> Self {
+        self.options.deferred_checkpoint = Some(manager);
+        self
+    }
+
+    /// Verify that all files removed by the commit are active files of the table version the
+    /// commit is written on top of, including after resolving conflicts.
+    ///
+    /// This catches bugs producing an inconsistent log, but requires scanning the files of
+    /// the table for every attempt, so it is disabled by default.
+    pub fn with_validate_removes(mut self, validate_removes: bool) -> Self {
+        self.options.validate_removes = validate_removes;
+        self
+    }
+
+    /// Record the intent of the commit in the given store before writing its log entry, and
+    /// clear it once the commit is finalized.
+    ///
+    /// After a crash, commits that may or may not have been written can be resolved with
+    /// [`reconcile_commit_intents`]. By default no intents are recorded.
+    pub fn with_commit_intent_store(mut self, intent_store: Arc<dyn CommitIntentStore>) -> Self {
+        self.options.intent_store = Some(intent_store);
+        self
+    }
+
+    /// Delay retries after losing a race for a table version according to the given policy,
+    /// instead of retrying immediately.
+    pub fn with_retry_backoff(mut self, retry_backoff: RetryBackoff) -> Self {
+        self.options.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Fail with [`TransactionError::Timeout`] if preparing and writing the commit, including
+    /// all retries, takes longer than `timeout`.
+    ///
+    /// Post commit hooks are not subject to the timeout, since the commit is already durable
+    /// once they run. If the commit entry was written by the time the timeout fires, the commit
+    /// succeeds as well.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// How to handle a `clientVersion` passed via [`CommitProperties::with_metadata`].
+    ///
+    /// By default the user supplied value is replaced with the delta-rs version.
+    pub fn with_client_version_policy(mut self, policy: ClientVersionPolicy) -> Self {
+        self.options.client_version_policy = policy;
+        self
+    }
+
+    /// Whether to add a commit info action to the commit, see
+    /// [`CommitBuilder::with_auto_commit_info`]
+    pub fn with_auto_commit_info(mut self, auto_commit_info: bool) -> Self {
+        self.options.auto_commit_info = auto_commit_info;
+        self
+    }
+
+    /// Override whether the commit is recorded as a blind append in the commit info.
+    ///
+    /// By default this is inferred from the operation and the committed actions.
+    pub fn with_blind_append(mut self, blind_append: bool) -> Self {
+        self.options.blind_append = Some(blind_append);
+        self
+    }
+
+    /// Compute additional commit info entries from the committed operation.
+    ///
+    /// The function is invoked for every commit and its output merged into the commit info,
+    /// without overriding entries passed via [`CommitProperties::with_metadata`] or reserved
+    /// keys like `timestamp` and `clientVersion`.
+    pub fn with_commit_info_fn(mut self, commit_info_fn: CommitInfoFn) -> Self {
+        self.options.commit_info_fn = Some(commit_info_fn);
+        self
+    }
+
+    /// Name and version of the engine embedding delta-rs, e.g. `myengine/1.2.3`, to be
+    /// recorded in the commit info.
+    pub fn with_engine_info(mut self, engine_info: impl Into<String>) -> Self {
+        self.options.engine_info = Some(engine_info.into());
+        self
+    }
+
+    /// Record the given timestamp in milliseconds since the epoch in the commit info instead
+    /// of the current time, e.g. for deterministic tests or when replaying a log.
+    ///
+    /// Only the `timestamp` of the commit info is affected, the commit is still written as
+    /// the next version of the table.
+    pub fn with_commit_timestamp(mut self, timestamp: i64) -> Self {
+        self.options.commit_timestamp = Some(timestamp);
+        self
+    }
+
+    /// Fail the commit if the serialized commit info, which includes all app metadata,
+    /// exceeds `max_bytes`.
+    ///
+    /// No limit is enforced by default.
+    pub fn with_max_commit_info_bytes(mut self, max_bytes: usize) -> Self {
+        self.options.max_commit_info_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Serialize the actions of the log entry with a custom serializer instead of their
+    /// derived json representation, e.g. for readers expecting a canonical form.
+    ///
+    /// Commits fail if the output does not deserialize to the original actions.
+    pub fn with_action_serializer(mut self, serializer: Arc<dyn ActionSerializer>) -> Self {
+        self.options.action_serializer = Some(serializer);
+        self
+    }
+}
+
+impl From<CommitProperties> for CommitBuilder {
+    fn from(value: CommitProperties) -> Self {
+        CommitBuilder {
+            app_metadata: value.app_metadata,
+            app_transaction: value.app_transaction,
+            options: value.options,
+            ..Default::default()
+        }
+    }
+}
+
+/// Prepare data to be committed to the Delta log and control how the commit is performed
+pub struct CommitBuilder {
+    actions: Vec<Action>,
+    action_stream: Option<ActionStream>,
+    app_metadata: HashMap<String, Value>,
+    app_transaction: Vec<Transaction>,
+    operation_id: Uuid,
+    options: CommitOptions,
+}
+
+impl Default for CommitBuilder {
+    fn default() -> Self {
+        CommitBuilder {
+            actions: Vec::new(),
+            action_stream: None,
+            app_metadata: HashMap::new(),
+            app_transaction: Vec::new(),
+            operation_id: Uuid::new_v4(),
+            options: CommitOptions::default(),
+        }
+    }
+}
+
+impl<'a> CommitBuilder {
+    /// Actions to be included in the commit.
+    ///
+    /// The commit may consist of no data actions at all, e.g. to only record a watermark as
+    /// an app transaction. It still advances the table version.
+    pub fn with_actions(mut self, actions: Vec<Action>) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    /// Stream of actions to be included in the commit in addition to the ones
+    /// passed via `with_actions`.
+    ///
+    /// The stream is consumed when the commit is prepared, which allows operations to
+    /// produce their actions incrementally instead of collecting them upfront. The actions
+    /// are serialized into the log entry as they are consumed and are not kept in memory.
+    ///
+    /// They are only loaded back from the staged log entry when required, i.e. to check for
+    /// conflicts once a concurrent commit landed or to validate removes. The table state
+    /// after the commit is read from the log rather than advanced in memory, and the
+    /// [`CommitData`] of the commit does not include the streamed actions unless they were
+    /// loaded back.
+    pub fn with_action_stream(mut self, actions: ActionStream) -> Self {
+        self.action_stream = Some(actions);
+        self
+    }
+
+    /// Metadata for the operation performed like metrics, user, and notebook.
+    ///
+    /// The entries are merged into the metadata specified so far, including the one of the
+    /// [`CommitProperties`] the builder was created from. For keys specified more than once,
+    /// the latest value wins.
+    pub fn with_app_metadata(mut self, app_metadata: HashMap<String, Value>) -> Self {
+        self.app_metadata.extend(app_metadata);
+        self
+    }
+
+    /// Add a single metadata entry for the operation, replacing an earlier value of the key
+    pub fn add_app_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.app_metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Maximum number of times to retry the transaction before failing to commit.
+    ///
+    /// Sets both the maximum number of write retries and of conflict resolutions.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.options.max_write_retries = max_retries;
+        self.options.max_conflict_resolutions = max_retries;
+        self
+    }
+
+    /// Maximum number of times to retry writing the commit entry after losing the race for a
+    /// version, see [CommitProperties::with_max_write_retries]
+    pub fn with_max_write_retries(mut self, max_write_retries: usize) -> Self {
+        self.options.max_write_retries = max_write_retries;
+        self
+    }
+
+    /// Maximum number of times to check the transaction for conflicts with concurrent
+    /// commits, see [CommitProperties::with_max_conflict_resolutions]
+    pub fn with_max_conflict_resolutions(mut self, max_conflict_resolutions: usize) -> Self {
+        self.options.max_conflict_resolutions = max_conflict_resolutions;
+        self
+    }
+
+    /// Specify all the post commit hook properties
+    pub fn with_post_commit_hook(mut self, post_commit_hook: PostCommitHookProperties) -> Self {
+        self.options.post_commit_hook = Some(post_commit_hook);
+        self
+    }
+
+    /// Propagate operation id to log store
+    pub fn with_operation_id(mut self, operation_id: Uuid) -> Self {
+        self.operation_id = operation_id;
+        self
+    }
+
+    /// Derive the name of the temporary commit file from the operation id instead of a
+    /// random token.
+    ///
+    /// Retrying the same logical operation after a crash then writes to the same temporary
+    /// file rather than leaking a new one on every attempt. Only relevant for log stores
+    /// that stage commits as temporary files.
+    pub fn with_deterministic_tmp_commit(mut self, deterministic: bool) -> Self {
+        self.options.deterministic_tmp_commit = deterministic;
+        self
+    }
+
+    /// Keep the temporary commit file when the commit fails for reasons other than a
+    /// conflict, e.g. to inspect commits rejected by the log store.
+    ///
+    /// The error of the commit is then wrapped in [`TransactionError::TmpCommitPreserved`]
+    /// reporting the location of the file. The file is also kept when the commit is dropped
+    /// before its log entry was written, which otherwise deletes it. Only relevant for log
+    /// stores that stage commits as temporary files.
+    pub fn with_preserve_tmp_on_error(mut self, preserve: bool) -> Self {
+        self.options.preserve_tmp_on_error = preserve;
+        self
+    }
+
+    /// Fail the commit if the serialized size of any single action exceeds `max_action_size`
+    /// bytes, e.g. because of un-truncated file statistics.
+    ///
+    /// No limit is enforced by default.
+    pub fn with_max_action_size(mut self, max_action_size: Option<usize>) -> Self {
+        self.options.max_action_size = max_action_size;
+        self
+    }
+
+    /// Policy for delaying retries after losing a race for a table version
+    pub fn with_retry_backoff(mut self, retry_backoff: RetryBackoff) -> Self {
+        self.options.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Maximum time to spend on preparing and writing the commit
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.options.timeout = timeout;
+        self
+    }
+
+    /// How to handle a `clientVersion` supplied in the app metadata
+    pub fn with_client_version_policy(mut self, policy: ClientVersionPolicy) -> Self {
+        self.options.client_version_policy = policy;
+        self
+    }
+
+    /// Whether to add a commit info action to the commit, enabled by default.
+    ///
+    /// When disabled, the actions are committed as given, e.g. to supply a custom commit
+    /// info or to replay commits of another table. Commits without a commit info are valid,
+    /// but some readers and tools like `DESCRIBE HISTORY` expect one. Settings which modify
+    /// the commit info, like the app metadata or the engine info, only apply to a commit
+    /// info passed with the actions.
+    pub fn with_auto_commit_info(mut self, auto_commit_info: bool) -> Self {
+        self.options.auto_commit_info = auto_commit_info;
+        self
+    }
+
+    /// Override whether the commit is recorded as a blind append
+    pub fn with_blind_append(mut self, blind_append: Option<bool>) -> Self {
+        self.options.blind_append = blind_append;
+        self
+    }
+
+    /// Strategy to resolve conflicts with commits written since the read version.
+    ///
+    /// Defaults to the [DefaultConflictResolution], which follows the table's isolation level.
+    pub fn with_conflict_resolution(mut self, resolution: Arc<dyn ConflictResolution>) -> Self {
+        self.options.conflict_resolution = Some(resolution);
+        self
+    }
+
+    /// Compute additional commit info entries from the committed operation
+    pub fn with_commit_info_fn(mut self, commit_info_fn: Option<CommitInfoFn>) -> Self {
+        self.options.commit_info_fn = commit_info_fn;
+        self
+    }
+
+    /// Name and version of the engine embedding delta-rs to be recorded in the commit info
+    pub fn with_engine_info(mut self, engine_info: Option<String>) -> Self {
+        self.options.engine_info = engine_info;
+        self
+    }
+
+    /// Timestamp to be recorded in the commit info instead of the current time, see
+    /// [CommitProperties::with_commit_timestamp]
+    pub fn with_commit_timestamp(mut self, timestamp: Option<i64>) -> Self {
+        self.options.commit_timestamp = timestamp;
+        self
+    }
+
+    /// Fail the commit if the serialized commit info exceeds the given number of bytes
+    pub fn with_max_commit_info_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.options.max_commit_info_bytes = max_bytes;
+        self
+    }
+
+    /// Serialize the actions with a custom serializer, see
+    /// [CommitProperties::with_action_serializer]
+    pub fn with_action_serializer(mut self, serializer: Option<Arc<dyn ActionSerializer>>) -> Self {
+        self.options.action_serializer = serializer;
+        self
+    }
+
+    /// Validate the commit against the reader and writer features of the table protocol
+    /// (default: `true`).
+    ///
+    /// Disabling the check saves re-validating the protocol on every commit, but trades
+    /// safety for speed: delta-rs will happily write to tables using features it does not
+    /// support, or remove files from append-only tables. Only disable it when the caller
+    /// has already verified that the table protocol is compatible with this writer.
+    pub fn with_protocol_check(mut self, protocol_check: bool) -> Self {
+        self.options.protocol_check = protocol_check;
+        self
+    }
+
+    /// Mirror the commit to a secondary log store, e.g. while migrating a table between
+    /// object stores.
+    ///
+    /// The commit entry is written to the secondary log store at the version it was written
+    /// as to the primary log store, so the secondary has to be an exact copy of the primary
+    /// table. The commit only succeeds if both writes succeed, but atomicity is best-effort:
+    /// if the primary write fails, the entry staged for the secondary is aborted, but an entry
+    /// already written to the primary can not be rolled back. A failure of the secondary is
+    /// then reported as [`TransactionError::SecondaryCommitFailed`] with the committed version,
+    /// and the secondary has to be repaired before the next commit.
+    ///
+    /// A commit timeout only applies to the primary log store.
+    pub fn with_secondary_log_store(mut self, log_store: Option<LogStoreRef>) -> Self {
+        self.options.secondary_log_store = log_store;
+        self
+    }
+
+    /// Set a custom execute handler, for pre and post execution
+    pub fn with_post_commit_hook_handler(
+        mut self,
+        handler: Option<Arc<dyn CustomExecuteHandler>>,
+    ) -> Self {
+        self.options.post_commit_hook_handler = handler;
+        self
+    }
+
+    /// Override the predicate recorded in the `operationParameters` of the commit info.
+    ///
+    /// The predicate of the [`DeltaOperation`] is still used for conflict detection.
+    pub fn with_operation_predicate(mut self, predicate: Option<String>) -> Self {
+        self.options.operation_predicate = predicate;
+        self
+    }
+
+    /// Number of commits after the read version to preload for conflict resolution
+    pub fn with_conflict_prewarm(mut self, num_versions: usize) -> Self {
+        self.options.conflict_prewarm = num_versions;
+        self
+    }
+
+    /// Maximum number of commits fetched concurrently when checking for conflicts
+    pub fn with_conflict_check_concurrency(mut self, concurrency: usize) -> Self {
+        self.options.conflict_check_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Report serialization progress for large commits, e.g. ones built from an action stream.
+    ///
+    /// The callback is invoked every `interval` actions with the number of actions
+    /// serialized so far.
+    pub fn with_serialization_progress(
+        mut self,
+        interval: usize,
+        callback: SerializationProgressFn,
+    ) -> Self {
+        self.options.serialization_progress = Some((interval, callback));
+        self
+    }
+
+    /// Fail the commit if the table protocol differs from the expected protocol
+    pub fn with_expected_protocol(mut self, protocol: Option<Protocol>) -> Self {
+        self.options.expected_protocol = protocol;
+        self
+    }
+
+    /// Only commit if the configuration of a metadata domain matches the expected value
+    pub fn with_domain_precondition(mut self, precondition: Option<DomainPrecondition>) -> Self {
+        self.options.domain_precondition = precondition;
+        self
+    }
+
+    /// Only commit if the timestamp of the latest commit satisfies the precondition
+    pub fn with_latest_commit_timestamp_precondition(
+        mut self,
+        precondition: Option<CommitTimestampPrecondition>,
+    ) -> Self {
+        self.options.timestamp_precondition = precondition;
+        self
+    }
+
+    /// Skip conflict checks against concurrent commits which only changed files outside
+    /// of the partitions matching all of the given filters
+    pub fn with_partition_scope(mut self, partition_scope: Option<Vec<PartitionFilter>>) -> Self {
+        self.options.partition_scope = partition_scope;
+        self
+    }
+
+    /// Hand checkpoints due after the commit to the given manager instead of creating them inline
+    pub fn with_deferred_checkpoint(mut self, manager: Option<DeferredCheckpointManager>) -> Self {
+        self.options.deferred_checkpoint = manager;
+        self
+    }
+
+    /// Verify that all files removed by the commit are active files of the table
+    pub fn with_validate_removes(mut self, validate_removes: bool) -> Self {
+        self.options.validate_removes = validate_removes;
+        self
+    }
+
+    /// Record the intent of the commit in the given store before writing it
+    pub fn with_commit_intent_store(
+        mut self,
+        intent_store: Option<Arc<dyn CommitIntentStore>>,
+    ) -> Self {
+        self.options.intent_store = intent_store;
+        self
+    }
+
+    /// Observe every attempt to write the commit entry, including failed ones, e.g. to
+    /// collect metrics about contention on the table.
+    ///
+    /// The observer is invoked after each attempt and cannot influence the commit, panics
+    /// of the observer are logged and otherwise ignored.
+    pub fn with_attempt_observer(mut self, observer: AttemptObserverFn) -> Self {
+        self.options.attempt_observer = Some(observer);
+        self
+    }
+
+    /// Observe the finalized commit, e.g. to refresh caches or notify other components of
+    /// the new table version.
+    ///
+    /// The observer is invoked once the post commit hook completed, and only for successful
+    /// commits. Panics of the observer are logged and otherwise ignored.
+    pub fn with_finalized_observer(mut self, observer: FinalizedObserverFn) -> Self {
+        self.options.finalized_observer = Some(observer);
+        self
+    }
+
+    fn commit_data(&mut self, operation: DeltaOperation) -> CommitData {
+        let actions = std::mem::take(&mut self.actions);
+        let app_metadata = std::mem::take(&mut self.app_metadata);
+        let app_transactions = std::mem::take(&mut self.app_transaction);
+        let mut data = if self.options.auto_commit_info {
+            CommitData::new_with_client_version_policy(
+                actions,
+                operation,
+                app_metadata,
+                app_transactions,
+                self.options.client_version_policy,
+            )
+        } else {
+            CommitData::new_without_commit_info(actions, operation, app_metadata, app_transactions)
+        };
+        if let Some(predicate) = self.options.operation_predicate.take() {
+            data.set_operation_predicate(predicate);
+        }
+        if let Some(commit_info_fn) = self.options.commit_info_fn.take() {
+            let info = commit_info_fn(&data.operation);
+            data.extend_commit_info(info);
+        }
+        if let Some(engine_info) = self.options.engine_info.take() {
+            data.set_engine_info(&engine_info);
+        }
+        if let Some(timestamp) = self.options.commit_timestamp {
+            data.set_commit_timestamp(timestamp);
+        }
+        data
+    }
+
+    /// Build the data exactly as it would be committed, without writing anything to storage.
+    ///
+    /// The returned [`CommitData`] includes the generated commit info and app transactions and
+    /// has been validated against the table protocol. Apart from the commit timestamp, its
+    /// [`CommitData::get_bytes`] output matches the log entry a real commit would write.
+    pub async fn build_dry_run(
+        mut self,
+        table_data: Option<&dyn TableReference>,
+        operation: DeltaOperation,
+    ) -> DeltaResult<CommitData> {
+        let mut data = self.commit_data(operation);
+        finalize_commit_data(
+            &mut data,
+            self.action_stream.take(),
+            self.options.blind_append,
+            self.options.max_commit_info_bytes,
+            table_data.filter(|_| self.options.protocol_check),
+        )
+        .await?;
+        Ok(data)
+    }
+
+    /// Prepare a Commit operation using the configured builder
+    pub fn build(
+        mut self,
+        table_data: Option<&'a dyn TableReference>,
+        log_store: LogStoreRef,
+        operation: DeltaOperation,
+    ) -> PreCommit<'a> {
+        let data = self.commit_data(operation);
+        PreCommit {
+            log_store,
+            table_data,
+            data,
+            action_stream: self.action_stream,
+            operation_id: self.operation_id,
+            options: self.options,
+        }
+    }
+}
+
+/// Represents a commit that has not yet started but all details are finalized
+pub struct PreCommit<'a> {
+    log_store: LogStoreRef,
+    table_data: Option<&'a dyn TableReference>,
+    data: CommitData,
+    action_stream: Option<ActionStream>,
+    operation_id: Uuid,
+    options: CommitOptions,
+}
+
+/// Complete the commit data with the streamed actions and validate it for the table
+async fn finalize_commit_data(
+    data: &mut CommitData,
+    action_stream: Option<ActionStream>,
+    blind_append: Option<bool>,
+    max_commit_info_bytes: Option<usize>,
+    table_data: Option<&dyn TableReference>,
+) -> DeltaResult<()> {
+    if let Some(action_stream) = action_stream {
+        data.extend_from_stream(action_stream).await?;
+    }
+    if let Some(blind_append) = blind_append {
+        data.set_blind_append(blind_append);
+    }
+    if let Some(max_bytes) = max_commit_info_bytes {
+        data.check_commit_info_size(max_bytes)?;
+    }
+    if let Some(table_reference) = table_data {
+        PROTOCOL.can_commit(table_reference, &data.actions, &data.operation)?;
+    }
+    Ok(())
+}
+
+/// Serialize the streamed actions into the log entry as they are consumed, without
+/// collecting them in the commit data. The tally over the streamed actions is recorded in
+/// the commit info, which has to be serialized afterwards.
+async fn serialize_action_stream(
+    data: &mut CommitData,
+    mut stream: ActionStream,
+    writer: &mut LogEntryWriter<'_>,
+    table_data: Option<&dyn TableReference>,
+) -> DeltaResult<()> {
+    let mut tally = ActionTally::default();
+    let mut chunk = Vec::with_capacity(ACTION_STREAM_CHUNK_SIZE);
+    let mut exhausted = false;
+    while !exhausted {
+        match stream.next().await.transpose()? {
+            Some(action) => chunk.push(action),
+            None => exhausted = true,
+        }
+        if chunk.len() < ACTION_STREAM_CHUNK_SIZE && !exhausted {
+            continue;
+        }
+        if let Some(table_reference) = table_data {
+            PROTOCOL.can_commit(table_reference, &chunk, &data.operation)?;
+        }
+        for action in chunk.drain(..) {
+            tally.record(&action);
+            writer.push(&action)?;
+        }
+    }
+    data.record_stream_tally(&tally)?;
+    Ok(())
+}
+
+impl<'a> std::future::IntoFuture for PreCommit<'a> {
+    type Output = DeltaResult<FinalizedCommit>;
+    type IntoFuture = BoxFuture<'a, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { self.into_prepared_commit_future().await?.await?.await })
+    }
+}
+
+impl<'a> PreCommit<'a> {
+    /// Prepare the commit but do not finalize it
+    pub fn into_prepared_commit_future(self) -> BoxFuture<'a, DeltaResult<PreparedCommit<'a>>> {
+        let mut this = self;
+
+        // Write delta log entry as temporary file to storage. For the actual commit,
+        // the temporary file is moved (atomic rename) to the delta log folder within `commit` function.
+        async fn write_tmp_commit(
+            log_entry: Bytes,
+            store: ObjectStoreRef,
+            token: Uuid,
+        ) -> DeltaResult<CommitOrBytes> {
+            let path = Path::from_iter([DELTA_LOG_FOLDER, &format!("_commit_{token}.json.tmp")]);
+            store.put(&path, log_entry.into()).await?;
+            Ok(CommitOrBytes::TmpCommit(path))
+        }
+
+        // Log stores supporting conditional puts are just passed the bytes around, other
+        // stores will use tmp_commits
+        async fn stage_log_entry(
+            log_entry: Bytes,
+            log_store: &LogStoreRef,
+            operation_id: Uuid,
+            deterministic_tmp_commit: bool,
+        ) -> DeltaResult<CommitOrBytes> {
+            if log_store.supports_conditional_put() {
+                return Ok(CommitOrBytes::LogBytes(log_entry));
+            }
+            let token = if deterministic_tmp_commit {
+                operation_id
+            } else {
+                Uuid::new_v4()
+            };
+            write_tmp_commit(log_entry, log_store.object_store(Some(operation_id)), token).await
+        }
+
+        // Fetch the commits following the read version which will have to be checked for
+        // conflicts. Failures are not fatal, the retry loop will fetch missing commits itself.
+        async fn prewarm_conflict_cache(
+            log_store: &LogStoreRef,
+            read_version: i64,
+            num_versions: usize,
+        ) -> HashMap<i64, WinningCommitSummary> {
+            let mut cache = HashMap::new();
+            let latest_version = match log_store.get_latest_version(read_version).await {
+                Ok(version) => version,
+                Err(err) => {
+                    debug!("Skipping conflict prewarm, failed to get latest version: {err}");
+                    return cache;
+                }
+            };
+            let last_version = latest_version.min(read_version + num_versions as i64);
+            for version in (read_version + 1)..=last_version {
+                match WinningCommitSummary::try_new(log_store.as_ref(), version - 1, version).await
+                {
+                    Ok(summary) => {
+                        cache.insert(version, summary);
+                    }
+                    Err(err) => {
+                        debug!("Stopping conflict prewarm at version {version}: {err}");
+                        break;
+                    }
+                }
+            }
+            cache
+        }
+
+        let started = Instant::now();
+        let timeout = this.options.timeout;
+        let prepare: BoxFuture<'a, DeltaResult<PreparedCommit<'a>>> = Box::pin(async move {
+            // fail fast on an inevitable failure, before any actions are streamed or staged
+            if let Some(table_reference) = this.table_data.filter(|_| this.options.protocol_check) {
+                PROTOCOL.check_append_only_actions(
+                    table_reference,
+                    &this.data.actions,
+                    &this.data.operation,
+                )?;
+            }
+            let mut writer = LogEntryWriter::new(
+                this.options.serialization_progress.as_ref(),
+                this.options.max_action_size,
+                this.options.action_serializer.as_deref(),
+            );
+            let streamed_actions = match this.action_stream.take() {
+                Some(action_stream) => {
+                    serialize_action_stream(
+                        &mut this.data,
+                        action_stream,
+                        &mut writer,
+                        this.table_data.filter(|_| this.options.protocol_check),
+                    )
+                    .await?;
+                    true
+                }
+                None => false,
+            };
+            finalize_commit_data(
+                &mut this.data,
+                None,
+                this.options.blind_append,
+                this.options.max_commit_info_bytes,
+                this.table_data.filter(|_| this.options.protocol_check),
+            )
+            .await?;
+            for action in &this.data.actions {
+                writer.push(action)?;
+            }
+            let num_actions = writer.num_actions;
+            if num_actions == 0 {
+                return Err(TransactionError::EmptyCommit.into());
+            }
+            let commit_stats = writer.stats;
+            let log_entry = writer.finish();
+            let metrics = CommitMetrics {
+                num_retries: 0,
+                num_actions: num_actions as u64,
+                commit_size_bytes: log_entry.len() as u64,
+                conflict_check_duration_ms: 0,
+            };
+            // the intent also identifies the log entry in the log if the commit times out
+            let intent = (this.options.intent_store.is_some() || this.options.timeout.is_some())
+                .then(|| {
+                    CommitIntent::new(
+                        this.operation_id,
+                        this.table_data
+                            .map(|table_reference| table_reference.eager_snapshot().version()),
+                        num_actions,
+                        &log_entry,
+                    )
+                });
+            if let (Some(intent_store), Some(intent)) = (&this.options.intent_store, &intent) {
+                intent_store.record(intent).await?;
+            }
+
+            let conflict_cache = match this.table_data {
+                Some(table_reference) if this.options.conflict_prewarm > 0 => {
+                    prewarm_conflict_cache(
+                        &this.log_store,
+                        table_reference.eager_snapshot().version(),
+                        this.options.conflict_prewarm,
+                    )
+                    .await
+                }
+                _ => HashMap::new(),
+            };
+
+            let secondary = match this.options.secondary_log_store.take() {
+                Some(log_store) => Some(SecondaryCommit {
+                    commit_or_bytes: stage_log_entry(
+                        log_entry.clone(),
+                        &log_store,
+                        this.operation_id,
+                        this.options.deterministic_tmp_commit,
+                    )
+                    .await?,
+                    log_store,
+                }),
+                None => None,
+            };
+            let commit_or_bytes = stage_log_entry(
+                log_entry,
+                &this.log_store,
+                this.operation_id,
+                this.options.deterministic_tmp_commit,
+            )
+            .await?;
+            let tmp_commit_guard = TmpCommitGuard::new(
+                &this.log_store,
+                &commit_or_bytes,
+                this.operation_id,
+                !this.options.preserve_tmp_on_error,
+            );
+
+            Ok(PreparedCommit {
+                commit_or_bytes,
+                tmp_commit_guard,
+                log_store: this.log_store,
+                table_data: this.table_data,
+                num_conflict_resolutions: 0,
+                conflict_check_duration: Duration::ZERO,
+                data: this.data,
+                streamed_actions,
+                operation_id: this.operation_id,
+                conflict_cache,
+                started,
+                intent,
+                metrics,
+                commit_stats,
+                secondary,
+                options: this.options,
+            })
+        });
+
+        match timeout {
+            Some(timeout) => Box::pin(async move {
+                tokio::time::timeout(timeout, prepare)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(TransactionError::Timeout {
+                            elapsed: started.elapsed(),
+                            attempts: 0,
+                        }
+                        .into())
+                    })
+            }),
+            None => prepare,
+        }
+    }
+}
+
+/// Represents a inflight commit
+///
+/// The commit can be cancelled by dropping it, or the future awaiting it, at any point. Its
+/// temporary commit file is then deleted in the background unless the log entry was already
+/// handed to the log store.
+pub struct PreparedCommit<'a> {
+    commit_or_bytes: CommitOrBytes,
+    /// Deletes the staged commit entry if the commit is dropped before it was written
+    tmp_commit_guard: TmpCommitGuard,
+    log_store: LogStoreRef,
+    data: CommitData,
+    table_data: Option<&'a dyn TableReference>,
+    num_conflict_resolutions: usize,
+    /// Time spent checking for conflicts across all attempts
+    conflict_check_duration: Duration,
+    /// Whether the actions of the commit data lack the streamed actions, which are only
+    /// part of the staged log entry
+    streamed_actions: bool,
+    operation_id: Uuid,
+    conflict_cache: HashMap<i64, WinningCommitSummary>,
+    started: Instant,
+    /// Identifies the staged log entry, set if it is recorded in the intent store or has to
+    /// be recognized in the log after a timeout
+    intent: Option<CommitIntent>,
+    /// Size of the commit, computed once when preparing it
+    metrics: CommitMetrics,
+    commit_stats: CommitStats,
+    /// The commit entry staged for the secondary log store, if any
+    secondary: Option<SecondaryCommit>,
+    options: CommitOptions,
+}
+
+/// Commit entry mirrored to a secondary log store once written to the primary one, see
+/// [`CommitBuilder::with_secondary_log_store`]
+struct SecondaryCommit {
+    log_store: LogStoreRef,
+    commit_or_bytes: CommitOrBytes,
+}
+
+impl SecondaryCommit {
+    /// Write the entry at the version the commit was written as to the primary log store, or
+    /// abort it if the commit failed or was already applied
+    async fn mirror(
+        self,
+        result: DeltaResult<PostCommit>,
+        attempts: &CommitAttempts,
+        operation_id: Uuid,
+    ) -> DeltaResult<PostCommit> {
+        let post_commit = match result {
+            Ok(post_commit) if !post_commit.already_committed => post_commit,
+            result => {
+                let version = attempts.version.load(Ordering::SeqCst);
+                self.abort(version, operation_id).await;
+                return result;
+            }
+        };
+        let version = post_commit.version;
+        if let Err(err) = self
+            .log_store
+            .write_commit_entry(version, self.commit_or_bytes.clone(), operation_id)
+            .await
+        {
+            warn!("Failed to mirror version {version} to the secondary log store: {err}");
+            self.abort(version, operation_id).await;
+            return Err(TransactionError::SecondaryCommitFailed {
+                version,
+                source: Box::new(err),
+            }
+            .into());
+        }
+        Ok(post_commit)
+    }
+
+    /// Abort the staged entry, failures are only logged as the outcome of the primary commit
+    /// is reported
+    async fn abort(&self, version: i64, operation_id: Uuid) {
+        if let Err(err) = self
+            .log_store
+            .abort_commit_entry(version, self.commit_or_bytes.clone(), operation_id)
+            .await
+        {
+            warn!("Failed to abort commit entry of secondary log store: {err}");
+        }
+    }
+}
+
+impl PreparedCommit<'_> {
+    /// The temporary commit file created
+    pub fn commit_or_bytes(&self) -> &CommitOrBytes {
+        &self.commit_or_bytes
+    }
+}
+
+/// Abort the commit entry after the commit failed with the given error and return the error
+/// to report. If the temporary commit file should be preserved, it is kept and its location
+/// added to the error instead. A failure to abort the entry is only logged, the original
+/// error is still reported.
+async fn abort_failed_commit_entry(
+    log_store: &LogStoreRef,
+    version: i64,
+    commit_or_bytes: CommitOrBytes,
+    operation_id: Uuid,
+    preserve_tmp_on_error: bool,
+    err: TransactionError,
+) -> DeltaTableError {
+    match commit_or_bytes {
+        CommitOrBytes::TmpCommit(path) if preserve_tmp_on_error => {
+            warn!("Preserving temporary commit {path} of failed commit");
+            TransactionError::TmpCommitPreserved {
+                path,
+                source: Box::new(err),
+            }
+            .into()
+        }
+        commit_or_bytes => match log_store
+            .abort_commit_entry(version, commit_or_bytes, operation_id)
+            .await
+        {
+            Ok(()) => err.into(),
+            Err(abort_err) => {
+                warn!("Failed to abort commit entry for version {version}: {abort_err}");
+                err.into()
+            }
+        },
+    }
+}
+
+/// Deletes the temporary commit file of a commit which is dropped before its log entry was
+/// written, e.g. because the commit future was cancelled.
+///
+/// The guard is disarmed while the log store is responsible for the entry, i.e. once the write
+/// was attempted, and for commits preserving their temporary file on error.
+struct TmpCommitGuard {
+    store: ObjectStoreRef,
+    path: Option<Path>,
+    armed: bool,
+}
+
+impl TmpCommitGuard {
+    fn new(
+        log_store: &LogStoreRef,
+        commit_or_bytes: &CommitOrBytes,
+        operation_id: Uuid,
+        armed: bool,
+    ) -> Self {
+        let path = match commit_or_bytes {
+            CommitOrBytes::TmpCommit(path) => Some(path.clone()),
+            _ => None,
+        };
+        Self {
+            store: log_store.object_store(Some(operation_id)),
+            path,
+            armed,
+        }
+    }
+}
+
+impl Drop for TmpCommitGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let Some(path) = self.path.take() else {
+            return;
+        };
+        // the file can only be deleted asynchronously, so the delete outlives the commit
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            warn!("Unable to delete temporary commit {path} of dropped commit without a runtime");
+            return;
+        };
+        let store = self.store.clone();
+        handle.spawn(async move {
+            match store.delete(&path).await {
+                Ok(()) | Err(ObjectStoreError::NotFound { .. }) => {
+                    debug!("Deleted temporary commit {path} of dropped commit")
+                }
+                Err(err) => {
+                    warn!("Failed to delete temporary commit {path} of dropped commit: {err}")
+                }
+            }
+        });
+    }
+}
+
+/// Tracks the attempts to write a commit entry, so they can be aborted on timeout
+#[derive(Debug, Default)]
+struct CommitAttempts {
+    attempts: AtomicUsize,
+    version: AtomicI64,
+}
+
+impl CommitAttempts {
+    /// Record an attempt to write the given version and return the number of the attempt
+    fn record(&self, version: i64) -> usize {
+        self.version.store(version, Ordering::SeqCst);
+        self.attempts.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+/// Stream the summaries of the commits within the given range of versions.
+///
+/// Versions without a commit file, e.g. because the log has already been cleaned up,
+/// are skipped.
+pub fn commit_summaries(
+    log_store: LogStoreRef,
+    versions: RangeInclusive<i64>,
+) -> BoxStream<'static, DeltaResult<(i64, WinningCommitSummary)>> {
+    futures::stream::iter(versions)
+        .filter_map(move |version| {
+            let log_store = log_store.clone();
+            async move {
+                match WinningCommitSummary::try_new(log_store.as_ref(), version - 1, version).await
+                {
+                    Ok(summary) => Some(Ok((version, summary))),
+                    Err(DeltaTableError::InvalidVersion(_)) => None,
+                    Err(err) => Some(Err(err)),
+                }
+            }
+        })
+        .boxed()
+}
+
+/// Precondition on the configuration of a metadata domain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainPrecondition {
+    /// The metadata domain
+    pub domain: String,
+    /// The expected configuration, `None` if the domain is expected to not exist
+    pub expected: Option<String>,
+}
+
+impl DomainPrecondition {
+    async fn check(&self, snapshot: &EagerSnapshot, log_store: &LogStoreRef) -> DeltaResult<()> {
+        let found = snapshot
+            .domain_metadata(log_store.object_store(None), &self.domain)
+            .await?
+            .map(|metadata| metadata.configuration);
+        if found != self.expected {
+            return Err(TransactionError::DomainPreconditionFailed {
+                domain: self.domain.clone(),
+                expected: self.expected.clone(),
+                found,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Precondition on the commit info timestamp of the latest table version
+#[derive(Clone)]
+pub struct CommitTimestampPrecondition(Arc<dyn Fn(i64) -> bool + Send + Sync>);
+
+impl std::fmt::Debug for CommitTimestampPrecondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CommitTimestampPrecondition")
+    }
+}
+
+impl CommitTimestampPrecondition {
+    /// Create a precondition from a predicate on the timestamp in milliseconds since the epoch
+    pub fn new(predicate: impl Fn(i64) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(predicate))
+    }
+
+    async fn check(&self, snapshot: &EagerSnapshot, log_store: &LogStoreRef) -> DeltaResult<()> {
+        let version = snapshot.version();
+        let timestamp = WinningCommitSummary::try_new(log_store.as_ref(), version - 1, version)
+            .await?
+            .commit_info
+            .and_then(|commit_info| commit_info.timestamp);
+        match timestamp {
+            Some(timestamp) if (self.0)(timestamp) => Ok(()),
+            _ => Err(
+                TransactionError::CommitTimestampPreconditionFailed { version, timestamp }.into(),
+            ),
+        }
+    }
+}
+
+fn check_expected_protocol(
+    expected: Option<&Protocol>,
+    snapshot: &EagerSnapshot,
+) -> Result<(), TransactionError> {
+    match expected {
+        Some(expected) if expected != snapshot.protocol() => {
+            Err(TransactionError::ProtocolMismatch {
+                expected: Box::new(expected.clone()),
+                found: Box::new(snapshot.protocol().clone()),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_removed_files_exist(
+    actions: &[Action],
+    snapshot: &EagerSnapshot,
+) -> Result<(), TransactionError> {
+    let mut unknown: HashSet<&str> = actions
+        .iter()
+        .filter_map(|action| match action {
+            Action::Remove(remove) => Some(remove.path.as_str()),
+            _ => None,
+        })
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    for file in snapshot.files() {
+        unknown.remove(file.path().as_ref());
+        if unknown.is_empty() {
+            return Ok(());
+        }
+    }
+    let mut paths: Vec<String> = unknown.into_iter().map(String::from).collect();
+    paths.sort();
+    Err(TransactionError::UnknownRemovedFiles {
+        version: snapshot.version(),
+        paths,
+    })
+}
+
+impl<'a> std::future::IntoFuture for PreparedCommit<'a> {
+    type Output = DeltaResult<PostCommit>;
+    type IntoFuture = BoxFuture<'a, Self::Output>;
+
+    fn into_future(mut self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let attempts = CommitAttempts::default();
+            if let Some(table_reference) = self.table_data {
+                let next_version = table_reference.eager_snapshot().version() + 1;
+                attempts.version.store(next_version, Ordering::SeqCst);
+            }
+            let secondary = self.secondary.take();
+            let operation_id = self.operation_id;
+
+            let outcome = match self.options.timeout {
+                Some(timeout) => {
+                    let remaining = timeout.saturating_sub(self.started.elapsed());
+                    match tokio::time::timeout(remaining, self.write_commit(&attempts)).await {
+                        Ok(outcome) => outcome,
+                        Err(_) => self.resolve_timeout(&attempts).await,
+                    }
+                }
+                None => self.write_commit(&attempts).await,
+            };
+            if outcome.is_err() {
+                self.clear_failed_intent(&attempts).await;
+            }
+            let result = outcome.map(|outcome| self.into_post_commit(outcome));
+
+            // the timeout only covers the primary log store, a slow secondary one must not
+            // report a timeout for a commit which is already durable
+            match secondary {
+                Some(secondary) => secondary.mirror(result, &attempts, operation_id).await,
+                None => result,
+            }
+        })
+    }
+}
+
+/// Outcome of writing the commit entry of a [`PreparedCommit`]
+enum CommitOutcome {
+    /// The commit created the table
+    Created,
+    /// The commit entry was written at the given version, or the table already contained the
+    /// app transactions of the commit
+    Committed {
+        version: i64,
+        read_snapshot: EagerSnapshot,
+        num_retries: u64,
+        already_committed: bool,
+    },
+}
+
+/// Result of a single attempt to write the commit entry
+enum AttemptOutcome {
+    /// The commit entry was written at the given version
+    Committed(i64),
+    /// Another writer won the race for the version, the commit can be retried
+    Retry,
+    /// The commit at the given version already contains the app transactions of the commit
+    AlreadyCommitted(i64),
+}
+
+/// Whether the table already contains all app transactions of a commit at an equal or higher
+/// version, i.e. the commit was applied before. Commits without app transactions are never
+/// considered committed.
+fn app_transactions_committed(
+    app_transactions: &[Transaction],
+    committed: impl IntoIterator<Item = Transaction>,
+) -> bool {
+    if app_transactions.is_empty() {
+        return false;
+    }
+    let committed: HashMap<String, i64> = committed
+        .into_iter()
+        .map(|txn| (txn.app_id, txn.version))
+        .collect();
+    app_transactions.iter().all(|txn| {
+        committed
+            .get(&txn.app_id)
+            .is_some_and(|version| *version >= txn.version)
+    })
+}
+
+impl<'a> PreparedCommit<'a> {
+    /// Write the commit entry, checking for conflicts and retrying if another writer won the
+    /// race for a version
+    async fn write_commit(&mut self, attempts: &CommitAttempts) -> DeltaResult<CommitOutcome> {
+        let started = self.started;
+
+        if self.table_data.is_none() {
+            let attempt = attempts.record(0);
+            self.tmp_commit_guard.armed = false;
+            let result = self
+                .log_store
+                .write_commit_entry(0, self.commit_or_bytes.clone(), self.operation_id)
+                .await;
+            self.observe_attempt(attempt, 0, &result);
+            result?;
+            return Ok(CommitOutcome::Created);
+        }
+
+        // unwrap() is safe here due to the above check
+        let mut read_snapshot = self.table_data.unwrap().eager_snapshot().clone();
+        check_expected_protocol(self.options.expected_protocol.as_ref(), &read_snapshot)?;
+        if let Some(precondition) = &self.options.domain_precondition {
+            precondition.check(&read_snapshot, &self.log_store).await?;
+        }
+        if let Some(precondition) = &self.options.timestamp_precondition {
+            precondition.check(&read_snapshot, &self.log_store).await?;
+        }
+        if self.options.validate_removes {
+            self.load_streamed_actions().await?;
+            check_removed_files_exist(&self.data.actions, &read_snapshot)?;
+        }
+        let already_committed = match read_snapshot.transactions() {
+            Ok(committed) => app_transactions_committed(&self.data.app_transactions, committed),
+            Err(err) => {
+                debug!("Unable to check for committed app transactions: {err}");
+                false
+            }
+        };
+        if already_committed {
+            info!(
+                "Table version {} already contains the app transactions, skipping commit",
+                read_snapshot.version()
+            );
+            self.discard_commit_entry().await?;
+            return Ok(CommitOutcome::Committed {
+                version: read_snapshot.version(),
+                read_snapshot,
+                num_retries: 0,
+                already_committed: true,
+            });
+        }
+
+        let mut attempt_number = 1;
+        let total_retries = self.options.max_write_retries + 1;
+        while attempt_number <= total_retries {
+            let span = info_span!(
+                "commit_attempt",
+                attempt = attempt_number,
+                read_version = read_snapshot.version(),
+                latest_version = field::Empty,
+                outcome = field::Empty,
+            );
+            let result = self
+                .attempt(&mut read_snapshot, attempts)
+                .instrument(span.clone())
+                .await;
+            span.record(
+                "outcome",
+                match &result {
+                    Ok(AttemptOutcome::Committed(_)) => "committed",
+                    Ok(AttemptOutcome::Retry) => "conflict_retry",
+                    Ok(AttemptOutcome::AlreadyCommitted(_)) => "already_committed",
+                    Err(_) => "failed",
+                },
+            );
+
+            match result? {
+                AttemptOutcome::Committed(version) => {
+                    return Ok(CommitOutcome::Committed {
+                        version,
+                        read_snapshot,
+                        num_retries: attempt_number as u64 - 1,
+                        already_committed: false,
+                    });
+                }
+                AttemptOutcome::AlreadyCommitted(version) => {
+                    info!("Table version {version} already contains the app transactions, skipping commit");
+                    self.discard_commit_entry().await?;
+                    return Ok(CommitOutcome::Committed {
+                        version,
+                        read_snapshot,
+                        num_retries: attempt_number as u64 - 1,
+                        already_committed: true,
+                    });
+                }
+                AttemptOutcome::Retry => {
+                    attempt_number += 1;
+                    if attempt_number > total_retries {
+                        break;
+                    }
+                    let delay = self.options.retry_backoff.delay(attempt_number - 1);
+                    if let Some(max_elapsed) = self.options.retry_backoff.max_elapsed {
+                        if started.elapsed() + delay > max_elapsed {
+                            warn!("Giving up on the transaction after {:?}", started.elapsed());
+                            return Err(TransactionError::MaxElapsedExceeded {
+                                elapsed: started.elapsed(),
+                                attempts: attempt_number - 1,
+                            }
+                            .into());
+                        }
+                    }
+                    if !delay.is_zero() {
+                        debug!("Retrying the transaction in {delay:?}");
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        }
+
+        Err(TransactionError::MaxCommitAttempts(self.options.max_write_retries as i32).into())
+    }
+
+    /// Determine the outcome of a commit whose write timed out. The write of the last attempt
+    /// may still have landed, in which case the commit is durable and is reported as such.
+    /// Otherwise the staged entry is aborted and the timeout is reported.
+    async fn resolve_timeout(&mut self, attempts: &CommitAttempts) -> DeltaResult<CommitOutcome> {
+        let version = attempts.version.load(Ordering::SeqCst);
+        let num_attempts = attempts.attempts.load(Ordering::SeqCst);
+        // the staged entry is either written or aborted below
+        self.tmp_commit_guard.armed = false;
+
+        if num_attempts > 0 {
+            match self.landed(version).await {
+                Ok(true) => {
+                    info!("Version {version} was written before the commit timed out");
+                    return match self.table_data {
+                        None => Ok(CommitOutcome::Created),
+                        Some(table_reference) => {
+                            let mut read_snapshot = table_reference.eager_snapshot().clone();
+                            read_snapshot
+                                .update(self.log_store.clone(), Some(version - 1))
+                                .await?;
+                            Ok(CommitOutcome::Committed {
+                                version,
+                                read_snapshot,
+                                num_retries: num_attempts as u64 - 1,
+                                already_committed: false,
+                            })
+                        }
+                    };
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    // the entry may have landed, so it must not be aborted
+                    warn!("Unable to check if version {version} was written before the commit timed out: {err}");
+                    return Err(TransactionError::Timeout {
+                        elapsed: self.started.elapsed(),
+                        attempts: num_attempts,
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let err = TransactionError::Timeout {
+            elapsed: self.started.elapsed(),
+            attempts: num_attempts,
+        };
+        Err(abort_failed_commit_entry(
+            &self.log_store,
+            version,
+            self.commit_or_bytes.clone(),
+            self.operation_id,
+            self.options.preserve_tmp_on_error,
+            err,
+        )
+        .await)
+    }
+
+    /// Whether the log entry at the given version is the staged entry of this commit
+    async fn landed(&self, version: i64) -> DeltaResult<bool> {
+        let Some(intent) = &self.intent else {
+            return Ok(false);
+        };
+        Ok(self
+            .log_store
+            .read_commit_entry(version)
+            .await?
+            .is_some_and(|log_entry| intent.matches(&log_entry)))
+    }
+
+    /// Clear the intent of a failed commit, unless its log entry may have been written
+    /// anyway and the commit has to be reconciled
+    async fn clear_failed_intent(&self, attempts: &CommitAttempts) {
+        let Some(intent_store) = &self.options.intent_store else {
+            return;
+        };
+        if attempts.attempts.load(Ordering::SeqCst) > 0 {
+            let version = attempts.version.load(Ordering::SeqCst);
+            if !matches!(self.landed(version).await, Ok(false)) {
+                return;
+            }
+        }
+        if let Err(err) = intent_store.clear(self.operation_id).await {
+            warn!(
+                "Failed to clear the commit intent of operation {}: {err}",
+                self.operation_id
+            );
+        }
+    }
+
+    fn into_post_commit(mut self, outcome: CommitOutcome) -> PostCommit {
+        self.tmp_commit_guard.armed = false;
+        let (version, read_snapshot, num_retries, already_committed) = match outcome {
+            CommitOutcome::Created => {
+                // creating the table only writes the checksum, if enabled
+                let write_crc = self.options.post_commit_hook.is_some_and(|v| v.write_crc);
+                self.options.post_commit_hook = Some(PostCommitHookProperties {
+                    write_crc,
+                    ..PostCommitHookProperties::disabled()
+                });
+                return PostCommit {
+                    version: 0,
+                    data: self.data,
+                    streamed_actions: self.streamed_actions,
+                    operation_id: self.operation_id,
+                    log_store: self.log_store,
+                    table_data: None,
+                    metrics: self.metrics,
+                    commit_stats: self.commit_stats,
+                    already_committed: false,
+                    options: self.options,
+                };
+            }
+            CommitOutcome::Committed {
+                version,
+                read_snapshot,
+                num_retries,
+                already_committed,
+            } => (version, read_snapshot, num_retries, already_committed),
+        };
+        PostCommit {
+            version,
+            data: self.data,
+            streamed_actions: self.streamed_actions,
+            operation_id: self.operation_id,
+            log_store: self.log_store,
+            table_data: Some(Box::new(read_snapshot)),
+            commit_stats: self.commit_stats,
+            metrics: CommitMetrics {
+                num_retries,
+                conflict_check_duration_ms: self.conflict_check_duration.as_millis() as u64,
+                ..self.metrics
+            },
+            already_committed,
+            options: self.options,
+        }
+    }
+
+    /// Report an attempt to write the commit entry to the attempt observer, if any
+    fn observe_attempt(&self, attempt: usize, version: i64, result: &Result<(), TransactionError>) {
+        let Some(observer) = &self.options.attempt_observer else {
+            return;
+        };
+        let info = AttemptInfo {
+            attempt,
+            version,
+            result: match result {
+                Ok(()) => AttemptResult::Ok,
+                Err(TransactionError::VersionAlreadyExists(_)) => AttemptResult::VersionConflict,
+                Err(_) => AttemptResult::OtherError,
+            },
+        };
+        let observed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| observer(info)));
+        if observed.is_err() {
+            warn!("Commit attempt observer panicked for version {version}");
+        }
+    }
+
+    /// Load the actions of a commit built from an action stream back from the staged log
+    /// entry, as the streamed actions are not kept in memory when preparing the commit
+    async fn load_streamed_actions(&mut self) -> DeltaResult<()> {
+        if !self.streamed_actions {
+            return Ok(());
+        }
+        let log_entry = match &self.commit_or_bytes {
+            CommitOrBytes::LogBytes(bytes) => bytes.clone(),
+            CommitOrBytes::TmpCommit(tmp_commit) => {
+                self.log_store
+                    .object_store(Some(self.operation_id))
+                    .get(tmp_commit)
+                    .await?
+                    .bytes()
+                    .await?
+            }
+        };
+        let version = self.table_data.map_or(0, |table_reference| {
+            table_reference.eager_snapshot().version() + 1
+        });
+        self.data.actions = get_actions(version, log_entry).await?;
+        self.streamed_actions = false;
+        Ok(())
+    }
+
+    /// Remove the temporary commit file of a commit which will not be written
+    async fn discard_commit_entry(&self) -> DeltaResult<()> {
+        if let CommitOrBytes::TmpCommit(tmp_commit) = &self.commit_or_bytes {
+            self.log_store
+                .object_store(Some(self.operation_id))
+                .delete(tmp_commit)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Run a single commit attempt, checking the transaction against any commits that
+    /// landed since `read_snapshot` before writing the commit entry.
+    async fn attempt(
+        &mut self,
+        read_snapshot: &mut EagerSnapshot,
+        attempts: &CommitAttempts,
+    ) -> DeltaResult<AttemptOutcome> {
+        let latest_version = self
+            .log_store
+            .get_latest_version(read_snapshot.version())
+            .await?;
+        Span::current().record("latest_version", latest_version);
+
+        if latest_version > read_snapshot.version() {
+            // Do not use the conflict checker to resolve the conflict once the maximum
+            // number of resolutions is reached and throw immediately
+            if self.num_conflict_resolutions >= self.options.max_conflict_resolutions {
+                return Err(TransactionError::MaxConflictResolutions(
+                    self.options.max_conflict_resolutions,
+                )
+                .into());
+            }
+            self.num_conflict_resolutions += 1;
+            warn!("Attempting to write a transaction {} but the underlying table has been updated to {latest_version}\n{:?}", read_snapshot.version() + 1, self.log_store);
+            if self.skips_conflict_checks() {
+                self.advance_blind_append(read_snapshot, latest_version)
+                    .await?;
+            } else {
+                let span = info_span!(
+                    "check_conflicts",
+                    from_version = read_snapshot.version() + 1,
+                    to_version = latest_version,
+                );
+                self.load_streamed_actions().await?;
+                let check_started = Instant::now();
+                let committed_version = self
+                    .check_conflicts(read_snapshot, latest_version)
+                    .instrument(span)
+                    .await;
+                self.conflict_check_duration += check_started.elapsed();
+                let committed_version = committed_version?;
+                if let Some(committed_version) = committed_version {
+                    read_snapshot
+                        .update(self.log_store.clone(), Some(committed_version))
+                        .await?;
+                    return Ok(AttemptOutcome::AlreadyCommitted(committed_version));
+                }
+                // Update snapshot to latest version after successful conflict check
+                read_snapshot
+                    .update(self.log_store.clone(), Some(latest_version))
+                    .await?;
+            }
+            check_expected_protocol(self.options.expected_protocol.as_ref(), read_snapshot)?;
+            if let Some(precondition) = &self.options.domain_precondition {
+                precondition.check(read_snapshot, &self.log_store).await?;
+            }
+            if let Some(precondition) = &self.options.timestamp_precondition {
+                precondition.check(read_snapshot, &self.log_store).await?;
+            }
+            if self.options.validate_removes {
+                check_removed_files_exist(&self.data.actions, read_snapshot)?;
+            }
+        }
+        let version: i64 = latest_version + 1;
+        let attempt = attempts.record(version);
+
+        // the log store takes over the staged entry, e.g. to recover incomplete commits
+        self.tmp_commit_guard.armed = false;
+        let result = self
+            .log_store
+            .write_commit_entry(version, self.commit_or_bytes.clone(), self.operation_id)
+            .await;
+        self.observe_attempt(attempt, version, &result);
+        match result {
+            Ok(()) => Ok(AttemptOutcome::Committed(version)),
+            Err(TransactionError::VersionAlreadyExists(version)) => {
+                error!("The transaction {version} already exists, will retry!");
+                self.tmp_commit_guard.armed = !self.options.preserve_tmp_on_error;
+                // If the version already exists, loop through again and re-check
+                // conflicts
+                Ok(AttemptOutcome::Retry)
+            }
+            Err(err) => Err(abort_failed_commit_entry(
+                &self.log_store,
+                version,
+                self.commit_or_bytes.clone(),
+                self.operation_id,
+                self.options.preserve_tmp_on_error,
+                err,
+            )
+            .await),
+        }
+    }
+
+    /// Whether the winning commits can be skipped when checking for conflicts.
+    ///
+    /// Blind appends only add files, so they commute with the data changes of other commits.
+    /// Commits with app transactions still read the winning commits to detect duplicates,
+    /// and custom conflict resolutions get to see every winning commit. The remaining checks
+    /// match the [BlindAppendConflictResolution].
+    fn skips_conflict_checks(&self) -> bool {
+        self.options.conflict_resolution.is_none()
+            && !self.streamed_actions
+            && self.data.operation.is_blind_append()
+            && only_adds_files(&self.data.actions)
+            && !self
+                .data
+                .actions
+                .iter()
+                .any(|action| matches!(action, Action::Txn(_)))
+    }
+
+    /// Advance the read snapshot of a blind append to the latest version, which only
+    /// conflicts with changes to the protocol or the metadata of the table
+    async fn advance_blind_append(
+        &self,
+        read_snapshot: &mut EagerSnapshot,
+        latest_version: i64,
+    ) -> DeltaResult<()> {
+        let read_protocol = read_snapshot.protocol().clone();
+        let read_metadata = read_snapshot.metadata().clone();
+        read_snapshot
+            .update(self.log_store.clone(), Some(latest_version))
+            .await?;
+        let source = if read_snapshot.protocol() != &read_protocol {
+            CommitConflictError::ProtocolChanged("protocol changed".into())
+        } else if read_snapshot.metadata() != &read_metadata {
+            CommitConflictError::MetadataChanged
+        } else {
+            return Ok(());
+        };
+        Err(TransactionError::CommitConflict {
+            source,
+            details: Box::new(ConflictDetails {
+                winning_version: latest_version,
+                ..Default::default()
+            }),
+        }
+        .into())
+    }
+
+    /// Check for conflicts with each version between the read snapshot and the latest version.
+    ///
+    /// The commits are fetched concurrently, but checked in order of their versions, so the
+    /// first conflicting commit is always the one reported.
+    ///
+    /// Returns the version of the first commit already containing the app transactions of
+    /// this commit, if any.
+    async fn check_conflicts(
+        &mut self,
+        read_snapshot: &EagerSnapshot,
+        latest_version: i64,
+    ) -> DeltaResult<Option<i64>> {
+        let versions = (read_snapshot.version() + 1)..=latest_version;
+        let mut cached: HashMap<i64, WinningCommitSummary> = versions
+            .clone()
+            .filter_map(|version| {
+                self.conflict_cache
+                    .remove(&version)
+                    .map(|summary| (version, summary))
+            })
+            .collect();
+        let log_store = self.log_store.clone();
+        let mut summaries = futures::stream::iter(versions)
+            .map(|winning_version| {
+                let cached = cached.remove(&winning_version);
+                let log_store = log_store.clone();
+                async move {
+                    let summary = match cached {
+                        Some(summary) => summary,
+                        None => {
+                            WinningCommitSummary::try_new(
+                                log_store.as_ref(),
+                                winning_version - 1,
+                                winning_version,
+                            )
+                            .await?
+                        }
+                    };
+                    Ok::<_, DeltaTableError>((winning_version, summary))
+                }
+            })
+            .buffered(self.options.conflict_check_concurrency);
+
+        // the transaction is checked against every winning commit, so it is only set up once
+        let mut transaction_info = TransactionInfo::try_new(
+            read_snapshot,
+            self.data.operation.read_predicate(),
+            &self.data.actions,
+            self.data.operation.read_whole_table(),
+        )?;
+        if let Some(partition_scope) = &self.options.partition_scope {
+            transaction_info = transaction_info.with_partition_scope(partition_scope);
+        }
+        let resolution = self
+            .options
+            .conflict_resolution
+            .as_deref()
+            .unwrap_or(&DefaultConflictResolution);
+
+        while let Some(next) = summaries.next().await {
+            let (winning_version, summary) = next?;
+            let committed = summary.actions.iter().filter_map(|action| match action {
+                Action::Txn(txn) => Some(txn.clone()),
+                _ => None,
+            });
+            if app_transactions_committed(&self.data.app_transactions, committed) {
+                return Ok(Some(winning_version));
+            }
+
+            if let Err(err) = resolution.resolve(
+                &PendingTransaction::new(&transaction_info),
+                &summary,
+                &self.data.operation,
+            ) {
+                let details =
+                    ConflictChecker::new(&transaction_info, &summary, Some(&self.data.operation))
+                        .conflict_details(winning_version, &err);
+                return Err(TransactionError::CommitConflict {
+                    source: err,
+                    details: Box::new(details),
+                }
+                .into());
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Represents items for the post commit hook
+pub struct PostCommit {
+    /// The winning version number of the commit
+    pub version: i64,
+    /// The data that was committed to the log store
+    pub data: CommitData,
+    streamed_actions: bool,
+    operation_id: Uuid,
+    log_store: LogStoreRef,
+    table_data: Option<Box<dyn TableReference>>,
+    metrics: CommitMetrics,
+    commit_stats: CommitStats,
+    already_committed: bool,
+    options: CommitOptions,
+}
+
+impl PostCommit {
+    /// Properties of the post commit hook, disabled if the commit was built without one
+    fn hook(&self) -> PostCommitHookProperties {
+        self.options
+            .post_commit_hook
+            .unwrap_or_else(PostCommitHookProperties::disabled)
+    }
+
+    /// Runs the post commit activities
+    async fn run_post_commit_hook(&self) -> DeltaResult<(DeltaTableState, PostCommitMetrics)> {
+        if let Some(table) = &self.table_data {
+            if self.already_committed {
+                // nothing was written, so there is nothing to advance or clean up
+                let snapshot = table.eager_snapshot().clone();
+                return Ok((DeltaTableState { snapshot }, PostCommitMetrics::default()));
+            }
+            let post_commit_operation_id = Uuid::new_v4();
+            let mut snapshot = table.eager_snapshot().clone();
+            if self.streamed_actions {
+                // the streamed actions are not kept in memory, so the commit is read back
+                snapshot
+                    .update(self.log_store.clone(), Some(self.version))
+                    .await?;
+            } else if self.version - snapshot.version() > 1 {
+                // This may only occur during concurrent write actions. We need to update the state first to - 1
+                // then we can advance.
+                snapshot
+                    .update(self.log_store.clone(), Some(self.version - 1))
+                    .await?;
+                snapshot.advance(vec![&self.data])?;
+            } else {
+                snapshot.advance(vec![&self.data])?;
+            }
+            let mut state = DeltaTableState { snapshot };
+            self.write_version_checksum(&state).await?;
+
+            let cleanup_logs = if let Some(cleanup_logs) = self.hook().cleanup_expired_logs {
+                cleanup_logs
+            } else {
+                state.table_config().enable_expired_log_cleanup()
+            };
+
+            // Run arbitrary before_post_commit_hook code
+            if let Some(custom_execute_handler) = &self.options.post_commit_hook_handler {
+                custom_execute_handler
+                    .before_post_commit_hook_with_metrics(
+                        &self.log_store,
+                        cleanup_logs || self.hook().create_checkpoint,
+                        post_commit_operation_id,
+                        &self.metrics,
+                    )
+                    .await?
+            }
+
+            let mut new_checkpoint_created = false;
+            let mut checkpoint_error = None;
+            if let Some(deferred) = self
+                .options
+                .deferred_checkpoint
+                .as_ref()
+                .filter(|_| self.hook().create_checkpoint)
+            {
+                if self.checkpoint_due(&state, self.version) {
+                    deferred.signal(&state, &self.log_store);
+                }
+            } else if self.hook().create_checkpoint {
+                // Execute create checkpoint hook
+                match self
+                    .create_checkpoint(
+                        &state,
+                        &self.log_store,
+                        self.version,
+                        post_commit_operation_id,
+                    )
+                    .await
+                {
+                    Ok(created) => new_checkpoint_created = created,
+                    Err(err) if self.hook().fail_on_error => return Err(err),
+                    Err(err) => {
+                        // The commit itself is durable, so don't fail it on a checkpoint error
+                        error!(
+                            "Failed to create checkpoint for version {}: {err}",
+                            self.version
+                        );
+                        checkpoint_error = Some(err.to_string());
+                    }
+                }
+            }
+
+            let mut num_log_files_cleaned_up: u64 = 0;
+            let mut cleanup_error = None;
+            if cleanup_logs {
+                let cutoff_timestamp = self.hook().log_cleanup_cutoff.unwrap_or_else(|| {
+                    Utc::now().timestamp_millis()
+                        - state.table_config().log_retention_duration().as_millis() as i64
+                });
+                // Execute clean up logs hook
+                match cleanup_expired_logs_for(
+                    self.version,
+                    self.log_store.as_ref(),
+                    cutoff_timestamp,
+                    Some(post_commit_operation_id),
+                )
+                .await
+                {
+                    Ok(cleaned_up) => num_log_files_cleaned_up = cleaned_up as u64,
+                    Err(err) if self.hook().fail_on_error => return Err(err.into()),
+                    Err(err) => {
+                        error!(
+                            "Failed to clean up expired logs for version {}: {err}",
+                            self.version
+                        );
+                        cleanup_error = Some(err.to_string());
+                    }
+                }
+                if num_log_files_cleaned_up > 0 {
+                    state = DeltaTableState::try_new(
+                        &state.snapshot().table_root(),
+                        self.log_store.object_store(None),
+                        state.load_config().clone(),
+                        Some(self.version),
+                    )
+                    .await?;
+                }
+            }
+
+            // Run arbitrary after_post_commit_hook code
+            if let Some(custom_execute_handler) = &self.options.post_commit_hook_handler {
+                custom_execute_handler
+                    .after_post_commit_hook_with_metrics(
+                        &self.log_store,
+                        cleanup_logs || self.hook().create_checkpoint,
+                        post_commit_operation_id,
+                        &self.metrics,
+                    )
+                    .await?
+            }
+            Ok((
+                state,
+                PostCommitMetrics {
+                    new_checkpoint_created,
+                    num_log_files_cleaned_up,
+                    checkpoint_error,
+                    cleanup_error,
+                },
+            ))
+        } else {
+            let state = DeltaTableState::try_new(
+                &Path::default(),
+                self.log_store.object_store(None),
+                Default::default(),
+                Some(self.version),
+            )
+            .await?;
+            self.write_version_checksum(&state).await?;
+            Ok((
+                state,
+                PostCommitMetrics {
+                    new_checkpoint_created: false,
+                    num_log_files_cleaned_up: 0,
+                    checkpoint_error: None,
+                    cleanup_error: None,
+                },
+            ))
+        }
+    }
+    /// Whether the given version of the table should be checkpointed
+    fn checkpoint_due(&self, table_state: &DeltaTableState, version: i64) -> bool {
+        if !table_state.load_config().require_files {
+            warn!("Checkpoint creation in post_commit_hook has been skipped due to table being initialized without files.");
+            return false;
+        }
+
+        let checkpoint_interval = self
+            .hook()
+            .checkpoint_interval
+            .filter(|interval| *interval > 0)
+            .unwrap_or_else(|| table_state.config().checkpoint_interval() as i64);
+        ((version + 1) % checkpoint_interval) == 0
+    }
+
+    /// Write the checksum file of the committed version, if enabled
+    async fn write_version_checksum(&self, state: &DeltaTableState) -> DeltaResult<()> {
+        if !self.hook().write_crc {
+            return Ok(());
+        }
+        let checksum = VersionChecksum::from_snapshot(state.snapshot(), Some(self.operation_id));
+        match write_version_checksum(self.log_store.as_ref(), self.version, &checksum).await {
+            Ok(()) => Ok(()),
+            Err(err) if self.hook().fail_on_error => Err(err),
+            Err(err) => {
+                error!(
+                    "Failed to write version checksum for version {}: {err}",
+                    self.version
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn create_checkpoint(
+        &self,
+        table_state: &DeltaTableState,
+        log_store: &LogStoreRef,
+        version: i64,
+        operation_id: Uuid,
+    ) -> DeltaResult<bool> {
+        if !self.checkpoint_due(table_state, version) {
+            return Ok(false);
+        }
+
+        let mut attempt: u32 = 0;
+        loop {
+            match create_checkpoint_for(
+                version,
+                table_state,
+                log_store.as_ref(),
+                Some(operation_id),
+            )
+            .await
+            {
+                Ok(()) => return Ok(true),
+                Err(err) if (attempt as usize) < self.hook().checkpoint_retries => {
+                    let backoff = checkpoint_retry_backoff(attempt);
+                    attempt = attempt.saturating_add(1);
+                    warn!("Failed to create checkpoint for version {version}, retrying in {backoff:?}: {err}");
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+/// A commit that successfully completed
+pub struct FinalizedCommit {
+    /// The new table state after a commit
+    pub snapshot: DeltaTableState,
+
+    /// Version of the finalized commit
+    pub version: i64,
+
+    /// Path of the log file of the commit, relative to the table root
+    pub log_path: Path,
+
+    /// Metrics associated with the commit operation
+    pub metrics: Metrics,
+
+    /// The operation that was committed
+    operation: DeltaOperation,
+
+    /// Summary of the files added and removed by the commit, empty if nothing was written
+    /// since the table already contained the app transactions of the commit
+    commit_stats: CommitStats,
+}
+
+impl FinalizedCommit {
+    /// The new table state after a commit
+    pub fn snapshot(&self) -> DeltaTableState {
+        self.snapshot.clone()
+    }
+    /// Version of the finalized commit
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+    /// Summary of the files added and removed by the commit, empty if nothing was written
+    /// since the table already contained the app transactions of the commit
+    pub fn commit_stats(&self) -> CommitStats {
+        self.commit_stats
+    }
+    /// Path of the log file of the commit, relative to the table root
+    pub fn log_path(&self) -> &Path {
+        &self.log_path
+    }
+
+    /// The operation that was committed
+    pub fn operation(&self) -> &DeltaOperation {
+        &self.operation
+    }
+}
+
+impl std::future::IntoFuture for PostCommit {
+    type Output = DeltaResult<FinalizedCommit>;
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            let result = this.run_post_commit_hook().await;
+            // the commit is durable at this point, even if the post commit hook failed
+            if let Some(intent_store) = &this.options.intent_store {
+                if let Err(err) = intent_store.clear(this.operation_id).await {
+                    warn!(
+                        "Failed to clear the commit intent of operation {}: {err}",
+                        this.operation_id
+                    );
+                }
+            }
+            let (snapshot, post_commit_metrics) = result?;
+            let finalized = FinalizedCommit {
+                snapshot,
+                version: this.version,
+                log_path: commit_uri_from_version(this.version),
+                metrics: Metrics {
+                    num_retries: this.metrics.num_retries,
+                    num_actions: this.metrics.num_actions,
+                    commit_size_bytes: this.metrics.commit_size_bytes,
+                    conflict_check_duration_ms: this.metrics.conflict_check_duration_ms,
+                    new_checkpoint_created: post_commit_metrics.new_checkpoint_created,
+                    num_log_files_cleaned_up: post_commit_metrics.num_log_files_cleaned_up,
+                    checkpoint_error: post_commit_metrics.checkpoint_error,
+                    cleanup_error: post_commit_metrics.cleanup_error,
+                    already_committed: this.already_committed,
+                },
+                operation: this.data.operation,
+                commit_stats: if this.already_committed {
+                    CommitStats::default()
+                } else {
+                    this.commit_stats
+                },
+            };
+            if let Some(observer) = &this.options.finalized_observer {
+                let observed =
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| observer(&finalized)));
+                if observed.is_err() {
+                    warn!(
+                        "Finalized commit observer panicked for version {}",
+                        finalized.version
+                    );
+                }
+            }
+            Ok(finalized)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::logstore::{default_logstore::DefaultLogStore, LogStore};
+    use crate::test_utils::{append_operation, create_test_table, TestLogStore, TestObjectStore};
+    use futures::TryStreamExt;
+    use object_store::{memory::InMemory, ObjectStore, PutPayload};
+    use url::Url;
+
+    #[test]
+    fn test_commit_uri_from_version() {
+        let version = commit_uri_from_version(0);
+        assert_eq!(version, Path::from("_delta_log/00000000000000000000.json"));
+        let version = commit_uri_from_version(123);
+        assert_eq!(version, Path::from("_delta_log/00000000000000000123.json"))
+    }
+
+    #[test]
+    fn test_max_retries_from_env() {
+        // a dedicated variable, as the process wide default is only read once
+        const VAR: &str = "DELTARS_MAX_COMMIT_RETRIES_TEST";
+        std::env::set_var(VAR, "3");
+        assert_eq!(max_retries_from_env(VAR), 3);
+        std::env::set_var(VAR, "many");
+        assert_eq!(max_retries_from_env(VAR), DEFAULT_RETRIES);
+        std::env::remove_var(VAR);
+        assert_eq!(max_retries_from_env(VAR), DEFAULT_RETRIES);
+
+        // explicitly configured retries take precedence over the default
+        let builder = CommitBuilder::from(CommitProperties::default().with_max_retries(1));
+        assert_eq!(builder.options.max_write_retries, 1);
+        assert_eq!(
+            CommitBuilder::default().options.max_write_retries,
+            default_max_retries()
+        );
+    }
+
+    #[test]
+    fn test_estimated_size() {
+        let operation = DeltaOperation::Write {
+            mode: SaveMode::Overwrite,
+            partition_by: None,
+            predicate: None,
+        };
+        let actions = vec![
+            Action::Add(crate::kernel::Add {
+                path: "new.parquet".to_string(),
+                size: 1024,
+                data_change: true,
+                stats: Some("{\"numRecords\":10}".to_string()),
+                ..Default::default()
+            }),
+            Action::Remove(crate::kernel::Remove {
+                path: "old.parquet".to_string(),
+                data_change: true,
+                ..Default::default()
+            }),
+        ];
+        let data = CommitData::new(
+            actions,
+            operation.clone(),
+            HashMap::from_iter([("userName".to_string(), Value::from("me"))]),
+            vec![Transaction::new("app", 1)],
+        );
+        assert_eq!(
+            data.estimated_size().unwrap(),
+            data.get_bytes().unwrap().len()
+        );
+
+        let empty = CommitData::new_without_commit_info(vec![], operation, HashMap::new(), vec![]);
+        assert_eq!(empty.estimated_size().unwrap(), 0);
+        assert_eq!(empty.get_bytes().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_try_commit_transaction() {
+        let store = Arc::new(InMemory::new());
+        let url = Url::parse("mem://what/is/this").unwrap();
+        let log_store = DefaultLogStore::new(
+            store.clone(),
+            crate::logstore::LogStoreConfig {
+                location: url,
+                options: Default::default(),
+            },
+        );
+        let version_path = Path::from("_delta_log/00000000000000000000.json");
+        store.put(&version_path, PutPayload::new()).await.unwrap();
+
+        let res = log_store
+            .write_commit_entry(
+                0,
+                CommitOrBytes::LogBytes(PutPayload::new().into()),
+                Uuid::new_v4(),
+            )
+            .await;
+        // fails if file version already exists
+        assert!(res.is_err());
+
+        // succeeds for next version
+        log_store
+            .write_commit_entry(
+                1,
+                CommitOrBytes::LogBytes(PutPayload::new().into()),
+                Uuid::new_v4(),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_commit_summaries() {
+        let table = create_test_table().await;
+
+        let summaries: Vec<_> = commit_summaries(table.log_store(), 0..=3)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(summaries.len(), 1);
+        let (version, summary) = &summaries[0];
+        assert_eq!(*version, 0);
+        assert_eq!(summary.protocol().len(), 1);
+        assert!(summary.touched_partitions().is_empty());
+
+        // the winning commit has to follow the read version
+        assert!(
+            WinningCommitSummary::try_new(table.log_store().as_ref(), 0, 0)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_retry_backoff_delay() {
+        let backoff = RetryBackoff::new(Duration::from_millis(10), 2.0, Duration::from_millis(50));
+        assert_eq!(backoff.delay(1), Duration::from_millis(10));
+        assert_eq!(backoff.delay(2), Duration::from_millis(20));
+        assert_eq!(backoff.delay(3), Duration::from_millis(40));
+        assert_eq!(backoff.delay(4), Duration::from_millis(50));
+        assert_eq!(RetryBackoff::default().delay(3), Duration::ZERO);
+
+        let jittered = backoff.with_jitter(true);
+        assert!(jittered.delay(3) <= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_retry_backoff_delay_does_not_overflow() {
+        let backoff = RetryBackoff::new(Duration::from_secs(1), 10.0, Duration::from_secs(60));
+        assert_eq!(backoff.delay(usize::MAX), Duration::from_secs(60));
+        assert_eq!(backoff.delay(1_000), Duration::from_secs(60));
+
+        let unbounded = RetryBackoff::new(Duration::MAX, 2.0, Duration::MAX);
+        assert_eq!(unbounded.delay(100), Duration::MAX);
+
+        let not_finite = RetryBackoff::new(Duration::from_secs(1), f64::NAN, Duration::MAX);
+        assert_eq!(not_finite.delay(2), Duration::ZERO);
+        let negative = RetryBackoff::new(Duration::from_secs(1), -2.0, Duration::MAX);
+        assert_eq!(negative.delay(2), Duration::ZERO);
+
+        let jittered = backoff.with_jitter(true);
+        assert!(jittered.delay(usize::MAX) <= Duration::from_secs(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_backoff_between_attempts() {
+        let table = create_test_table().await;
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).losing_every_race());
+        let operation = append_operation();
+
+        let result = CommitBuilder::default()
+            .with_max_retries(3)
+            .with_retry_backoff(RetryBackoff::new(
+                Duration::from_millis(20),
+                2.0,
+                Duration::from_secs(1),
+            ))
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation.clone(),
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap()
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::MaxCommitAttempts(3)
+            })
+        ));
+
+        {
+            let attempts = log_store.attempts();
+            assert_eq!(attempts.len(), 4);
+            for (retry, window) in attempts.windows(2).enumerate() {
+                let expected = Duration::from_millis(20 * 2u64.pow(retry as u32));
+                assert!(window[1] - window[0] >= expected);
+            }
+        }
+
+        // running out of time is reported separately from running out of retries
+        log_store.clear_attempts();
+        let result = CommitBuilder::default()
+            .with_max_retries(10)
+            .with_retry_backoff(
+                RetryBackoff::new(Duration::from_millis(20), 2.0, Duration::from_secs(1))
+                    .with_max_elapsed(Duration::from_millis(100)),
+            )
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation,
+            )
+            .await;
+        // attempts after 0, 20 and 60ms, the next one would only start after 140ms
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::MaxElapsedExceeded { attempts: 3, .. }
+            })
+        ));
+        assert_eq!(log_store.attempts().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_conflict_check_concurrency() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let add = |path: String| {
+            Action::Add(crate::kernel::Add {
+                path,
+                size: 100,
+                data_change: true,
+                ..Default::default()
+            })
+        };
+
+        // non-conflicting appends by other writers
+        let mut snapshot = table.snapshot().unwrap().clone();
+        for i in 0..20 {
+            snapshot = CommitBuilder::default()
+                .with_actions(vec![add(format!("other-{i}.parquet"))])
+                .build(Some(&snapshot), table.log_store(), operation.clone())
+                .await
+                .unwrap()
+                .snapshot();
+        }
+
+        let log_store = Arc::new(
+            TestLogStore::new(table.log_store()).with_read_delay(Duration::from_millis(10)),
+        );
+        // app transactions are checked against the winning commits, unlike blind appends
+        let commit = CommitBuilder::default()
+            .with_actions(vec![
+                add("late.parquet".into()),
+                Action::Txn(Transaction::new("late", 1)),
+            ])
+            .with_conflict_check_concurrency(4)
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation,
+            )
+            .await
+            .unwrap();
+        assert_eq!(commit.version(), 21);
+        assert_eq!(commit.snapshot().files_count(), 21);
+        let max_in_flight = log_store.max_in_flight_reads();
+        assert!(max_in_flight > 1 && max_in_flight <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_conflict_check_duration() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let add = |path: String| {
+            Action::Add(crate::kernel::Add {
+                path,
+                size: 100,
+                data_change: true,
+                ..Default::default()
+            })
+        };
+
+        // no conflicts to check
+        let mut snapshot = table.snapshot().unwrap().clone();
+        let commit = CommitBuilder::default()
+            .with_actions(vec![add("first.parquet".into())])
+            .build(Some(&snapshot), table.log_store(), operation.clone())
+            .await
+            .unwrap();
+        assert_eq!(commit.metrics.conflict_check_duration_ms, 0);
+        assert_eq!(commit.metrics.num_retries, 0);
+
+        snapshot = commit.snapshot();
+        for i in 0..3 {
+            snapshot = CommitBuilder::default()
+                .with_actions(vec![add(format!("other-{i}.parquet"))])
+                .build(Some(&snapshot), table.log_store(), operation.clone())
+                .await
+                .unwrap()
+                .snapshot();
+        }
+
+        // every commit read while checking conflicts takes at least 10ms
+        let log_store = Arc::new(
+            TestLogStore::new(table.log_store()).with_read_delay(Duration::from_millis(10)),
+        );
+        let commit = CommitBuilder::default()
+            .with_actions(vec![
+                add("late.parquet".into()),
+                Action::Txn(Transaction::new("late", 1)),
+            ])
+            .build(Some(&commit.snapshot()), log_store, operation)
+            .await
+            .unwrap();
+        assert_eq!(commit.version(), 5);
+        assert!(commit.metrics.conflict_check_duration_ms >= 10);
+        // the conflicts were resolved within the first attempt
+        assert_eq!(commit.metrics.num_retries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_put_capability() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+
+        for conditional_put in [false, true] {
+            let log_store: LogStoreRef = Arc::new(
+                TestLogStore::new(table.log_store())
+                    .with_conditional_put(conditional_put)
+                    .rejecting(),
+            );
+            let prepared = CommitBuilder::default()
+                .build(
+                    Some(table.snapshot().unwrap()),
+                    log_store,
+                    operation.clone(),
+                )
+                .into_prepared_commit_future()
+                .await
+                .unwrap();
+            // stores with conditional puts are handed the bytes instead of a staged file
+            assert_eq!(
+                matches!(prepared.commit_or_bytes(), CommitOrBytes::LogBytes(_)),
+                conditional_put
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_commit_deletes_tmp_commit() {
+        let table = create_test_table().await;
+        let store = table.log_store().object_store(None);
+        let operation = append_operation();
+        let assert_deleted = |tmp_commit: Path| {
+            let store = store.clone();
+            async move {
+                // the temporary commit is deleted in the background
+                for _ in 0..50 {
+                    if matches!(
+                        store.head(&tmp_commit).await,
+                        Err(ObjectStoreError::NotFound { .. })
+                    ) {
+                        return;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                panic!("temporary commit {tmp_commit} was not deleted");
+            }
+        };
+
+        // dropped before the commit was attempted
+        let log_store: LogStoreRef = Arc::new(TestLogStore::new(table.log_store()));
+        let prepared = CommitBuilder::default()
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store,
+                operation.clone(),
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        let CommitOrBytes::TmpCommit(tmp_commit) = prepared.commit_or_bytes().clone() else {
+            panic!("expected a temporary commit");
+        };
+        assert!(store.head(&tmp_commit).await.is_ok());
+        drop(prepared);
+        assert_deleted(tmp_commit).await;
+
+        // cancelled while waiting to retry a lost race
+        let log_store: LogStoreRef = Arc::new(TestLogStore::new(table.log_store()).with_races(1));
+        let backoff = Duration::from_secs(60);
+        let prepared = CommitBuilder::default()
+            .with_retry_backoff(RetryBackoff::new(backoff, 1.0, backoff))
+            .build(Some(table.snapshot().unwrap()), log_store, operation)
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        let CommitOrBytes::TmpCommit(tmp_commit) = prepared.commit_or_bytes().clone() else {
+            panic!("expected a temporary commit");
+        };
+        let result = tokio::time::timeout(Duration::from_millis(100), prepared).await;
+        assert!(result.is_err());
+        assert_deleted(tmp_commit).await;
+    }
+
+    #[tokio::test]
+    async fn test_preserve_tmp_on_error() {
+        let table = create_test_table().await;
+        let log_store: LogStoreRef = Arc::new(TestLogStore::new(table.log_store()).rejecting());
+        let operation = append_operation();
+
+        let prepared = CommitBuilder::default()
+            .with_preserve_tmp_on_error(true)
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation.clone(),
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        let CommitOrBytes::TmpCommit(tmp_commit) = prepared.commit_or_bytes().clone() else {
+            panic!("expected a temporary commit");
+        };
+        let Err(DeltaTableError::Transaction {
+            source: TransactionError::TmpCommitPreserved { path, source },
+        }) = prepared.await
+        else {
+            panic!("expected the temporary commit to be preserved");
+        };
+        assert_eq!(path, tmp_commit);
+        assert!(matches!(*source, TransactionError::LogStoreError { .. }));
+        let store = log_store.object_store(None);
+        assert!(store.head(&tmp_commit).await.is_ok());
+
+        // by default, the temporary commit is deleted
+        let prepared = CommitBuilder::default()
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation,
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        let CommitOrBytes::TmpCommit(tmp_commit) = prepared.commit_or_bytes().clone() else {
+            panic!("expected a temporary commit");
+        };
+        assert!(matches!(
+            prepared.await,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::LogStoreError { .. }
+            })
+        ));
+        assert!(store.head(&tmp_commit).await.is_err());
+    }
+
+    /// Writer collecting the output of a tracing subscriber
+    #[derive(Clone, Default)]
+    struct CapturedOutput(Arc<parking_lot::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "datafusion")]
+    #[tokio::test]
+    async fn test_validate_removes() {
+        let table = crate::DeltaOps::new_in_memory()
+            .write(vec![crate::writer::test_utils::get_record_batch(
+                None, false,
+            )])
+            .await
+            .unwrap();
+        let snapshot = table.snapshot().unwrap();
+        let existing = snapshot.file_actions().unwrap()[0].path.clone();
+        let remove = |path: &str| {
+            Action::Remove(crate::kernel::Remove {
+                path: path.to_string(),
+                data_change: true,
+                ..Default::default()
+            })
+        };
+        let operation = DeltaOperation::Delete { predicate: None };
+
+        let result = CommitBuilder::from(CommitProperties::default().with_validate_removes(true))
+            .with_actions(vec![remove(&existing), remove("missing.parquet")])
+            .build(Some(snapshot), table.log_store(), operation.clone())
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::UnknownRemovedFiles { version: 0, paths }
+            }) if paths == vec!["missing.parquet".to_string()]
+        ));
+
+        let finalized =
+            CommitBuilder::from(CommitProperties::default().with_validate_removes(true))
+                .with_actions(vec![remove(&existing)])
+                .build(Some(snapshot), table.log_store(), operation)
+                .await
+                .unwrap();
+        assert_eq!(finalized.version(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_finalized_commit_log_path() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+
+        let mut snapshot = table.snapshot().unwrap().clone();
+        for (version, expected) in [
+            (1, "_delta_log/00000000000000000001.json"),
+            (2, "_delta_log/00000000000000000002.json"),
+        ] {
+            let finalized = CommitBuilder::default()
+                .build(Some(&snapshot), table.log_store(), operation.clone())
+                .await
+                .unwrap();
+            assert_eq!(finalized.version(), version);
+            assert_eq!(finalized.log_path().as_ref(), expected);
+            assert!(table
+                .log_store()
+                .object_store(None)
+                .head(finalized.log_path())
+                .await
+                .is_ok());
+            snapshot = finalized.snapshot();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_size_metrics() {
+        let table = create_test_table().await;
+        // the racing log store writes tmp commits and forces a retry
+        let log_store: LogStoreRef = Arc::new(TestLogStore::new(table.log_store()).with_races(1));
+        let actions = ["a.parquet", "b.parquet"]
+            .into_iter()
+            .map(|path| {
+                Action::Add(crate::kernel::Add {
+                    path: path.to_string(),
+                    data_change: true,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let operation = append_operation();
+
+        let prepared = CommitBuilder::default()
+            .with_actions(actions)
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation,
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        let CommitOrBytes::TmpCommit(tmp_path) = prepared.commit_or_bytes().clone() else {
+            panic!("expected a tmp commit");
+        };
+        let tmp_size = log_store
+            .object_store(None)
+            .head(&tmp_path)
+            .await
+            .unwrap()
+            .size;
+
+        let finalized = prepared.await.unwrap().await.unwrap();
+        assert_eq!(finalized.metrics.num_retries, 1);
+        // two adds and the commit info
+        assert_eq!(finalized.metrics.num_actions, 3);
+        assert_eq!(finalized.metrics.commit_size_bytes, tmp_size);
+    }
+
+    #[tokio::test]
+    async fn test_max_conflict_resolutions() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+
+        // every lost race is followed by a conflict resolution in the next attempt
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).with_races(3));
+        let result = CommitBuilder::from(
+            CommitProperties::default()
+                .with_max_write_retries(10)
+                .with_max_conflict_resolutions(2),
+        )
+        .build(
+            Some(table.snapshot().unwrap()),
+            log_store,
+            operation.clone(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::MaxConflictResolutions(2)
+            })
+        ));
+
+        let mut table = table;
+        table.update().await.unwrap();
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).with_races(3));
+        let finalized = CommitBuilder::from(
+            CommitProperties::default()
+                .with_max_write_retries(3)
+                .with_max_conflict_resolutions(3),
+        )
+        .build(Some(table.snapshot().unwrap()), log_store, operation)
+        .await
+        .unwrap();
+        assert_eq!(finalized.version(), table.version() + 4);
+        assert_eq!(finalized.metrics.num_retries, 3);
+    }
+
+    #[tokio::test]
+    async fn test_attempt_observer() {
+        let table = create_test_table().await;
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).with_races(2));
+        let operation = append_operation();
+        let observed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observer: AttemptObserverFn = {
+            let observed = observed.clone();
+            Arc::new(move |info| observed.lock().unwrap().push(info))
+        };
+
+        let finalized = CommitBuilder::default()
+            .with_max_retries(2)
+            .with_attempt_observer(observer)
+            .build(Some(table.snapshot().unwrap()), log_store, operation)
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 3);
+
+        let expected = [
+            (1, 1, AttemptResult::VersionConflict),
+            (2, 2, AttemptResult::VersionConflict),
+            (3, 3, AttemptResult::Ok),
+        ]
+        .map(|(attempt, version, result)| AttemptInfo {
+            attempt,
+            version,
+            result,
+        });
+        assert_eq!(*observed.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_commit_without_data_actions() {
+        let table = create_test_table().await;
+        let operation = DeltaOperation::StreamingUpdate {
+            output_mode: crate::protocol::OutputMode::Append,
+            query_id: "watermarks".into(),
+            epoch_id: 5,
+        };
+
+        // record a watermark without changing any files
+        let finalized = CommitBuilder::default()
+            .with_actions(vec![Action::Txn(Transaction::new("watermark", 5))])
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 1);
+        assert_eq!(finalized.metrics.num_actions, 2);
+        assert_eq!(finalized.commit_stats(), CommitStats::default());
+        let snapshot = finalized.snapshot();
+        assert_eq!(snapshot.files_count(), 0);
+        let versions: HashMap<_, _> = snapshot
+            .app_transaction_version()
+            .unwrap()
+            .map(|txn| (txn.app_id, txn.version))
+            .collect();
+        assert_eq!(versions.get("watermark"), Some(&5));
+
+        let log_entry = table
+            .log_store()
+            .read_commit_entry(1)
+            .await
+            .unwrap()
+            .unwrap();
+        let actions = get_actions(1, log_entry).await.unwrap();
+        assert_eq!(actions.len(), 2);
+        assert!(actions
+            .iter()
+            .any(|action| matches!(action, Action::CommitInfo(_))));
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            Action::Txn(txn) if txn.app_id == "watermark" && txn.version == 5
+        )));
+
+        // without a commit info there is nothing to commit
+        let result = CommitBuilder::default()
+            .with_auto_commit_info(false)
+            .build(Some(&snapshot), table.log_store(), operation)
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::EmptyCommit
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_append_only_fails_before_staging() {
+        let table = crate::DeltaOps::new_in_memory()
+            .create()
+            .with_column(
+                "value",
+                crate::kernel::DataType::Primitive(crate::kernel::PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .with_configuration_property(crate::TableProperty::AppendOnly, Some("true"))
+            .await
+            .unwrap();
+        let remove = Action::Remove(crate::kernel::Remove {
+            path: "part-00000.parquet".into(),
+            data_change: true,
+            ..Default::default()
+        });
+
+        // without conditional put, the commit would be staged as a temporary file
+        let log_store: LogStoreRef = Arc::new(TestLogStore::new(table.log_store()));
+        let result = CommitBuilder::default()
+            .with_actions(vec![remove])
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store,
+                DeltaOperation::Delete { predicate: None },
+            )
+            .into_prepared_commit_future()
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::DeltaTableAppendOnly
+            })
+        ));
+        let files = crate::logstore::tests::flatten_list_stream(
+            table.log_store().object_store(None).as_ref(),
+            Some(&Path::from(DELTA_LOG_FOLDER)),
+        )
+        .await
+        .unwrap();
+        assert!(files
+            .iter()
+            .all(|path| !path.as_ref().ends_with(".json.tmp")));
+    }
+
+    #[tokio::test]
+    async fn test_commit_timestamp() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let timestamp = 1_600_000_000_000;
+
+        let finalized =
+            CommitBuilder::from(CommitProperties::default().with_commit_timestamp(timestamp))
+                .build(
+                    Some(table.snapshot().unwrap()),
+                    table.log_store(),
+                    operation,
+                )
+                .await
+                .unwrap();
+        let log_entry = table
+            .log_store()
+            .read_commit_entry(finalized.version())
+            .await
+            .unwrap()
+            .unwrap();
+        let commit_info = get_actions(finalized.version(), log_entry)
+            .await
+            .unwrap()
+            .into_iter()
+            .find_map(|action| match action {
+                Action::CommitInfo(commit_info) => Some(commit_info),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(commit_info.timestamp, Some(timestamp));
+    }
+
+    #[tokio::test]
+    async fn test_commit_stats() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let add = |path: &str, stats: Option<&str>| {
+            Action::Add(crate::kernel::Add {
+                path: path.to_string(),
+                size: 100,
+                data_change: true,
+                stats: stats.map(String::from),
+                ..Default::default()
+            })
+        };
+
+        let finalized = CommitBuilder::default()
+            .with_actions(vec![
+                add("a.parquet", Some("{\"numRecords\":10}")),
+                add("b.parquet", None),
+            ])
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            finalized.commit_stats(),
+            CommitStats {
+                num_added_files: 2,
+                num_removed_files: 0,
+                num_added_rows: 10,
+            }
+        );
+
+        // files without statistics do not contribute to the number of rows
+        let finalized = CommitBuilder::default()
+            .with_actions(vec![
+                add("c.parquet", Some("{\"numRecords\":5,\"minValues\":{}}")),
+                add("d.parquet", Some("{\"numRecords\":7}")),
+                Action::Remove(crate::kernel::Remove {
+                    path: "a.parquet".to_string(),
+                    data_change: true,
+                    ..Default::default()
+                }),
+            ])
+            .build(Some(&finalized.snapshot()), table.log_store(), operation)
+            .await
+            .unwrap();
+        assert_eq!(
+            finalized.commit_stats(),
+            CommitStats {
+                num_added_files: 2,
+                num_removed_files: 1,
+                num_added_rows: 12,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_finalized_observer() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let observed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observer: FinalizedObserverFn = {
+            let observed = observed.clone();
+            Arc::new(move |finalized: &FinalizedCommit| {
+                observed.lock().unwrap().push((
+                    finalized.version(),
+                    finalized.snapshot.version(),
+                    finalized.metrics.new_checkpoint_created,
+                ))
+            })
+        };
+
+        let finalized =
+            CommitBuilder::from(CommitProperties::default().with_checkpoint_interval(2))
+                .with_finalized_observer(observer.clone())
+                .build(
+                    Some(table.snapshot().unwrap()),
+                    table.log_store(),
+                    operation.clone(),
+                )
+                .await
+                .unwrap();
+        assert_eq!(finalized.version(), 1);
+        assert_eq!(*observed.lock().unwrap(), vec![(1, 1, true)]);
+
+        // the observer is not invoked for failed commits
+        let result = CommitBuilder::default()
+            .with_max_retries(0)
+            .with_finalized_observer(observer)
+            .build(
+                Some(table.snapshot().unwrap()),
+                Arc::new(TestLogStore::new(table.log_store()).with_races(1)),
+                operation,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(observed.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_commit_attempt_spans() {
+        use tracing_subscriber::fmt::format::FmtSpan;
+
+        let table = create_test_table().await;
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).with_races(2));
+        let operation = append_operation();
+
+        let output = CapturedOutput::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .without_time()
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let finalized = CommitBuilder::default()
+            .with_max_retries(2)
+            .build(Some(table.snapshot().unwrap()), log_store, operation)
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 3);
+
+        // every closed span is logged with its recorded fields, prefixed by its parents
+        let output = String::from_utf8(output.0.lock().clone()).unwrap();
+        let closed: Vec<(&str, &str)> = output
+            .lines()
+            .filter(|line| line.contains("close time.busy"))
+            .filter_map(|line| {
+                let (spans, _) = line.split_once("}: ")?;
+                let span = spans.rsplit(':').next()?;
+                let name = span.split('{').next()?.rsplit(' ').next()?;
+                Some((name, line))
+            })
+            .collect();
+        let commit_attempts: Vec<_> = closed
+            .iter()
+            .filter(|(name, _)| *name == "commit_attempt")
+            .map(|(_, line)| line)
+            .collect();
+        assert_eq!(commit_attempts.len(), 3);
+        let expected = [
+            ("1", "0", "0", "conflict_retry"),
+            ("2", "0", "1", "conflict_retry"),
+            ("3", "1", "2", "committed"),
+        ];
+        for (line, (attempt, read_version, latest_version, outcome)) in
+            commit_attempts.iter().zip(expected)
+        {
+            for field in [
+                format!("{{attempt={attempt} "),
+                format!(" read_version={read_version} "),
+                format!(" latest_version={latest_version} "),
+                format!(" outcome=\"{outcome}\""),
+            ] {
+                assert!(line.contains(&field), "{field} missing in {line}");
+            }
+        }
+
+        let conflict_checks: Vec<_> = closed
+            .iter()
+            .filter(|(name, _)| *name == "check_conflicts")
+            .collect();
+        assert_eq!(conflict_checks.len(), 2);
+        assert!(conflict_checks
+            .iter()
+            .all(|(_, line)| line.contains("}:check_conflicts{")));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_interval_override() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+
+        // version 1 is not a checkpoint version with the default interval of 100
+        let finalized =
+            CommitBuilder::from(CommitProperties::default().with_checkpoint_interval(2))
+                .build(
+                    Some(table.snapshot().unwrap()),
+                    table.log_store(),
+                    operation,
+                )
+                .await
+                .unwrap();
+        assert_eq!(finalized.version(), 1);
+        assert!(finalized.metrics.new_checkpoint_created);
+        let checkpoint_path = Path::from("_delta_log/00000000000000000001.checkpoint.parquet");
+        assert!(table
+            .log_store()
+            .object_store(None)
+            .head(&checkpoint_path)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_log_cleanup_cutoff() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        // checkpoint version 1, so the log of version 0 can be cleaned up
+        let finalized =
+            CommitBuilder::from(CommitProperties::default().with_checkpoint_interval(2))
+                .build(
+                    Some(table.snapshot().unwrap()),
+                    table.log_store(),
+                    operation.clone(),
+                )
+                .await
+                .unwrap();
+        assert!(finalized.metrics.new_checkpoint_created);
+        let first_commit = Path::from("_delta_log/00000000000000000000.json");
+        let store = table.log_store().object_store(None);
+
+        // nothing is old enough for a cutoff far in the past
+        let finalized = CommitBuilder::from(
+            CommitProperties::default()
+                .with_cleanup_expired_logs(Some(true))
+                .with_log_cleanup_cutoff(0),
+        )
+        .build(
+            Some(&finalized.snapshot()),
+            table.log_store(),
+            operation.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(finalized.metrics.num_log_files_cleaned_up, 0);
+        assert!(store.head(&first_commit).await.is_ok());
+
+        // everything before the checkpoint is expired for a cutoff far in the future
+        let finalized = CommitBuilder::from(
+            CommitProperties::default()
+                .with_cleanup_expired_logs(Some(true))
+                .with_log_cleanup_cutoff(i64::MAX),
+        )
+        .build(Some(&finalized.snapshot()), table.log_store(), operation)
+        .await
+        .unwrap();
+        assert_eq!(finalized.metrics.num_log_files_cleaned_up, 1);
+        assert!(store.head(&first_commit).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_protocol_check() {
+        let protocol = Protocol {
+            min_reader_version: 1,
+            min_writer_version: 7,
+            reader_features: None,
+            writer_features: Some([crate::kernel::WriterFeature::ColumnMapping].into()),
+        };
+        let table = crate::DeltaOps::new_in_memory()
+            .create()
+            .with_column(
+                "value",
+                crate::kernel::DataType::Primitive(crate::kernel::PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .with_actions(vec![Action::Protocol(protocol)])
+            .await
+            .unwrap();
+        let operation = append_operation();
+
+        let result = CommitBuilder::default()
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation.clone(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::UnsupportedWriterFeatures(_)
+            })
+        ));
+
+        let finalized = CommitBuilder::default()
+            .with_protocol_check(false)
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation,
+            )
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_checkpoint() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let manager = DeferredCheckpointManager::new();
+        let checkpoint_exists = |version: i64| {
+            let log_store = table.log_store();
+            async move {
+                let path = Path::from(format!("_delta_log/{version:020}.checkpoint.parquet"));
+                log_store.object_store(None).head(&path).await.is_ok()
+            }
+        };
+
+        // versions 1 and 3 are due for a checkpoint, but both are deferred
+        let mut snapshot = table.snapshot().unwrap().clone();
+        for version in 1..=3 {
+            let finalized = CommitBuilder::from(
+                CommitProperties::default()
+                    .with_checkpoint_interval(2)
+                    .with_deferred_checkpoint(manager.clone()),
+            )
+            .build(Some(&snapshot), table.log_store(), operation.clone())
+            .await
+            .unwrap();
+            assert_eq!(finalized.version(), version);
+            assert!(!finalized.metrics.new_checkpoint_created);
+            snapshot = finalized.snapshot();
+            let expected = if version < 3 { 1 } else { 3 };
+            assert_eq!(manager.pending_version(), Some(expected));
+        }
+        assert!(!checkpoint_exists(1).await);
+
+        // draining only creates a checkpoint for the latest pending version
+        assert_eq!(manager.drain().await.unwrap(), Some(3));
+        assert!(checkpoint_exists(3).await);
+        assert!(!checkpoint_exists(1).await);
+        assert_eq!(manager.pending_version(), None);
+        assert_eq!(manager.drain().await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_checkpoint_retry_backoff() {
+        assert_eq!(checkpoint_retry_backoff(0), Duration::from_millis(100));
+        assert_eq!(checkpoint_retry_backoff(3), Duration::from_millis(800));
+        assert_eq!(checkpoint_retry_backoff(7), CHECKPOINT_RETRY_MAX_BACKOFF);
+        assert_eq!(
+            checkpoint_retry_backoff(u32::MAX),
+            CHECKPOINT_RETRY_MAX_BACKOFF
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_checkpoint_retries() {
+        let store = Arc::new(TestObjectStore::new().with_failing_writes("checkpoint", 2));
+        let log_store: LogStoreRef = Arc::new(DefaultLogStore::new(
+            store.clone(),
+            crate::logstore::LogStoreConfig {
+                location: Url::parse("memory:///").unwrap(),
+                options: Default::default(),
+            },
+        ));
+        let table = crate::DeltaOps(crate::DeltaTable::new(log_store, Default::default()))
+            .create()
+            .with_column(
+                "value",
+                crate::kernel::DataType::Primitive(crate::kernel::PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .with_configuration_property(
+                crate::table::config::TableProperty::CheckpointInterval,
+                Some("1"),
+            )
+            .await
+            .unwrap();
+        let operation = append_operation();
+        assert_eq!(
+            PostCommitHookProperties::default().checkpoint_retries(),
+            DEFAULT_CHECKPOINT_RETRIES
+        );
+
+        // the first two attempts fail, the third one creates the checkpoint
+        let finalized = CommitBuilder::default()
+            .with_post_commit_hook(PostCommitHookProperties::default().with_checkpoint_retries(2))
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(finalized.metrics.new_checkpoint_created);
+        assert!(finalized.metrics.checkpoint_error.is_none());
+        assert_eq!(store.failing_writes(), 0);
+
+        // without retries a single failure is recorded in the metrics
+        store.set_failing_writes(1);
+        let finalized = CommitBuilder::default()
+            .with_post_commit_hook(PostCommitHookProperties::default().with_checkpoint_retries(0))
+            .build(Some(&finalized.snapshot()), table.log_store(), operation)
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 2);
+        assert!(!finalized.metrics.new_checkpoint_created);
+        assert!(finalized.metrics.checkpoint_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_post_commit_errors() {
+        let store = Arc::new(TestObjectStore::new().with_failing_writes("checkpoint", usize::MAX));
+        let log_store: LogStoreRef = Arc::new(DefaultLogStore::new(
+            store,
+            crate::logstore::LogStoreConfig {
+                location: Url::parse("memory:///").unwrap(),
+                options: Default::default(),
+            },
+        ));
+        let table = crate::DeltaOps(crate::DeltaTable::new(log_store, Default::default()))
+            .create()
+            .with_column(
+                "value",
+                crate::kernel::DataType::Primitive(crate::kernel::PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .with_configuration_property(
+                crate::table::config::TableProperty::CheckpointInterval,
+                Some("1"),
+            )
+            .await
+            .unwrap();
+        let operation = append_operation();
+
+        // the commit is durable, so by default a failing checkpoint is only recorded
+        let finalized = CommitBuilder::from(CommitProperties::default().with_checkpoint_retries(0))
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 1);
+        assert!(finalized.metrics.checkpoint_error.is_some());
+
+        let result = CommitBuilder::from(
+            CommitProperties::default()
+                .with_checkpoint_retries(0)
+                .with_fail_on_post_commit_error(true),
+        )
+        .build(
+            Some(table.snapshot().unwrap()),
+            table.log_store(),
+            operation,
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(table.log_store().get_latest_version(0).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_commit_timeout() {
+        let table = create_test_table().await;
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).losing_every_race());
+        let operation = append_operation();
+
+        let started = Instant::now();
+        let result = CommitBuilder::default()
+            .with_max_retries(1000)
+            .with_retry_backoff(RetryBackoff::new(
+                Duration::from_millis(50),
+                1.0,
+                Duration::from_millis(50),
+            ))
+            .with_timeout(Some(Duration::from_millis(200)))
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation.clone(),
+            )
+            .await;
+        assert!(started.elapsed() < Duration::from_secs(2));
+        match result {
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::Timeout { elapsed, attempts },
+            }) => {
+                assert!(elapsed >= Duration::from_millis(200));
+                assert!(attempts > 0);
+                assert_eq!(attempts, log_store.attempts().len());
+            }
+            Err(err) => panic!("expected timeout, got {err}"),
+            Ok(_) => panic!("expected timeout, but the commit succeeded"),
+        }
+
+        let tmp_commits: Vec<_> = log_store
+            .object_store(None)
+            .list(Some(&Path::from(DELTA_LOG_FOLDER)))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|meta| meta.location.as_ref().ends_with(".json.tmp"))
+            .collect();
+        assert!(tmp_commits.is_empty());
+
+        // a commit entry which is written before the timeout fires makes the commit durable
+        let log_store: LogStoreRef = Arc::new(
+            TestLogStore::new(table.log_store())
+                .with_conditional_put(true)
+                .with_write_delay(Duration::from_secs(60)),
+        );
+        let finalized = CommitBuilder::default()
+            .with_timeout(Some(Duration::from_millis(200)))
+            .build(Some(table.snapshot().unwrap()), log_store, operation)
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 1);
+        assert_eq!(finalized.metrics.num_retries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_blind_append_skips_conflict_checks() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let add = |path: &str| {
+            Action::Add(crate::kernel::Add {
+                path: path.to_string(),
+                size: 100,
+                data_change: true,
+                ..Default::default()
+            })
+        };
+
+        let snapshot = table.snapshot().unwrap().clone();
+        CommitBuilder::default()
+            .with_actions(vec![add("other.parquet")])
+            .build(Some(&snapshot), table.log_store(), operation.clone())
+            .await
+            .unwrap();
+        // the winning commit is not read, which would take at least 10ms
+        let log_store = Arc::new(
+            TestLogStore::new(table.log_store()).with_read_delay(Duration::from_millis(10)),
+        );
+        let commit = CommitBuilder::default()
+            .with_actions(vec![add("late.parquet")])
+            .build(Some(&snapshot), log_store, operation.clone())
+            .await
+            .unwrap();
+        assert_eq!(commit.version(), 2);
+        assert_eq!(commit.metrics.conflict_check_duration_ms, 0);
+        assert_eq!(commit.snapshot().files_count(), 2);
+
+        // blind appends still conflict with changes to the metadata
+        let mut metadata = snapshot.metadata().clone();
+        metadata.description = Some("changed".into());
+        CommitBuilder::default()
+            .with_actions(vec![Action::Metadata(metadata)])
+            .build(
+                Some(&commit.snapshot()),
+                table.log_store(),
+                DeltaOperation::SetTableProperties {
+                    properties: HashMap::new(),
+                },
+            )
+            .await
+            .unwrap();
+        let result = CommitBuilder::default()
+            .with_actions(vec![add("stale.parquet")])
+            .build(Some(&commit.snapshot()), table.log_store(), operation)
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::CommitConflict {
+                    source: CommitConflictError::MetadataChanged,
+                    ..
+                }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_conflict_details() {
+        let table = create_test_table().await;
+        let add = |path: &str| {
+            Action::Add(crate::kernel::Add {
+                path: path.to_string(),
+                data_change: true,
+                ..Default::default()
+            })
+        };
+
+        CommitBuilder::default()
+            .with_actions(vec![add("winner.parquet")])
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                DeltaOperation::Update { predicate: None },
+            )
+            .await
+            .unwrap();
+
+        let result = CommitBuilder::default()
+            .with_actions(vec![add("loser.parquet")])
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                DeltaOperation::Merge {
+                    predicate: None,
+                    merge_predicate: None,
+                    matched_predicates: vec![],
+                    not_matched_predicates: vec![],
+                    not_matched_by_source_predicates: vec![],
+                },
+            )
+            .await;
+        let Err(DeltaTableError::Transaction { source }) = result else {
+            panic!("expected a commit conflict");
+        };
+        assert!(matches!(
+            source,
+            TransactionError::CommitConflict {
+                source: CommitConflictError::ConcurrentAppend,
+                ..
+            }
+        ));
+        let details = source.conflict_details().unwrap();
+        assert_eq!(details.winning_version, 1);
+        assert_eq!(details.winning_operation.as_deref(), Some("UPDATE"));
+        assert_eq!(
+            details.conflicting_paths,
+            vec!["winner.parquet".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_conflict_resolution() {
+        struct CountingResolution(AtomicUsize);
+
+        impl ConflictResolution for CountingResolution {
+            fn resolve(
+                &self,
+                _txn: &PendingTransaction<'_>,
+                _summary: &WinningCommitSummary,
+                _operation: &DeltaOperation,
+            ) -> Result<(), CommitConflictError> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let mut table = create_test_table().await;
+        let read_snapshot = table.snapshot().unwrap().clone();
+        let operation = DeltaOperation::Update { predicate: None };
+        for i in 0..2 {
+            let add = Action::Add(crate::kernel::Add {
+                path: format!("winner-{i}.parquet"),
+                data_change: true,
+                ..Default::default()
+            });
+            CommitBuilder::default()
+                .with_actions(vec![add])
+                .build(
+                    Some(table.snapshot().unwrap()),
+                    table.log_store(),
+                    operation.clone(),
+                )
+                .await
+                .unwrap();
+            table.update().await.unwrap();
+        }
+
+        let resolution = Arc::new(CountingResolution(AtomicUsize::new(0)));
+        let add = Action::Add(crate::kernel::Add {
+            path: "loser.parquet".to_string(),
+            data_change: true,
+            ..Default::default()
+        });
+        let commit = CommitBuilder::default()
+            .with_actions(vec![add])
+            .with_conflict_resolution(resolution.clone())
+            .build(Some(&read_snapshot), table.log_store(), operation)
+            .await
+            .unwrap();
+
+        assert_eq!(commit.version(), 3);
+        assert_eq!(resolution.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_latest_commit_timestamp_precondition() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+
+        let result = CommitBuilder::from(
+            CommitProperties::default().with_latest_commit_timestamp_precondition(|ts| ts < 0),
+        )
+        .build(
+            Some(table.snapshot().unwrap()),
+            table.log_store(),
+            operation.clone(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::CommitTimestampPreconditionFailed {
+                    version: 0,
+                    timestamp: Some(_),
+                }
+            })
+        ));
+
+        let now = Utc::now().timestamp_millis();
+        let finalized = CommitBuilder::from(
+            CommitProperties::default()
+                .with_latest_commit_timestamp_precondition(move |ts| ts <= now),
+        )
+        .build(
+            Some(table.snapshot().unwrap()),
+            table.log_store(),
+            operation,
+        )
+        .await
+        .unwrap();
+        assert_eq!(finalized.version(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_build_dry_run() {
+        let table = create_test_table().await;
+        // the conflicting log store writes tmp commits, which we can compare against
+        let log_store: LogStoreRef =
+            Arc::new(TestLogStore::new(table.log_store()).losing_every_race());
+        let operation = append_operation();
+        let properties = || {
+            CommitProperties::default()
+                .with_metadata([("userName".to_string(), Value::String("user".into()))])
+                .with_application_transaction(Transaction::new("app", 1))
+        };
+
+        let dry_run = CommitBuilder::from(properties())
+            .build_dry_run(Some(table.snapshot().unwrap()), operation.clone())
+            .await
+            .unwrap();
+        assert!(dry_run
+            .actions
+            .iter()
+            .any(|action| matches!(action, Action::CommitInfo(_))));
+        assert!(dry_run
+            .actions
+            .iter()
+            .any(|action| matches!(action, Action::Txn(_))));
+
+        let prepared = CommitBuilder::from(properties())
+            .build(
+                Some(table.snapshot().unwrap()),
+                log_store.clone(),
+                operation,
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        let CommitOrBytes::TmpCommit(tmp_path) = prepared.commit_or_bytes() else {
+            panic!("expected a tmp commit");
+        };
+        let written = log_store
+            .object_store(None)
+            .get(tmp_path)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+
+        // commit info maps are serialized in arbitrary order, so compare the parsed actions
+        let parse = |bytes: &[u8]| {
+            let mut actions = bytes
+                .split(|b| *b == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| serde_json::from_slice::<Value>(line).unwrap())
+                .collect::<Vec<_>>();
+            for action in actions.iter_mut() {
+                if let Some(commit_info) = action.get_mut("commitInfo") {
+                    commit_info.as_object_mut().unwrap().remove("timestamp");
+                }
+            }
+            actions
+        };
+        assert_eq!(parse(&dry_run.get_bytes().unwrap()), parse(&written));
+    }
+
+    #[tokio::test]
+    async fn test_max_commit_info_bytes() {
+        let operation = append_operation();
+        let metadata = [("payload".to_string(), Value::String("x".repeat(10_000)))];
+        let log_store = crate::DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap();
+        let result = CommitBuilder::from(
+            CommitProperties::default()
+                .with_metadata(metadata)
+                .with_max_commit_info_bytes(1024),
+        )
+        .build(None, log_store, operation)
+        .into_prepared_commit_future()
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::CommitInfoTooLarge { limit: 1024, size }
+            }) if size > 10_000
+        ));
+    }
+
+    #[test]
+    fn test_commit_info_fn() {
+        let operation = append_operation();
+        let commit_info_fn: CommitInfoFn = Arc::new(|operation: &DeltaOperation| {
+            HashMap::from([
+                ("traceId".to_string(), Value::String("abc".to_string())),
+                (
+                    "operationName".to_string(),
+                    Value::String(operation.name().to_string()),
+                ),
+                ("timestamp".to_string(), Value::from(0)),
+                (
+                    "clientVersion".to_string(),
+                    Value::String("custom".to_string()),
+                ),
+            ])
+        });
+        let log_store = crate::DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap();
+        let commit =
+            CommitBuilder::from(CommitProperties::default().with_commit_info_fn(commit_info_fn))
+                .build(None, log_store, operation);
+
+        let bytes = commit.data.get_bytes().unwrap();
+        let commit_info: Value = serde_json::from_slice(&bytes).unwrap();
+        let commit_info = &commit_info["commitInfo"];
+        assert_eq!(commit_info["traceId"], Value::String("abc".to_string()));
+        assert_eq!(
+            commit_info["operationName"],
+            Value::String("WRITE".to_string())
+        );
+        assert_ne!(commit_info["timestamp"], Value::from(0));
+        assert_eq!(
+            commit_info["clientVersion"],
+            Value::String(format!("delta-rs.{}", crate_version()))
+        );
+    }
+
+    #[test]
+    fn test_blind_append() {
+        let blind_append = |data: &CommitData| {
+            data.actions.iter().find_map(|action| match action {
+                Action::CommitInfo(info) => info.is_blind_append,
+                _ => None,
+            })
+        };
+        let add = Action::Add(crate::kernel::Add {
+            path: "new.parquet".to_string(),
+            ..Default::default()
+        });
+        let remove = Action::Remove(crate::kernel::Remove {
+            path: "old.parquet".to_string(),
+            data_change: true,
+            ..Default::default()
+        });
+
+        let append = CommitData::new(
+            vec![add.clone()],
+            append_operation(),
+            HashMap::new(),
+            vec![],
+        );
+        assert_eq!(blind_append(&append), Some(true));
+
+        let mut overwrite = CommitData::new(
+            vec![add, remove],
+            DeltaOperation::Write {
+                mode: SaveMode::Overwrite,
+                partition_by: None,
+                predicate: None,
+            },
+            HashMap::new(),
+            vec![],
+        );
+        assert_eq!(blind_append(&overwrite), Some(false));
+
+        overwrite.set_blind_append(true);
+        assert_eq!(blind_append(&overwrite), Some(true));
+    }
+
+    #[test]
+    fn test_action_tally_blind_append() {
+        let mut tally = ActionTally::default();
+        tally.record(&Action::Add(crate::kernel::Add {
+            size: 10,
+            ..Default::default()
+        }));
+        assert!(tally.is_blind_append());
+
+        let mut with_cdc = tally.clone();
+        with_cdc.record(&Action::Cdc(Default::default()));
+        assert!(!with_cdc.is_blind_append());
+
+        tally.record(&Action::Remove(crate::kernel::Remove {
+            path: "old.parquet".to_string(),
+            ..Default::default()
+        }));
+        assert!(!tally.is_blind_append());
+        assert_eq!(tally.num_actions, 2);
+    }
+
+    #[tokio::test]
+    async fn test_auto_commit_info() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let add = Action::Add(crate::kernel::Add {
+            path: "part-0.parquet".to_string(),
+            size: 10,
+            data_change: true,
+            ..Default::default()
+        });
+
+        let commit = CommitBuilder::from(CommitProperties::default().with_auto_commit_info(false))
+            .with_actions(vec![add])
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation,
+            )
+            .await
+            .unwrap();
+        let log_entry = table
+            .log_store()
+            .read_commit_entry(commit.version())
+            .await
+            .unwrap()
+            .unwrap();
+        let actions = crate::logstore::get_actions(commit.version(), log_entry)
+            .await
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(actions[0], Action::Add(_)));
+    }
+
+    #[tokio::test]
+    async fn test_layered_app_metadata() {
+        let operation = append_operation();
+        let defaults = [
+            ("team".to_string(), Value::from("ingest")),
+            ("pipeline".to_string(), Value::from("default")),
+        ];
+        let properties = CommitProperties::default()
+            .with_metadata(defaults)
+            .add_metadata("pipeline", "backfill")
+            .with_metadata([("run".to_string(), Value::from(1))]);
+        let data = CommitBuilder::from(properties)
+            .add_app_metadata("run", 2)
+            .build_dry_run(None, operation)
+            .await
+            .unwrap();
+
+        let info = data
+            .actions
+            .iter()
+            .find_map(|action| match action {
+                Action::CommitInfo(commit_info) => Some(&commit_info.info),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(info["team"], Value::from("ingest"));
+        assert_eq!(info["pipeline"], Value::from("backfill"));
+        assert_eq!(info["run"], Value::from(2));
+    }
+
+    #[test]
+    fn test_client_version_policy() {
+        let operation = append_operation();
+        let app_metadata = HashMap::from([(
+            "clientVersion".to_string(),
+            Value::String("my-app.1.0".to_string()),
+        )]);
+        let client_version = |policy| {
+            let data = CommitData::new_with_client_version_policy(
+                vec![],
+                operation.clone(),
+                app_metadata.clone(),
+                vec![],
+                policy,
+            );
+            data.app_metadata["clientVersion"].clone()
+        };
+
+        assert_eq!(
+            client_version(ClientVersionPolicy::Combine),
+            Value::String(format!("my-app.1.0 (delta-rs.{})", crate_version()))
+        );
+        assert_eq!(
+            client_version(ClientVersionPolicy::Respect),
+            Value::String("my-app.1.0".to_string())
+        );
+        assert_eq!(
+            client_version(ClientVersionPolicy::Overwrite),
+            Value::String(format!("delta-rs.{}", crate_version()))
+        );
+
+        // the delta-rs version is recorded by default, as before the policy was configurable
+        let data = CommitData::new(vec![], operation, app_metadata, vec![]);
+        assert_eq!(
+            data.app_metadata["clientVersion"],
+            Value::String(format!("delta-rs.{}", crate_version()))
+        );
+    }
+
+    #[test]
+    fn test_max_action_size() {
+        let operation = append_operation();
+        let add = Action::Add(crate::kernel::Add {
+            path: "a".repeat(1000),
+            ..Default::default()
+        });
+        let data = CommitData::new(vec![add], operation, HashMap::new(), vec![]);
+
+        assert!(data.serialize_actions(None, Some(10_000), None).is_ok());
+        let result = data.serialize_actions(None, Some(500), None);
+        assert!(matches!(
+            result,
+            Err(TransactionError::ActionTooLarge { action_index: 0, size }) if size > 1000
+        ));
+    }
+
+    /// Serializes actions without null fields and with sorted keys
+    #[derive(Debug)]
+    struct CanonicalSerializer;
+
+    impl ActionSerializer for CanonicalSerializer {
+        fn serialize(&self, action: &Action) -> Result<String, serde_json::Error> {
+            fn canonical(value: Value) -> Value {
+                match value {
+                    Value::Object(map) => {
+                        let mut entries = map
+                            .into_iter()
+                            .filter(|(_, value)| !value.is_null())
+                            .collect::<Vec<_>>();
+                        entries.sort_by(|a, b| a.0.cmp(&b.0));
+                        Value::Object(
+                            entries
+                                .into_iter()
+                                .map(|(key, value)| (key, canonical(value)))
+                                .collect(),
+                        )
+                    }
+                    value => value,
+                }
+            }
+            serde_json::to_string(&canonical(serde_json::to_value(action)?))
+        }
+    }
+
+    /// Drops the path of added files, which readers require
+    #[derive(Debug)]
+    struct LossySerializer;
+
+    impl ActionSerializer for LossySerializer {
+        fn serialize(&self, action: &Action) -> Result<String, serde_json::Error> {
+            let mut value = serde_json::to_value(action)?;
+            if let Some(add) = value.get_mut("add").and_then(Value::as_object_mut) {
+                add.remove("path");
+            }
+            serde_json::to_string(&value)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_action_serializer() {
+        let table = create_test_table().await;
+        let log_store = table.log_store();
+        let operation = append_operation();
+        let add = Action::Add(crate::kernel::Add {
+            path: "part-00000.parquet".to_string(),
+            size: 100,
+            data_change: true,
+            ..Default::default()
+        });
+
+        let commit = CommitBuilder::from(
+            CommitProperties::default().with_action_serializer(Arc::new(CanonicalSerializer)),
+        )
+        .with_actions(vec![add.clone()])
+        .build(
+            Some(table.snapshot().unwrap()),
+            log_store.clone(),
+            operation.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(commit.version(), 1);
+
+        let log_entry = log_store.read_commit_entry(1).await.unwrap().unwrap();
+        let log_entry = std::str::from_utf8(&log_entry).unwrap();
+        assert!(!log_entry.contains("null"));
+        // the canonical entry can be read back
+        let mut table = crate::DeltaTable::new(log_store.clone(), Default::default());
+        table.load().await.unwrap();
+        assert_eq!(table.version(), 1);
+        assert_eq!(
+            table
+                .get_files_iter()
+                .unwrap()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>(),
+            vec!["part-00000.parquet".to_string()]
+        );
+
+        let result = CommitBuilder::from(
+            CommitProperties::default().with_action_serializer(Arc::new(LossySerializer)),
+        )
+        .with_actions(vec![add])
+        .build(Some(&commit.snapshot()), log_store, operation)
+        .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::InvalidSerializedAction { .. }
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_secondary_log_store() {
+        let create_table = || async {
+            crate::DeltaOps::new_in_memory()
+                .create()
+                .with_column(
+                    "value",
+                    crate::kernel::DataType::Primitive(crate::kernel::PrimitiveType::Integer),
+                    true,
+                    None,
+                )
+                .await
+                .unwrap()
+        };
+        let primary = create_table().await;
+        let secondary = create_table().await;
+        let operation = append_operation();
+        let add = |path: &str| {
+            Action::Add(crate::kernel::Add {
+                path: path.to_string(),
+                size: 100,
+                data_change: true,
+                ..Default::default()
+            })
+        };
+
+        let commit = CommitBuilder::default()
+            .with_actions(vec![add("a.parquet")])
+            .with_secondary_log_store(Some(secondary.log_store()))
+            .build(
+                Some(primary.snapshot().unwrap()),
+                primary.log_store(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(commit.version(), 1);
+        let primary_entry = primary.log_store().read_commit_entry(1).await.unwrap();
+        let secondary_entry = secondary.log_store().read_commit_entry(1).await.unwrap();
+        assert!(primary_entry.is_some());
+        assert_eq!(primary_entry, secondary_entry);
+
+        // the secondary diverged, so mirroring the next commit fails
+        CommitBuilder::default()
+            .with_actions(vec![add("c.parquet")])
+            .build(
+                Some(&commit.snapshot()),
+                secondary.log_store(),
+                operation.clone(),
+            )
+            .await
+            .unwrap();
+        let result = CommitBuilder::default()
+            .with_actions(vec![add("d.parquet")])
+            .with_secondary_log_store(Some(secondary.log_store()))
+            .build(Some(&commit.snapshot()), primary.log_store(), operation)
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::SecondaryCommitFailed { version: 2, .. }
+            })
+        ));
+        assert!(primary
+            .log_store()
+            .read_commit_entry(2)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_conflict_prewarm() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let mut snapshot = table.snapshot().unwrap().clone();
+        for _ in 0..3 {
+            snapshot = CommitBuilder::default()
+                .build(Some(&snapshot), table.log_store(), operation.clone())
+                .await
+                .unwrap()
+                .snapshot();
+        }
+
+        // without the hint no commits are preloaded
+        let prepared = CommitBuilder::default()
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation.clone(),
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        assert!(prepared.conflict_cache.is_empty());
+        drop(prepared);
+
+        // only the hinted number of commits following the read version are preloaded
+        let prepared = CommitBuilder::default()
+            .with_conflict_prewarm(2)
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation,
+            )
+            .into_prepared_commit_future()
+            .await
+            .unwrap();
+        let mut prewarmed: Vec<_> = prepared.conflict_cache.keys().copied().collect();
+        prewarmed.sort();
+        assert_eq!(prewarmed, vec![1, 2]);
+
+        // the retry loop consumes the preloaded commits
+        let finalized = prepared.await.unwrap().await.unwrap();
+        assert_eq!(finalized.version(), 4);
+        assert_eq!(finalized.metrics.num_retries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_serialization_progress() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let adds = futures::stream::iter((0..10).map(|i| {
+            Ok(Action::Add(crate::kernel::Add {
+                path: format!("part-{i}.parquet"),
+                size: 10,
+                data_change: true,
+                ..Default::default()
+            }))
+        }))
+        .boxed();
+
+        let progress = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reported = progress.clone();
+        let finalized = CommitBuilder::default()
+            .with_action_stream(adds)
+            .with_serialization_progress(
+                3,
+                Arc::new(move |num_actions| reported.lock().unwrap().push(num_actions)),
+            )
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation,
+            )
+            .await
+            .unwrap();
+        // the ten adds and the commit info are serialized
+        assert_eq!(finalized.metrics.num_actions, 11);
+        assert_eq!(*progress.lock().unwrap(), vec![3, 6, 9]);
     }
-}
 
-impl<'a> std::future::IntoFuture for PreparedCommit<'a> {
-    type Output = DeltaResult<PostCommit>;
-    type IntoFuture = BoxFuture<'a, Self::Output>;
+    #[tokio::test]
+    async fn test_expected_protocol() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let snapshot = table.snapshot().unwrap();
 
-    fn into_future(self) -> Self::IntoFuture {
-        let this = self;
+        let unexpected = Protocol::new(3, 7);
+        let result = CommitBuilder::from(
+            CommitProperties::default().with_expected_protocol(unexpected.clone()),
+        )
+        .build(Some(snapshot), table.log_store(), operation.clone())
+        .await;
+        match result {
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::ProtocolMismatch { expected, found },
+            }) => {
+                assert_eq!(*expected, unexpected);
+                assert_eq!(&*found, snapshot.protocol());
+            }
+            other => panic!("expected a protocol mismatch, got {other:?}"),
+        }
+        assert_eq!(table.log_store().get_latest_version(0).await.unwrap(), 0);
 
-        Box::pin(async move {
-            let commit_or_bytes = this.commit_or_bytes;
+        let finalized = CommitBuilder::from(
+            CommitProperties::default().with_expected_protocol(snapshot.protocol().clone()),
+        )
+        .build(Some(snapshot), table.log_store(), operation)
+        .await
+        .unwrap();
+        assert_eq!(finalized.version(), 1);
+    }
 
-            if this.table_data.is_none() {
-                this.log_store
-                    .write_commit_entry(0, commit_or_bytes.clone(), this.operation_id)
-                    .await?;
-                return Ok(PostCommit {
-                    version: 0,
-                    data: this.data,
-                    create_checkpoint: false,
-                    cleanup_expired_logs: None,
-                    log_store: this.log_store,
-                    table_data: None,
-                    custom_execute_handler: this.post_commit_hook_handler,
-                    metrics: CommitMetrics { num_retries: 0 },
-                });
+    fn domain_action(configuration: &str, removed: bool) -> Action {
+        Action::DomainMetadata(crate::kernel::DomainMetadata {
+            domain: "app".into(),
+            configuration: configuration.into(),
+            removed,
+        })
+    }
+
+    fn assert_domain_precondition_failed(
+        result: DeltaResult<FinalizedCommit>,
+        expected: Option<&str>,
+        found: Option<&str>,
+    ) {
+        match result {
+            Err(DeltaTableError::Transaction {
+                source:
+                    TransactionError::DomainPreconditionFailed {
+                        domain,
+                        expected: actual_expected,
+                        found: actual_found,
+                    },
+            }) => {
+                assert_eq!(domain, "app");
+                assert_eq!(actual_expected.as_deref(), expected);
+                assert_eq!(actual_found.as_deref(), found);
             }
+            other => panic!("expected a failed domain precondition, got {other:?}"),
+        }
+    }
 
-            // unwrap() is safe here due to the above check
-            let mut read_snapshot = this.table_data.unwrap().eager_snapshot().clone();
+    #[tokio::test]
+    async fn test_domain_precondition() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let commit = |snapshot: DeltaTableState, expected: Option<&str>, action: Action| {
+            let log_store = table.log_store();
+            let operation = operation.clone();
+            let properties = CommitProperties::default()
+                .with_domain_precondition("app", expected.map(String::from));
+            async move {
+                CommitBuilder::from(properties)
+                    .with_actions(vec![action])
+                    .build(Some(&snapshot), log_store, operation)
+                    .await
+            }
+        };
 
-            let mut attempt_number = 1;
-            let total_retries = this.max_retries + 1;
-            while attempt_number <= total_retries {
-                let latest_version = this
-                    .log_store
-                    .get_latest_version(read_snapshot.version())
-                    .await?;
+        // a domain which does not exist yet can be created
+        let snapshot = table.snapshot().unwrap().clone();
+        let finalized = commit(snapshot, None, domain_action("1", false))
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 1);
 
-                if latest_version > read_snapshot.version() {
-                    // If max_retries are set to 0, do not try to use the conflict checker to resolve the conflict
-                    // and throw immediately
-                    if this.max_retries == 0 {
-                        return Err(
-                            TransactionError::MaxCommitAttempts(this.max_retries as i32).into()
-                        );
-                    }
-                    warn!("Attempting to write a transaction {} but the underlying table has been updated to {latest_version}\n{:?}", read_snapshot.version() + 1, this.log_store);
-                    let mut steps = latest_version - read_snapshot.version();
-
-                    // Need to check for conflicts with each version between the read_snapshot and
-                    // the latest!
-                    while steps != 0 {
-                        let summary = WinningCommitSummary::try_new(
-                            this.log_store.as_ref(),
-                            latest_version - steps,
-                            (latest_version - steps) + 1,
-                        )
-                        .await?;
-                        let transaction_info = TransactionInfo::try_new(
-                            &read_snapshot,
-                            this.data.operation.read_predicate(),
-                            &this.data.actions,
-                            this.data.operation.read_whole_table(),
-                        )?;
-                        let conflict_checker = ConflictChecker::new(
-                            transaction_info,
-                            summary,
-                            Some(&this.data.operation),
-                        );
+        // the configuration is only replaced if it matches the expected one
+        let finalized = commit(finalized.snapshot(), Some("1"), domain_action("2", false))
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 2);
+        let result = commit(finalized.snapshot(), Some("1"), domain_action("3", false)).await;
+        assert_domain_precondition_failed(result, Some("1"), Some("2"));
+        let result = commit(finalized.snapshot(), None, domain_action("3", false)).await;
+        assert_domain_precondition_failed(result, None, Some("2"));
+        assert_eq!(table.log_store().get_latest_version(0).await.unwrap(), 2);
 
-                        match conflict_checker.check_conflicts() {
-                            Ok(_) => {}
-                            Err(err) => {
-                                return Err(TransactionError::CommitConflict(err).into());
-                            }
-                        }
-                        steps -= 1;
-                    }
-                    // Update snapshot to latest version after successful conflict check
-                    read_snapshot
-                        .update(this.log_store.clone(), Some(latest_version))
-                        .await?;
-                }
-                let version: i64 = latest_version + 1;
+        // a removed domain no longer exists
+        let finalized = commit(finalized.snapshot(), Some("2"), domain_action("2", true))
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 3);
+        let result = commit(finalized.snapshot(), Some("2"), domain_action("3", false)).await;
+        assert_domain_precondition_failed(result, Some("2"), None);
+        let finalized = commit(finalized.snapshot(), None, domain_action("3", false))
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 4);
+    }
 
-                match this
-                    .log_store
-                    .write_commit_entry(version, commit_or_bytes.clone(), this.operation_id)
-                    .await
-                {
-                    Ok(()) => {
-                        return Ok(PostCommit {
-                            version,
-                            data: this.data,
-                            create_checkpoint: this
-                                .post_commit
-                                .map(|v| v.create_checkpoint)
-                                .unwrap_or_default(),
-                            cleanup_expired_logs: this
-                                .post_commit
-                                .map(|v| v.cleanup_expired_logs)
-                                .unwrap_or_default(),
-                            log_store: this.log_store,
-                            table_data: Some(Box::new(read_snapshot)),
-                            custom_execute_handler: this.post_commit_hook_handler,
-                            metrics: CommitMetrics {
-                                num_retries: attempt_number as u64 - 1,
-                            },
-                        });
-                    }
-                    Err(TransactionError::VersionAlreadyExists(version)) => {
-                        error!("The transaction {version} already exists, will retry!");
-                        // If the version already exists, loop through again and re-check
-                        // conflicts
-                        attempt_number += 1;
-                    }
-                    Err(err) => {
-                        this.log_store
-                            .abort_commit_entry(version, commit_or_bytes, this.operation_id)
-                            .await?;
-                        return Err(err.into());
-                    }
-                }
-            }
+    #[tokio::test]
+    async fn test_domain_precondition_rechecked_on_retry() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let snapshot = table.snapshot().unwrap().clone();
 
-            Err(TransactionError::MaxCommitAttempts(this.max_retries as i32).into())
-        })
+        // a concurrent writer creates the domain after the snapshot was read
+        CommitBuilder::default()
+            .with_actions(vec![domain_action("other", false)])
+            .build(Some(&snapshot), table.log_store(), operation.clone())
+            .await
+            .unwrap();
+
+        let log_store = Arc::new(TestLogStore::new(table.log_store()));
+        let result =
+            CommitBuilder::from(CommitProperties::default().with_domain_precondition("app", None))
+                .with_actions(vec![domain_action("mine", false)])
+                .build(Some(&snapshot), log_store.clone(), operation)
+                .await;
+        // the precondition held for the read snapshot, but not after rebasing
+        assert_eq!(log_store.attempts().len(), 1);
+        assert_domain_precondition_failed(result, None, Some("other"));
+        assert_eq!(table.log_store().get_latest_version(0).await.unwrap(), 1);
     }
-}
 
-/// Represents items for the post commit hook
-pub struct PostCommit {
-    /// The winning version number of the commit
-    pub version: i64,
-    /// The data that was committed to the log store
-    pub data: CommitData,
-    create_checkpoint: bool,
-    cleanup_expired_logs: Option<bool>,
-    log_store: LogStoreRef,
-    table_data: Option<Box<dyn TableReference>>,
-    custom_execute_handler: Option<Arc<dyn CustomExecuteHandler>>,
-    metrics: CommitMetrics,
-}
+    #[tokio::test]
+    async fn test_post_commit_hook_handler_metrics() {
+        #[derive(Default)]
+        struct RecordingHandler {
+            metrics: std::sync::Mutex<Vec<CommitMetrics>>,
+        }
 
-impl PostCommit {
-    /// Runs the post commit activities
-    async fn run_post_commit_hook(&self) -> DeltaResult<(DeltaTableState, PostCommitMetrics)> {
-        if let Some(table) = &self.table_data {
-            let post_commit_operation_id = Uuid::new_v4();
-            let mut snapshot = table.eager_snapshot().clone();
-            if self.version - snapshot.version() > 1 {
-                // This may only occur during concurrent write actions. We need to update the state first to - 1
-                // then we can advance.
-                snapshot
-                    .update(self.log_store.clone(), Some(self.version - 1))
-                    .await?;
-                snapshot.advance(vec![&self.data])?;
-            } else {
-                snapshot.advance(vec![&self.data])?;
+        #[async_trait::async_trait]
+        impl CustomExecuteHandler for RecordingHandler {
+            async fn pre_execute(&self, _: &LogStoreRef, _: Uuid) -> DeltaResult<()> {
+                Ok(())
             }
-            let mut state = DeltaTableState { snapshot };
 
-            let cleanup_logs = if let Some(cleanup_logs) = self.cleanup_expired_logs {
-                cleanup_logs
-            } else {
-                state.table_config().enable_expired_log_cleanup()
-            };
+            async fn post_execute(&self, _: &LogStoreRef, _: Uuid) -> DeltaResult<()> {
+                Ok(())
+            }
 
-            // Run arbitrary before_post_commit_hook code
-            if let Some(custom_execute_handler) = &self.custom_execute_handler {
-                custom_execute_handler
-                    .before_post_commit_hook(
-                        &self.log_store,
-                        cleanup_logs || self.create_checkpoint,
-                        post_commit_operation_id,
-                    )
-                    .await?
+            async fn before_post_commit_hook(
+                &self,
+                _: &LogStoreRef,
+                _: bool,
+                _: Uuid,
+            ) -> DeltaResult<()> {
+                panic!("the hook with metrics should be called instead");
             }
 
-            let mut new_checkpoint_created = false;
-            if self.create_checkpoint {
-                // Execute create checkpoint hook
-                new_checkpoint_created = self
-                    .create_checkpoint(
-                        &state,
-                        &self.log_store,
-                        self.version,
-                        post_commit_operation_id,
-                    )
-                    .await?;
+            async fn after_post_commit_hook(
+                &self,
+                _: &LogStoreRef,
+                _: bool,
+                _: Uuid,
+            ) -> DeltaResult<()> {
+                panic!("the hook with metrics should be called instead");
             }
 
-            let mut num_log_files_cleaned_up: u64 = 0;
-            if cleanup_logs {
-                // Execute clean up logs hook
-                num_log_files_cleaned_up = cleanup_expired_logs_for(
-                    self.version,
-                    self.log_store.as_ref(),
-                    Utc::now().timestamp_millis()
-                        - state.table_config().log_retention_duration().as_millis() as i64,
-                    Some(post_commit_operation_id),
-                )
-                .await? as u64;
-                if num_log_files_cleaned_up > 0 {
-                    state = DeltaTableState::try_new(
-                        &state.snapshot().table_root(),
-                        self.log_store.object_store(None),
-                        state.load_config().clone(),
-                        Some(self.version),
-                    )
-                    .await?;
-                }
+            async fn before_post_commit_hook_with_metrics(
+                &self,
+                _: &LogStoreRef,
+                _: bool,
+                _: Uuid,
+                metrics: &CommitMetrics,
+            ) -> DeltaResult<()> {
+                self.metrics.lock().unwrap().push(metrics.clone());
+                Ok(())
             }
 
-            // Run arbitrary after_post_commit_hook code
-            if let Some(custom_execute_handler) = &self.custom_execute_handler {
-                custom_execute_handler
-                    .after_post_commit_hook(
-                        &self.log_store,
-                        cleanup_logs || self.create_checkpoint,
-                        post_commit_operation_id,
-                    )
-                    .await?
+            async fn after_post_commit_hook_with_metrics(
+                &self,
+                _: &LogStoreRef,
+                _: bool,
+                _: Uuid,
+                metrics: &CommitMetrics,
+            ) -> DeltaResult<()> {
+                self.metrics.lock().unwrap().push(metrics.clone());
+                Ok(())
             }
-            Ok((
-                state,
-                PostCommitMetrics {
-                    new_checkpoint_created,
-                    num_log_files_cleaned_up,
-                },
-            ))
-        } else {
-            let state = DeltaTableState::try_new(
-                &Path::default(),
-                self.log_store.object_store(None),
-                Default::default(),
-                Some(self.version),
-            )
-            .await?;
-            Ok((
-                state,
-                PostCommitMetrics {
-                    new_checkpoint_created: false,
-                    num_log_files_cleaned_up: 0,
-                },
-            ))
-        }
-    }
-    async fn create_checkpoint(
-        &self,
-        table_state: &DeltaTableState,
-        log_store: &LogStoreRef,
-        version: i64,
-        operation_id: Uuid,
-    ) -> DeltaResult<bool> {
-        if !table_state.load_config().require_files {
-            warn!("Checkpoint creation in post_commit_hook has been skipped due to table being initialized without files.");
-            return Ok(false);
         }
 
-        let checkpoint_interval = table_state.config().checkpoint_interval() as i64;
-        if ((version + 1) % checkpoint_interval) == 0 {
-            create_checkpoint_for(version, table_state, log_store.as_ref(), Some(operation_id))
-                .await?;
-            Ok(true)
-        } else {
-            Ok(false)
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let log_store: LogStoreRef = Arc::new(TestLogStore::new(table.log_store()).with_races(1));
+
+        let handler = Arc::new(RecordingHandler::default());
+        let finalized = CommitBuilder::default()
+            .with_post_commit_hook_handler(Some(handler.clone()))
+            .build(Some(table.snapshot().unwrap()), log_store, operation)
+            .await
+            .unwrap();
+        assert_eq!(finalized.version(), 2);
+
+        // both hooks observe the retry caused by the lost race
+        let metrics = handler.metrics.lock().unwrap();
+        assert_eq!(metrics.len(), 2);
+        for metrics in metrics.iter() {
+            assert_eq!(metrics.num_retries, 1);
+            assert_eq!(metrics.num_actions, finalized.metrics.num_actions);
         }
     }
-}
-
-/// A commit that successfully completed
-pub struct FinalizedCommit {
-    /// The new table state after a commit
-    pub snapshot: DeltaTableState,
 
-    /// Version of the finalized commit
-    pub version: i64,
+    #[tokio::test]
+    async fn test_deterministic_tmp_commit() {
+        let table = create_test_table().await;
+        let log_store: LogStoreRef = Arc::new(TestLogStore::new(table.log_store()).rejecting());
+        let operation = append_operation();
+        let operation_id = Uuid::new_v4();
+        let tmp_commit = |deterministic: bool| {
+            let log_store = log_store.clone();
+            let operation = operation.clone();
+            let snapshot = table.snapshot().unwrap();
+            async move {
+                let prepared = CommitBuilder::default()
+                    .with_operation_id(operation_id)
+                    .with_deterministic_tmp_commit(deterministic)
+                    .build(Some(snapshot), log_store, operation)
+                    .into_prepared_commit_future()
+                    .await
+                    .unwrap();
+                let CommitOrBytes::TmpCommit(tmp_commit) = prepared.commit_or_bytes().clone()
+                else {
+                    panic!("expected a temporary commit");
+                };
+                tmp_commit
+            }
+        };
 
-    /// Metrics associated with the commit operation
-    pub metrics: Metrics,
-}
+        // retries of the same operation write to the same temporary commit
+        let expected = Path::from(format!("_delta_log/_commit_{operation_id}.json.tmp"));
+        assert_eq!(tmp_commit(true).await, expected);
+        assert_eq!(tmp_commit(true).await, expected);
 
-impl FinalizedCommit {
-    /// The new table state after a commit
-    pub fn snapshot(&self) -> DeltaTableState {
-        self.snapshot.clone()
-    }
-    /// Version of the finalized commit
-    pub fn version(&self) -> i64 {
-        self.version
+        // by default every attempt uses a random token
+        let random = tmp_commit(false).await;
+        assert_ne!(random, expected);
+        assert_ne!(tmp_commit(false).await, random);
     }
-}
-
-impl std::future::IntoFuture for PostCommit {
-    type Output = DeltaResult<FinalizedCommit>;
-    type IntoFuture = BoxFuture<'static, Self::Output>;
 
-    fn into_future(self) -> Self::IntoFuture {
-        let this = self;
+    #[tokio::test]
+    async fn test_failed_commit_clears_intent() {
+        let table = create_test_table().await;
+        let intent_store = Arc::new(ObjectStoreCommitIntentStore::new(
+            table.log_store().object_store(None),
+        ));
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).with_races(1));
+        let operation = append_operation();
 
-        Box::pin(async move {
-            match this.run_post_commit_hook().await {
-                Ok((snapshot, post_commit_metrics)) => Ok(FinalizedCommit {
-                    snapshot,
-                    version: this.version,
-                    metrics: Metrics {
-                        num_retries: this.metrics.num_retries,
-                        new_checkpoint_created: post_commit_metrics.new_checkpoint_created,
-                        num_log_files_cleaned_up: post_commit_metrics.num_log_files_cleaned_up,
-                    },
-                }),
-                Err(err) => Err(err),
-            }
-        })
+        // the competing commit took the only version attempted, so nothing can have landed
+        let result = CommitBuilder::from(
+            CommitProperties::default()
+                .with_max_retries(0)
+                .with_commit_intent_store(intent_store.clone()),
+        )
+        .build(Some(table.snapshot().unwrap()), log_store, operation)
+        .await;
+        assert!(result.is_err());
+        assert!(intent_store.pending().await.unwrap().is_empty());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
+    #[tokio::test]
+    async fn test_commit_data_from_action_stream() {
+        let operation = append_operation();
+        let mut data = CommitData::new(vec![], operation, HashMap::new(), vec![]);
+        let adds = (0..3).map(|i| {
+            Ok(Action::Add(crate::kernel::Add {
+                path: format!("part-{i}.parquet"),
+                size: 10,
+                ..Default::default()
+            }))
+        });
+        let tally = data
+            .extend_from_stream(futures::stream::iter(adds).boxed())
+            .await
+            .unwrap();
 
-    use super::*;
-    use crate::logstore::{commit_uri_from_version, default_logstore::DefaultLogStore, LogStore};
-    use object_store::{memory::InMemory, ObjectStore, PutPayload};
-    use url::Url;
+        assert_eq!(tally.num_actions, 3);
+        assert_eq!(tally.num_added_files, 3);
+        assert_eq!(tally.num_added_bytes, 30);
+        assert_eq!(data.actions.len(), 4);
 
-    #[test]
-    fn test_commit_uri_from_version() {
-        let version = commit_uri_from_version(0);
-        assert_eq!(version, Path::from("_delta_log/00000000000000000000.json"));
-        let version = commit_uri_from_version(123);
-        assert_eq!(version, Path::from("_delta_log/00000000000000000123.json"))
+        let commit_info = data
+            .actions
+            .iter()
+            .find_map(|a| match a {
+                Action::CommitInfo(info) => Some(info),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            commit_info.info["operationMetrics"]["numAddedFiles"],
+            serde_json::json!(3)
+        );
+        assert!(commit_info.info["operationMetrics"]
+            .get("numActions")
+            .is_none());
     }
 
     #[tokio::test]
-    async fn test_try_commit_transaction() {
-        let store = Arc::new(InMemory::new());
-        let url = Url::parse("mem://what/is/this").unwrap();
-        let log_store = DefaultLogStore::new(
-            store.clone(),
-            crate::logstore::LogStoreConfig {
-                location: url,
-                options: Default::default(),
-            },
-        );
-        let version_path = Path::from("_delta_log/00000000000000000000.json");
-        store.put(&version_path, PutPayload::new()).await.unwrap();
+    async fn test_commit_action_stream() {
+        let table = create_test_table().await;
+        let operation = append_operation();
+        let adds = |prefix: &'static str, num_files: usize| {
+            futures::stream::iter((0..num_files).map(move |i| {
+                Ok(Action::Add(crate::kernel::Add {
+                    path: format!("{prefix}-{i}.parquet"),
+                    size: 10,
+                    data_change: true,
+                    ..Default::default()
+                }))
+            }))
+            .boxed()
+        };
 
-        let res = log_store
-            .write_commit_entry(
-                0,
-                CommitOrBytes::LogBytes(PutPayload::new().into()),
-                Uuid::new_v4(),
+        let commit = CommitBuilder::default()
+            .with_action_stream(adds("part", 100_000))
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation.clone(),
             )
-            .await;
-        // fails if file version already exists
-        assert!(res.is_err());
+            .await
+            .unwrap();
+        assert_eq!(commit.version(), 1);
+        assert_eq!(commit.metrics.num_actions, 100_001);
+        assert_eq!(commit.snapshot().files_count(), 100_000);
+        let log_entry = table
+            .log_store()
+            .read_commit_entry(1)
+            .await
+            .unwrap()
+            .unwrap();
+        let lines: Vec<_> = std::str::from_utf8(&log_entry).unwrap().lines().collect();
+        assert_eq!(lines.len(), 100_001);
+        let commit_info = lines
+            .iter()
+            .find(|line| line.contains("commitInfo"))
+            .unwrap();
+        assert!(commit_info.contains(r#""numAddedFiles":100000"#));
 
-        // succeeds for next version
-        log_store
-            .write_commit_entry(
-                1,
-                CommitOrBytes::LogBytes(PutPayload::new().into()),
-                Uuid::new_v4(),
+        // the streamed actions are loaded back to check for conflicts with version 1
+        let commit = CommitBuilder::default()
+            .with_action_stream(adds("late", 10))
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                operation,
             )
             .await
             .unwrap();
+        assert_eq!(commit.version(), 2);
+        assert_eq!(commit.snapshot().files_count(), 100_010);
     }
 }
@@ -169,6 +169,16 @@ impl ProtocolChecker {
     ) -> Result<(), TransactionError> {
         self.can_write_to(snapshot)?;
 
+        self.check_append_only_actions(snapshot, actions, operation)
+    }
+
+    /// Check that the actions do not remove data from an append-only table
+    pub fn check_append_only_actions(
+        &self,
+        snapshot: &dyn TableReference,
+        actions: &[Action],
+        operation: &DeltaOperation,
+    ) -> Result<(), TransactionError> {
         // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#append-only-tables
         let append_only_enabled = if snapshot.protocol().min_writer_version < 2 {
             false
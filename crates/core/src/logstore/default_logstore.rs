@@ -46,6 +46,10 @@ impl LogStore for DefaultLogStore {
         "DefaultLogStore".into()
     }
 
+    fn supports_conditional_put(&self) -> bool {
+        true
+    }
+
     async fn read_commit_entry(&self, version: i64) -> DeltaResult<Option<Bytes>> {
         super::read_commit_entry(self.object_store(None).as_ref(), version).await
     }
@@ -78,7 +78,7 @@ pub use self::factories::{
     logstore_factories, object_store_factories, store_for, LogStoreFactory,
     LogStoreFactoryRegistry, ObjectStoreFactory, ObjectStoreFactoryRegistry,
 };
-pub use self::storage::utils::commit_uri_from_version;
+pub use self::storage::utils::{checksum_uri_from_version, commit_uri_from_version};
 pub use self::storage::{
     DefaultObjectStoreRegistry, DeltaIOStorageBackend, IORuntime, ObjectStoreRef,
     ObjectStoreRegistry, ObjectStoreRetryExt,
@@ -275,6 +275,15 @@ pub trait LogStore: Send + Sync + AsAny {
     /// Return the name of this LogStore implementation
     fn name(&self) -> String;
 
+    /// Whether commit entries are written directly from their bytes, e.g. with a
+    /// conditional put, which fails if the version already exists.
+    ///
+    /// Otherwise commits are staged as temporary files, which are moved into place by
+    /// [`write_commit_entry`](Self::write_commit_entry). Defaults to `false`.
+    fn supports_conditional_put(&self) -> bool {
+        false
+    }
+
     /// Trigger sync operation on log store to.
     async fn refresh(&self) -> DeltaResult<()> {
         Ok(())
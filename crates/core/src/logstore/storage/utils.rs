@@ -20,6 +20,19 @@ pub fn commit_uri_from_version(version: i64) -> Path {
     super::DELTA_LOG_PATH.child(version.as_str())
 }
 
+/// Return the uri of the checksum file of a version.
+///
+/// ```rust
+/// # use deltalake_core::logstore::*;
+/// use object_store::path::Path;
+/// let uri = checksum_uri_from_version(1);
+/// assert_eq!(uri, Path::from("_delta_log/00000000000000000001.crc"));
+/// ```
+pub fn checksum_uri_from_version(version: i64) -> Path {
+    let version = format!("{version:020}.crc");
+    super::DELTA_LOG_PATH.child(version.as_str())
+}
+
 impl TryFrom<Add> for ObjectMeta {
     type Error = DeltaTableError;
 
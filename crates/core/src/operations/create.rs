@@ -364,9 +364,9 @@ impl std::future::IntoFuture for CreateBuilder {
                         table.load().await?;
                         let remove_actions = table
                             .snapshot()?
-                            .log_data()
+                            .remove_all_files()
                             .into_iter()
-                            .map(|p| p.remove_action(true).into());
+                            .map(Action::Remove);
                         actions.extend(remove_actions);
                         Some(table.snapshot()?)
                     }
@@ -33,6 +33,7 @@ use self::{
     merge::MergeBuilder, update::UpdateBuilder, write::WriteBuilder,
 };
 use crate::errors::{DeltaResult, DeltaTableError};
+use crate::kernel::transaction::CommitMetrics;
 use crate::logstore::LogStoreRef;
 use crate::table::builder::DeltaTableBuilder;
 use crate::DeltaTable;
@@ -91,6 +92,32 @@ pub trait CustomExecuteHandler: Send + Sync {
         file_operation: bool,
         operation_id: Uuid,
     ) -> DeltaResult<()>;
+
+    // Execute arbitrary code at the start of the post commit hook with access to the commit metrics,
+    // defaults to `before_post_commit_hook`
+    async fn before_post_commit_hook_with_metrics(
+        &self,
+        log_store: &LogStoreRef,
+        file_operation: bool,
+        operation_id: Uuid,
+        _metrics: &CommitMetrics,
+    ) -> DeltaResult<()> {
+        self.before_post_commit_hook(log_store, file_operation, operation_id)
+            .await
+    }
+
+    // Execute arbitrary code at the end of the post commit hook with access to the commit metrics,
+    // defaults to `after_post_commit_hook`
+    async fn after_post_commit_hook_with_metrics(
+        &self,
+        log_store: &LogStoreRef,
+        file_operation: bool,
+        operation_id: Uuid,
+        _metrics: &CommitMetrics,
+    ) -> DeltaResult<()> {
+        self.after_post_commit_hook(log_store, file_operation, operation_id)
+            .await
+    }
 }
 
 #[allow(unused)]
@@ -45,7 +45,7 @@ use super::write::writer::{PartitionWriter, PartitionWriterConfig};
 use super::{CustomExecuteHandler, Operation};
 use crate::delta_datafusion::DeltaTableProvider;
 use crate::errors::{DeltaResult, DeltaTableError};
-use crate::kernel::transaction::{CommitBuilder, CommitProperties, DEFAULT_RETRIES, PROTOCOL};
+use crate::kernel::transaction::{default_max_retries, CommitBuilder, CommitProperties, PROTOCOL};
 use crate::kernel::{Action, Add, PartitionsExt, Remove};
 use crate::logstore::{LogStoreRef, ObjectStoreRef};
 use crate::protocol::DeltaOperation;
@@ -745,11 +745,14 @@ impl MergePlan {
 
                 debug!("committing {} actions", actions.len());
 
+                // the actions are serialized into the log entry as they are consumed rather
+                // than being copied into the commit data
+                let actions = futures::stream::iter(actions.into_iter().map(Ok)).boxed();
                 let commit = CommitBuilder::from(properties)
-                    .with_actions(actions)
+                    .with_action_stream(actions)
                     .with_operation_id(operation_id)
                     .with_post_commit_hook_handler(handle.cloned())
-                    .with_max_retries(DEFAULT_RETRIES + commits_made)
+                    .with_max_retries(default_max_retries() + commits_made)
                     .build(
                         Some(&snapshot),
                         log_store.clone(),
@@ -47,6 +47,13 @@ impl AsyncShareableBuffer {
         inner.is_empty()
     }
 
+    /// Creates a new instance writing to the provided buffer, e.g. to reuse its allocation.
+    pub fn from_vec(buffer: Vec<u8>) -> Self {
+        Self {
+            buffer: Arc::new(TokioRwLock::new(buffer)),
+        }
+    }
+
     /// Creates a new instance with the buffer initialized from the provided bytes.
     #[allow(dead_code)]
     pub fn from_bytes(bytes: &[u8]) -> Self {
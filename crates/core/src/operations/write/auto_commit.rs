@@ -0,0 +1,412 @@
+//! Writer committing the written files at regular intervals, for long running ingestion.
+//!
+//! The [AutoCommitWriter] wraps a [DeltaWriter] and commits the files written since the last
+//! commit as an append once the [FlushPolicy] is met. Commits are performed inline with the
+//! writes, so ingestion is slowed down to the pace of the commits instead of buffering an
+//! unbounded number of uncommitted files.
+//!
+//! Every commit records an app transaction of the writer, so a commit which failed after its
+//! log entry was written is detected when it is retried, instead of adding the files twice.
+//!
+//! ```rust ignore
+//! let writer = AutoCommitWriter::try_new(&table, config)?.with_flush_policy(
+//!     FlushPolicy::default()
+//!         .with_max_rows(1_000_000)
+//!         .with_max_interval(Duration::from_secs(30)),
+//! );
+//! let mut commits = writer.into_commit_stream(batches);
+//! while let Some(commit) = commits.next().await {
+//!     println!("Committed version {}", commit?.version());
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow_array::RecordBatch;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use tokio::time::Instant;
+use tracing::debug;
+use uuid::Uuid;
+
+use super::writer::{DeltaWriter, WriterConfig};
+use crate::kernel::transaction::{CommitBuilder, CommitProperties, FinalizedCommit};
+use crate::kernel::{Action, Add, Transaction};
+use crate::logstore::LogStoreRef;
+use crate::protocol::{DeltaOperation, SaveMode};
+use crate::table::state::DeltaTableState;
+use crate::{DeltaResult, DeltaTable};
+
+/// When an [AutoCommitWriter] commits the files written since its last commit.
+///
+/// A commit is performed as soon as any of the configured limits is reached. Without limits,
+/// files are only committed explicitly or when the writer is closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlushPolicy {
+    max_rows: Option<usize>,
+    max_interval: Option<Duration>,
+}
+
+impl FlushPolicy {
+    /// Commit once at least `max_rows` rows were written since the last commit
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Commit once `max_interval` passed since the last commit
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = Some(max_interval);
+        self
+    }
+}
+
+/// Writer appending batches to a table and committing them according to a [FlushPolicy]
+pub struct AutoCommitWriter {
+    writer: DeltaWriter,
+    log_store: LogStoreRef,
+    snapshot: DeltaTableState,
+    policy: FlushPolicy,
+    commit_properties: CommitProperties,
+    /// Files written since the last commit, collected as they are flushed by the writer
+    pending: Arc<parking_lot::Mutex<Vec<Add>>>,
+    /// Files of a failed commit, which are retried as the same app transaction
+    failed: Option<Vec<Add>>,
+    /// App id of the transactions recorded by the commits of the writer
+    app_id: String,
+    /// Version of the app transaction of the next commit
+    txn_version: i64,
+    rows_since_commit: usize,
+    last_commit: Instant,
+}
+
+impl std::fmt::Debug for AutoCommitWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AutoCommitWriter")
+            .field("version", &self.snapshot.version())
+            .field("policy", &self.policy)
+            .field("rows_since_commit", &self.rows_since_commit)
+            .finish()
+    }
+}
+
+impl AutoCommitWriter {
+    /// Create a writer appending to the given table.
+    ///
+    /// The writer config is validated against the metadata of the table.
+    pub fn try_new(table: &DeltaTable, config: WriterConfig) -> DeltaResult<Self> {
+        let snapshot = table.snapshot()?.clone();
+        config.validate_against(snapshot.metadata())?;
+        let log_store = table.log_store();
+
+        let pending = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let flushed = pending.clone();
+        let writer = DeltaWriter::new(log_store.object_store(None), config)
+            .with_file_flushed_callback(Arc::new(move |add: &Add| {
+                flushed.lock().push(add.clone())
+            }));
+
+        Ok(Self {
+            writer,
+            log_store,
+            snapshot,
+            policy: FlushPolicy::default(),
+            commit_properties: CommitProperties::default(),
+            pending,
+            failed: None,
+            app_id: format!("delta-rs-auto-commit-{}", Uuid::new_v4()),
+            txn_version: 0,
+            rows_since_commit: 0,
+            last_commit: Instant::now(),
+        })
+    }
+
+    /// Specify when the written files are committed
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Specify the properties of the commits, e.g. the number of conflict retries
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+
+    /// The table state as of the last commit of the writer
+    pub fn snapshot(&self) -> &DeltaTableState {
+        &self.snapshot
+    }
+
+    /// App id of the app transactions recorded by the commits of the writer
+    pub fn application_id(&self) -> &str {
+        &self.app_id
+    }
+
+    /// Whether the flush policy requires a commit
+    pub fn commit_due(&self) -> bool {
+        let rows_due = self
+            .policy
+            .max_rows
+            .is_some_and(|max_rows| self.rows_since_commit >= max_rows);
+        let interval_due = self
+            .policy
+            .max_interval
+            .is_some_and(|max_interval| self.last_commit.elapsed() >= max_interval);
+        rows_due || interval_due
+    }
+
+    /// Write a batch, committing all written files if the flush policy requires it.
+    ///
+    /// Returns the commit, if one was performed.
+    pub async fn write(&mut self, batch: &RecordBatch) -> DeltaResult<Option<FinalizedCommit>> {
+        self.writer.write(batch).await?;
+        self.rows_since_commit += batch.num_rows();
+        if self.commit_due() {
+            return self.commit().await;
+        }
+        Ok(None)
+    }
+
+    /// Flush all buffered data and commit the files written since the last commit.
+    ///
+    /// Returns no commit if no data was written since the last commit. The files of a failed
+    /// commit are committed on their own by the next call, with the same app transaction, so
+    /// they are not added again if the failed commit made it into the log after all.
+    pub async fn commit(&mut self) -> DeltaResult<Option<FinalizedCommit>> {
+        // the written files are collected by the flush callback
+        self.writer.flush_all().await?;
+        if let Some(adds) = self.failed.take() {
+            return self.commit_adds(adds).await.map(Some);
+        }
+        let adds = std::mem::take(&mut *self.pending.lock());
+        self.rows_since_commit = 0;
+        self.last_commit = Instant::now();
+        if adds.is_empty() {
+            return Ok(None);
+        }
+        self.commit_adds(adds).await.map(Some)
+    }
+
+    async fn commit_adds(&mut self, adds: Vec<Add>) -> DeltaResult<FinalizedCommit> {
+        let partition_columns = self.snapshot.metadata().partition_columns.clone();
+        let operation = DeltaOperation::Write {
+            mode: SaveMode::Append,
+            partition_by: (!partition_columns.is_empty()).then_some(partition_columns),
+            predicate: None,
+        };
+        let result = CommitBuilder::from(
+            self.commit_properties
+                .clone()
+                .with_application_transaction(Transaction::new(&self.app_id, self.txn_version)),
+        )
+        .with_actions(adds.iter().cloned().map(Action::Add).collect())
+        .build(Some(&self.snapshot), self.log_store.clone(), operation)
+        .await;
+        match result {
+            Ok(commit) => {
+                debug!(
+                    "Committed {} files as version {}",
+                    adds.len(),
+                    commit.version()
+                );
+                self.txn_version += 1;
+                self.snapshot = commit.snapshot();
+                Ok(commit)
+            }
+            Err(err) => {
+                self.failed = Some(adds);
+                Err(err)
+            }
+        }
+    }
+
+    /// Commit all remaining data and close the writer.
+    ///
+    /// Returns the last commit performed, if any.
+    pub async fn close(mut self) -> DeltaResult<Option<FinalizedCommit>> {
+        let mut last_commit = None;
+        // a failed commit is retried on its own before the remaining files
+        while let Some(commit) = self.commit().await? {
+            last_commit = Some(commit);
+        }
+        Ok(last_commit)
+    }
+
+    /// Time at which the flush interval elapses, if any
+    fn deadline(&self) -> Option<Instant> {
+        self.policy
+            .max_interval
+            .map(|max_interval| self.last_commit + max_interval)
+    }
+
+    /// Write all batches of the stream, yielding the commits as they are performed.
+    ///
+    /// Batches are only pulled from the input while the commits are consumed, which
+    /// propagates backpressure to the input when commits fall behind. Commits due to the
+    /// flush interval are also performed while waiting for the next batch. Once the input
+    /// ends, the remaining data is committed. The stream ends after the first error.
+    pub fn into_commit_stream<S>(
+        self,
+        batches: S,
+    ) -> BoxStream<'static, DeltaResult<FinalizedCommit>>
+    where
+        S: Stream<Item = DeltaResult<RecordBatch>> + Send + 'static,
+    {
+        futures::stream::unfold(Some((self, batches.boxed())), |state| async move {
+            let (mut writer, mut batches) = state?;
+            loop {
+                let next = match writer.deadline() {
+                    Some(deadline) => tokio::select! {
+                        batch = batches.next() => Some(batch),
+                        _ = tokio::time::sleep_until(deadline) => None,
+                    },
+                    None => Some(batches.next().await),
+                };
+                let result = match next {
+                    Some(Some(Ok(batch))) => writer.write(&batch).await,
+                    Some(Some(Err(err))) => Err(err),
+                    Some(None) => {
+                        return match writer.close().await {
+                            Ok(commit) => commit.map(|commit| (Ok(commit), None)),
+                            Err(err) => Some((Err(err), None)),
+                        };
+                    }
+                    // the flush interval elapsed while waiting for the next batch
+                    None => writer.commit().await,
+                };
+                match result {
+                    Ok(Some(commit)) => return Some((Ok(commit), Some((writer, batches)))),
+                    Ok(None) => continue,
+                    Err(err) => return Some((Err(err), None)),
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::config::DEFAULT_NUM_INDEX_COLS;
+    use crate::test_utils::TestLogStore;
+    use crate::writer::test_utils::{get_arrow_schema, get_delta_schema, get_record_batch};
+    use crate::DeltaOps;
+
+    async fn setup() -> (DeltaTable, WriterConfig) {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(get_delta_schema().fields().cloned())
+            .await
+            .unwrap();
+        let config = WriterConfig::new(
+            get_arrow_schema(&None),
+            vec![],
+            None,
+            None,
+            None,
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        );
+        (table, config)
+    }
+
+    #[tokio::test]
+    async fn test_commit_on_max_rows() {
+        let (table, config) = setup().await;
+        let batch = get_record_batch(None, false);
+        let mut writer = AutoCommitWriter::try_new(&table, config)
+            .unwrap()
+            .with_flush_policy(FlushPolicy::default().with_max_rows(batch.num_rows() + 1));
+
+        assert!(writer.write(&batch).await.unwrap().is_none());
+        let commit = writer.write(&batch).await.unwrap().unwrap();
+        assert_eq!(commit.version(), 1);
+        assert_eq!(writer.snapshot().version(), 1);
+        assert_eq!(writer.snapshot().files_count(), 1);
+
+        writer.write(&batch).await.unwrap();
+        let commit = writer.close().await.unwrap().unwrap();
+        assert_eq!(commit.version(), 2);
+        assert_eq!(commit.snapshot().files_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_close_without_data() {
+        let (table, config) = setup().await;
+        let writer = AutoCommitWriter::try_new(&table, config).unwrap();
+        assert!(writer.close().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_commit_stream() {
+        let (table, config) = setup().await;
+        let batch = get_record_batch(None, false);
+        let writer = AutoCommitWriter::try_new(&table, config)
+            .unwrap()
+            .with_flush_policy(FlushPolicy::default().with_max_rows(2 * batch.num_rows()));
+        let batches = futures::stream::iter((0..5).map(move |_| Ok(batch.clone())));
+
+        // two commits of two batches each, and the final commit of the remaining batch
+        let versions = writer
+            .into_commit_stream(batches)
+            .map(|commit| commit.unwrap().version())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(versions, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_commit_stream_interval() {
+        let (table, config) = setup().await;
+        let batch = get_record_batch(None, false);
+        let writer = AutoCommitWriter::try_new(&table, config)
+            .unwrap()
+            .with_flush_policy(FlushPolicy::default().with_max_interval(Duration::from_millis(50)));
+        // the input never ends, so the data is only committed once the interval elapses
+        let batches = futures::stream::iter(vec![Ok(batch)]).chain(futures::stream::pending());
+
+        let mut commits = writer.into_commit_stream(batches);
+        let commit = commits.next().await.unwrap().unwrap();
+        assert_eq!(commit.version(), 1);
+        assert_eq!(commit.snapshot().files_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_commit_which_landed() {
+        let (table, config) = setup().await;
+        let log_store = Arc::new(TestLogStore::new(table.log_store()).with_lost_acks(1));
+        let mut table = DeltaTable::new(log_store, Default::default());
+        table.load().await.unwrap();
+        let batch = get_record_batch(None, false);
+        let mut writer = AutoCommitWriter::try_new(&table, config).unwrap();
+
+        // the log entry was written, but the commit failed
+        writer.write(&batch).await.unwrap();
+        assert!(writer.commit().await.is_err());
+
+        // the retry finds the app transaction in the log and does not add the files again
+        writer.write(&batch).await.unwrap();
+        let commit = writer.commit().await.unwrap().unwrap();
+        assert!(commit.metrics.already_committed);
+        assert_eq!(commit.version(), 1);
+        assert_eq!(commit.snapshot().files_count(), 1);
+
+        let app_id = writer.application_id().to_string();
+        let commit = writer.close().await.unwrap().unwrap();
+        assert_eq!(commit.version(), 2);
+        assert_eq!(commit.snapshot().files_count(), 2);
+        assert_eq!(
+            commit
+                .snapshot()
+                .snapshot()
+                .transactions()
+                .unwrap()
+                .find(|txn| txn.app_id == app_id)
+                .map(|txn| txn.version),
+            Some(1)
+        );
+    }
+}
@@ -24,6 +24,7 @@
 //! ````
 
 pub(crate) mod async_utils;
+pub mod auto_commit;
 pub mod configs;
 pub(crate) mod execution;
 pub(crate) mod generated_columns;
@@ -55,6 +56,7 @@ use parquet::file::properties::WriterProperties;
 use schema_evolution::try_cast_schema;
 use serde::{Deserialize, Serialize};
 use tracing::log::*;
+use writer::CompressionCodec;
 
 use super::cdc::CDC_COLUMN_NAME;
 use super::datafusion_utils::Expression;
@@ -150,6 +152,8 @@ pub struct WriteBuilder {
     safe_cast: bool,
     /// Parquet writer properties
     writer_properties: Option<WriterProperties>,
+    /// Compression overriding the one of the writer properties
+    compression: Option<(CompressionCodec, Option<i32>)>,
     /// Additional information to add to the commit
     commit_properties: CommitProperties,
     /// Name of the table, only used when table doesn't exist yet
@@ -201,6 +205,7 @@ impl WriteBuilder {
             safe_cast: false,
             schema_mode: None,
             writer_properties: None,
+            compression: None,
             commit_properties: CommitProperties::default(),
             name: None,
             description: None,
@@ -274,6 +279,13 @@ impl WriteBuilder {
         self
     }
 
+    /// Compress written files with the given codec and level, overriding the compression of
+    /// the writer properties. The default level of the codec is used if no level is given.
+    pub fn with_compression(mut self, codec: CompressionCodec, level: Option<i32>) -> Self {
+        self.compression = Some((codec, level));
+        self
+    }
+
     /// Additional metadata to be added to commit info
     pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
         self.commit_properties = commit_properties;
@@ -412,9 +424,20 @@ impl std::future::IntoFuture for WriteBuilder {
     type IntoFuture = BoxFuture<'static, Self::Output>;
 
     fn into_future(self) -> Self::IntoFuture {
-        let this = self;
+        let mut this = self;
 
         Box::pin(async move {
+            if let Some((codec, level)) = this.compression {
+                this.writer_properties = Some(
+                    this.writer_properties
+                        .take()
+                        .unwrap_or_default()
+                        .into_builder()
+                        .set_compression(codec.compression(level)?)
+                        .build(),
+                );
+            }
+
             // Runs pre execution handler.
             let operation_id = this.get_operation_id();
             this.pre_execute(operation_id).await?;
@@ -666,10 +689,8 @@ impl std::future::IntoFuture for WriteBuilder {
                             }
                         }
                         _ => {
-                            let remove_actions = snapshot
-                                .log_data()
-                                .into_iter()
-                                .map(|p| p.remove_action(true).into());
+                            let remove_actions =
+                                snapshot.remove_all_files().into_iter().map(Action::Remove);
                             actions.extend(remove_actions);
                         }
                     };
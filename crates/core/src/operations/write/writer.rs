@@ -1,28 +1,42 @@
 //! Abstractions and implementations for writing data to delta tables
 
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 
-use arrow_array::RecordBatch;
-use arrow_schema::{ArrowError, SchemaRef as ArrowSchemaRef};
+use arrow_array::{RecordBatch, RecordBatchOptions};
+use arrow_ord::sort::SortOptions;
+use arrow_row::{RowConverter, SortField};
+use arrow_schema::{
+    ArrowError, DataType, Field, Schema as ArrowSchema, SchemaRef as ArrowSchemaRef,
+};
+use arrow_select::interleave::interleave_record_batch;
 use bytes::Bytes;
 use delta_kernel::expressions::Scalar;
 use futures::{StreamExt, TryStreamExt};
 use indexmap::IndexMap;
+use object_store::client::{HttpError, HttpErrorKind};
+use object_store::Error as ObjectStoreError;
 use object_store::{path::Path, ObjectStore};
-use parquet::arrow::AsyncArrowWriter;
-use parquet::basic::Compression;
-use parquet::file::properties::WriterProperties;
+use parquet::arrow::ArrowSchemaConverter;
+use parquet::arrow::{AsyncArrowWriter, PARQUET_FIELD_ID_META_KEY};
+use parquet::basic::{BrotliLevel, Compression, Encoding, GzipLevel, ZstdLevel};
+use parquet::errors::ParquetError;
+use parquet::file::properties::{EnabledStatistics, WriterProperties, WriterVersion};
+use parquet::format::{FileMetaData, KeyValue};
+use parquet::schema::types::ColumnPath;
+use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
-use tracing::debug;
+use tracing::{debug, warn};
 
 use super::async_utils::AsyncShareableBuffer;
 use crate::crate_version;
 use crate::errors::{DeltaResult, DeltaTableError};
-use crate::kernel::{Add, PartitionsExt};
+use crate::kernel::transaction::{fnv1a, RetryBackoff, FNV1A_OFFSET_BASIS};
+use crate::kernel::{Add, Metadata, PartitionsExt};
 use crate::logstore::ObjectStoreRef;
 use crate::writer::record_batch::{divide_by_partition_values, PartitionResult};
-use crate::writer::stats::create_add;
+use crate::writer::stats::{create_add, row_group_stats_from_file_metadata};
 use crate::writer::utils::{
     arrow_schema_without_partitions, next_data_path, record_batch_without_partitions,
 };
@@ -31,6 +45,55 @@ use crate::writer::utils::{
 const DEFAULT_TARGET_FILE_SIZE: usize = 104_857_600;
 const DEFAULT_WRITE_BATCH_SIZE: usize = 1024;
 const DEFAULT_UPLOAD_PART_SIZE: usize = 1024 * 1024 * 5;
+const DEFAULT_UPLOAD_CONCURRENCY: usize = 10;
+
+/// Directory files are written to before being published, see [WriterConfig::with_staging]
+pub const STAGING_PREFIX: &str = "_staging";
+
+/// Tag of [Add] actions holding the statistics of every row group of the file, see
+/// [WriterConfig::with_row_group_stats_in_tags]
+pub const ROW_GROUP_STATS_TAG: &str = "delta-rs.rowGroupStats";
+
+/// Tag of [Add] actions holding the uncompressed size of the data of the file in bytes, see
+/// [WriterConfig::with_uncompressed_size_in_tags]
+pub const UNCOMPRESSED_SIZE_TAG: &str = "delta-rs.uncompressedSizeBytes";
+
+/// Whether a failed upload may succeed when retried, e.g. after a timeout or a dropped
+/// connection.
+///
+/// Object stores report failed requests as [ObjectStoreError::Generic], so its chain of
+/// sources is searched for I/O or HTTP errors of a transient kind. All other variants, e.g.
+/// denied requests or missing paths, fail the same way when retried.
+fn is_transient_upload_error(err: &ObjectStoreError) -> bool {
+    let ObjectStoreError::Generic { source, .. } = err else {
+        return false;
+    };
+    let mut current: Option<&(dyn std::error::Error + 'static)> = Some(source.as_ref());
+    while let Some(err) = current {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io_err.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+        if let Some(http_err) = err.downcast_ref::<HttpError>() {
+            if matches!(
+                http_err.kind(),
+                HttpErrorKind::Connect | HttpErrorKind::Timeout | HttpErrorKind::Interrupted
+            ) {
+                return true;
+            }
+        }
+        current = err.source();
+    }
+    false
+}
 
 fn upload_part_size() -> usize {
     static UPLOAD_SIZE: OnceLock<usize> = OnceLock::new();
@@ -55,6 +118,103 @@ fn upload_part_size() -> usize {
     })
 }
 
+/// Compression used when no writer properties are configured, can be overridden
+/// through the `DELTARS_DEFAULT_COMPRESSION` environment variable (e.g. `zstd(3)`).
+fn default_compression() -> Compression {
+    static COMPRESSION: OnceLock<Compression> = OnceLock::new();
+    *COMPRESSION.get_or_init(|| compression_from_env("DELTARS_DEFAULT_COMPRESSION"))
+}
+
+fn compression_from_env(var: &str) -> Compression {
+    std::env::var(var)
+        .ok()
+        .and_then(|s| match Compression::from_str(&s) {
+            Ok(compression) => Some(compression),
+            Err(err) => {
+                debug!("Invalid {var} {s:?}, falling back on SNAPPY: {err}");
+                None
+            }
+        })
+        .unwrap_or(Compression::SNAPPY)
+}
+
+const COLUMN_MAPPING_ID_KEY: &str = "delta.columnMapping.id";
+
+fn column_mapping_id(field: &Field) -> Option<i64> {
+    field
+        .metadata()
+        .get(COLUMN_MAPPING_ID_KEY)
+        .and_then(|id| id.parse().ok())
+}
+
+fn nested_fields(data_type: &DataType) -> Vec<&Field> {
+    match data_type {
+        DataType::Struct(fields) => fields.iter().map(|f| f.as_ref()).collect(),
+        DataType::List(field)
+        | DataType::LargeList(field)
+        | DataType::FixedSizeList(field, _)
+        | DataType::Map(field, _) => vec![field.as_ref()],
+        _ => vec![],
+    }
+}
+
+fn max_column_mapping_id(field: &Field) -> i64 {
+    nested_fields(field.data_type())
+        .into_iter()
+        .map(max_column_mapping_id)
+        .chain(column_mapping_id(field))
+        .max()
+        .unwrap_or_default()
+}
+
+fn with_field_id(field: &Field, next_id: &mut i64) -> Field {
+    let id = column_mapping_id(field).unwrap_or_else(|| {
+        *next_id += 1;
+        *next_id
+    });
+    let data_type = match field.data_type() {
+        DataType::Struct(fields) => {
+            DataType::Struct(fields.iter().map(|f| with_field_id(f, next_id)).collect())
+        }
+        DataType::List(f) => DataType::List(Arc::new(with_field_id(f, next_id))),
+        DataType::LargeList(f) => DataType::LargeList(Arc::new(with_field_id(f, next_id))),
+        DataType::FixedSizeList(f, size) => {
+            DataType::FixedSizeList(Arc::new(with_field_id(f, next_id)), *size)
+        }
+        DataType::Map(f, sorted) => DataType::Map(Arc::new(with_field_id(f, next_id)), *sorted),
+        other => other.clone(),
+    };
+    let mut metadata = field.metadata().clone();
+    metadata.insert(PARQUET_FIELD_ID_META_KEY.to_string(), id.to_string());
+    field
+        .clone()
+        .with_data_type(data_type)
+        .with_metadata(metadata)
+}
+
+/// Annotate all fields with parquet field ids.
+///
+/// Ids are taken from the column mapping metadata where available. All other fields,
+/// including nested ones, get ids assigned in depth-first order after the highest
+/// column mapping id, so they are stable for a given schema.
+fn arrow_schema_with_field_ids(schema: &ArrowSchema) -> ArrowSchemaRef {
+    let mut next_id = schema
+        .fields()
+        .iter()
+        .map(|f| max_column_mapping_id(f))
+        .max()
+        .unwrap_or_default();
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| with_field_id(f, &mut next_id))
+        .collect::<Vec<_>>();
+    Arc::new(ArrowSchema::new_with_metadata(
+        fields,
+        schema.metadata().clone(),
+    ))
+}
+
 #[derive(thiserror::Error, Debug)]
 enum WriteError {
     #[error("Unexpected Arrow schema: got: {schema}, expected: {expected_schema}")]
@@ -76,6 +236,120 @@ enum WriteError {
 
     #[error("Error partitioning record batch: {0}")]
     Partitioning(String),
+
+    #[error("Cannot use byte stream split encoding for column {column}: {reason}")]
+    ByteStreamSplit {
+        column: String,
+        reason: &'static str,
+    },
+
+    #[error("Invalid compression level {level} for {codec:?}: {reason}")]
+    InvalidCompressionLevel {
+        codec: CompressionCodec,
+        level: i32,
+        reason: String,
+    },
+
+    #[error("Error converting the file schema to parquet: {source}")]
+    ParquetSchema { source: ParquetError },
+
+    #[error("Cannot write bloom filter for column {column}: {reason}")]
+    BloomFilter {
+        column: String,
+        reason: &'static str,
+    },
+
+    #[error("Cannot set the encoding of column {column}: {reason}")]
+    ColumnEncoding {
+        column: String,
+        reason: &'static str,
+    },
+
+    #[error("Upload concurrency must be at least 1")]
+    InvalidUploadConcurrency,
+
+    #[error("Maximum number of rows per file must be at least 1")]
+    InvalidMaxRowsPerFile,
+
+    #[error("write_batch_size of {write_batch_size} rows exceeds the max_row_group_size of {max_row_group_size} rows of the writer properties")]
+    WriteBatchSizeExceedsRowGroupSize {
+        write_batch_size: usize,
+        max_row_group_size: usize,
+    },
+
+    #[error("Cannot sort by column {column}, it is not a top-level column of the written files")]
+    SortColumn { column: String },
+
+    #[error("The writer of partition {0} is already closed")]
+    PartitionWriterClosed(Path),
+}
+
+/// Check whether arrays of one type can be cast to the other without changing their values
+fn is_safe_adaptation(from: &DataType, to: &DataType) -> bool {
+    match (from, to) {
+        (DataType::Dictionary(_, value), to) if value.as_ref() == to => true,
+        (from, DataType::Dictionary(_, value)) if value.as_ref() == from => true,
+        (from, to) => from == to,
+    }
+}
+
+/// Check whether the schema contains the column with the given dot separated path
+fn has_column_path(schema: &ArrowSchema, path: &str) -> bool {
+    let mut parts = path.split('.');
+    let Some(Ok(mut field)) = parts.next().map(|name| schema.field_with_name(name)) else {
+        return false;
+    };
+    for name in parts {
+        match field.data_type() {
+            DataType::Struct(fields) => match fields.find(name) {
+                Some((_, child)) => field = child.as_ref(),
+                None => return false,
+            },
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Adapt a record batch to the expected schema, if it only differs from it in safe ways.
+///
+/// See [PartitionWriterConfig::with_schema_adaptation] for the supported differences.
+fn adapt_batch(batch: &RecordBatch, schema: &ArrowSchemaRef) -> Result<RecordBatch, WriteError> {
+    let batch_schema = batch.schema();
+    let mismatch = || WriteError::SchemaMismatch {
+        schema: batch_schema.clone(),
+        expected_schema: schema.clone(),
+    };
+    if batch_schema.fields().len() != schema.fields().len() {
+        return Err(mismatch());
+    }
+
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(batch_schema.fields())
+        .zip(schema.fields())
+        .map(|((column, field), expected)| {
+            if field.name() != expected.name()
+                || (field.is_nullable() && !expected.is_nullable())
+                || !is_safe_adaptation(field.data_type(), expected.data_type())
+            {
+                return Err(mismatch());
+            }
+            if field.data_type() == expected.data_type() {
+                Ok(column.clone())
+            } else {
+                Ok(arrow_cast::cast(column, expected.data_type())?)
+            }
+        })
+        .collect::<Result<Vec<_>, WriteError>>()?;
+
+    let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        columns,
+        &options,
+    )?)
 }
 
 impl From<WriteError> for DeltaTableError {
@@ -92,6 +366,168 @@ impl From<WriteError> for DeltaTableError {
     }
 }
 
+/// Callback invoked with the [Add] action of every file a [PartitionWriter] flushes to storage
+pub type FileFlushedFn = Arc<dyn Fn(&Add) + Send + Sync>;
+
+/// Target file size of a partition given its partition values, `None` to use the target
+/// file size of the [WriterConfig]
+pub type PartitionTargetSizeFn =
+    Arc<dyn Fn(&IndexMap<String, Scalar>) -> Option<usize> + Send + Sync>;
+
+/// Generates the path of a data file from the partition prefix, the part counter and the
+/// writer id. The returned path is relative to the table root.
+pub type DataPathFn = Arc<dyn Fn(&Path, usize, &uuid::Uuid) -> Path + Send + Sync>;
+
+/// Wrapper to make a [DataPathFn] part of the debuggable writer configs
+#[derive(Clone)]
+struct DataPathGenerator(DataPathFn);
+
+impl std::fmt::Debug for DataPathGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DataPathFn")
+    }
+}
+
+/// Encodes the partition values of written files into the directory the files are written to.
+///
+/// Delta readers take the partition values from the `partitionValues` of the [Add] actions
+/// rather than from the paths, so any layout can be read back by them. External readers
+/// deriving partition values from the paths, e.g. catalogs expecting Hive style directories,
+/// rely on the encoding being reversible, which is the responsibility of the encoder.
+///
+/// The encoded path must be a valid relative object store path. Partition writers created
+/// directly use the encoding by passing it as the `path_prefix` of
+/// [PartitionWriterConfig::try_new].
+pub trait PartitionPathEncoder: std::fmt::Debug + Send + Sync {
+    /// The directory of the partition, relative to the table root
+    fn encode(&self, partition_values: &IndexMap<String, Scalar>) -> String;
+}
+
+/// Encodes partitions as nested `<column>=<value>` directories, the default layout
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HivePartitionPathEncoder;
+
+impl PartitionPathEncoder for HivePartitionPathEncoder {
+    fn encode(&self, partition_values: &IndexMap<String, Scalar>) -> String {
+        partition_values.hive_partition_path()
+    }
+}
+
+/// Order in which a [DeltaWriter] flushes its partitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PartitionFlushOrder {
+    /// Flush partitions in no particular order
+    #[default]
+    Unordered,
+    /// Flush partitions sorted ascending by their partition path
+    Ascending,
+    /// Flush partitions sorted descending by their partition path
+    Descending,
+}
+
+impl PartitionFlushOrder {
+    fn sort<K: Ord, T>(&self, partitions: &mut [(K, T)]) {
+        match self {
+            Self::Unordered => {}
+            Self::Ascending => partitions.sort_by(|(a, _), (b, _)| a.cmp(b)),
+            Self::Descending => partitions.sort_by(|(a, _), (b, _)| b.cmp(a)),
+        }
+    }
+}
+
+/// Compression codecs for written parquet files, see [WriterConfig::with_compression]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionCodec {
+    /// No compression
+    Uncompressed,
+    /// Snappy compression
+    Snappy,
+    /// Gzip compression, supporting levels 0 to 9
+    Gzip,
+    /// Brotli compression, supporting levels 0 to 11
+    Brotli,
+    /// LZ4 compression without the hadoop framing
+    Lz4Raw,
+    /// Zstd compression, supporting levels 1 to 22
+    Zstd,
+}
+
+impl CompressionCodec {
+    /// Resolve the parquet compression for the codec, using the default level of the codec
+    /// if no level is given.
+    ///
+    /// Fails if the level is out of range for the codec, or the codec has no levels.
+    pub fn compression(self, level: Option<i32>) -> DeltaResult<Compression> {
+        Ok(self.try_compression(level)?)
+    }
+
+    fn try_compression(self, level: Option<i32>) -> Result<Compression, WriteError> {
+        let invalid = |level: i32, reason: String| WriteError::InvalidCompressionLevel {
+            codec: self,
+            level,
+            reason,
+        };
+        let unsigned_level = |level: i32| {
+            u32::try_from(level).map_err(|_| invalid(level, "must not be negative".into()))
+        };
+        Ok(match (self, level) {
+            (Self::Uncompressed, None) => Compression::UNCOMPRESSED,
+            (Self::Snappy, None) => Compression::SNAPPY,
+            (Self::Lz4Raw, None) => Compression::LZ4_RAW,
+            (Self::Uncompressed | Self::Snappy | Self::Lz4Raw, Some(level)) => {
+                return Err(invalid(level, "the codec has no levels".into()))
+            }
+            (Self::Gzip, level) => Compression::GZIP(match level {
+                Some(level) => GzipLevel::try_new(unsigned_level(level)?)
+                    .map_err(|err| invalid(level, err.to_string()))?,
+                None => GzipLevel::default(),
+            }),
+            (Self::Brotli, level) => Compression::BROTLI(match level {
+                Some(level) => BrotliLevel::try_new(unsigned_level(level)?)
+                    .map_err(|err| invalid(level, err.to_string()))?,
+                None => BrotliLevel::default(),
+            }),
+            (Self::Zstd, level) => Compression::ZSTD(match level {
+                Some(level) => {
+                    ZstdLevel::try_new(level).map_err(|err| invalid(level, err.to_string()))?
+                }
+                None => ZstdLevel::default(),
+            }),
+        })
+    }
+}
+
+/// Behavior of a [PartitionWriter] when a single chunk of `write_batch_size` rows
+/// is estimated to exceed the `target_file_size`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OversizedChunkBehavior {
+    /// Log a warning and keep writing chunks of `write_batch_size` rows
+    #[default]
+    Warn,
+    /// Reduce the number of rows per chunk so the target file size can be honored
+    Split,
+}
+
+/// Behavior of a [PartitionWriter] when `write_batch_size` exceeds the maximum row group size
+/// of the writer properties, which would split every chunk into degenerate row groups
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WriteBatchSizeBehavior {
+    /// Log a warning and write chunks of at most the maximum row group size
+    #[default]
+    Clamp,
+    /// Fail with a generic error naming both sizes when creating the writer
+    Error,
+}
+
+/// Settings of the bloom filters written for columns, see [WriterConfig::with_bloom_filter_columns]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BloomFilterOptions {
+    /// False positive probability of the filters, the parquet default is used if not set
+    pub fpp: Option<f64>,
+    /// Expected number of distinct values per row group, the parquet default is used if not set
+    pub ndv: Option<u64>,
+}
+
 /// Configuration to write data into Delta tables
 #[derive(Debug)]
 pub struct WriterConfig {
@@ -110,10 +546,73 @@ pub struct WriterConfig {
     num_indexed_cols: i32,
     /// Stats columns, specific columns to collect stats from, takes precedence over num_indexed_cols
     stats_columns: Option<Vec<String>>,
+    /// Order in which partitions are flushed
+    partition_flush_order: PartitionFlushOrder,
+    /// Embed parquet field ids in the written files
+    field_ids: bool,
+    /// Behavior when a chunk of `write_batch_size` rows exceeds the target file size
+    oversized_chunk_behavior: OversizedChunkBehavior,
+    /// Behavior when `write_batch_size` exceeds the maximum row group size
+    write_batch_size_behavior: WriteBatchSizeBehavior,
+    /// Size written files are kept below, based on the in-memory size of the written data
+    max_file_size: Option<usize>,
+    /// Size below which written files are reported as undersized
+    min_file_size: Option<usize>,
+    /// Floating point columns written with byte stream split encoding
+    byte_stream_split: Vec<String>,
+    /// Columns written with bloom filters
+    bloom_filter_columns: Vec<String>,
+    /// Settings of the bloom filters
+    bloom_filter_options: BloomFilterOptions,
+    /// Encodings of individual columns, and whether they are dictionary encoded
+    column_encodings: Vec<(String, Encoding, bool)>,
+    /// Compression overriding the one of the writer properties
+    compression: Option<(CompressionCodec, Option<i32>)>,
+    /// Parquet writer version overriding the one of the writer properties
+    writer_version: Option<WriterVersion>,
+    /// Custom strategy for the paths of written files
+    data_path_fn: Option<DataPathGenerator>,
+    /// Layout of the partition directories
+    partition_path_encoder: Arc<dyn PartitionPathEncoder>,
+    /// Key-value metadata embedded in the footer of written files
+    parquet_kv_metadata: Vec<(String, Option<String>)>,
+    /// Adapt batches to the table schema if they only differ in safe ways
+    schema_adaptation: bool,
+    /// Shrink chunks as the buffered data approaches the target file size
+    adaptive_chunk_size: bool,
+    /// Upper bound for the data buffered across all partition writers
+    max_total_buffered_bytes: Option<usize>,
+    /// Maximum number of parts uploaded concurrently per file
+    upload_concurrency: usize,
+    /// Reuse buffers across the partition writers
+    buffer_pooling: bool,
+    /// Size below which files are written with a single put
+    multipart_threshold: usize,
+    /// Seed the writer ids are derived from, instead of generating them randomly
+    naming_seed: Option<u64>,
+    /// Columns the rows of each file are sorted by, and whether they are sorted ascending
+    sort_columns: Vec<(String, bool)>,
+    /// Record the statistics of every row group in the tags of the [Add] actions
+    row_group_stats_in_tags: bool,
+    /// Record the uncompressed size of the data in the tags of the [Add] actions
+    uncompressed_size_in_tags: bool,
+    /// Maximum number of rows written to a single file
+    max_rows_per_file: Option<usize>,
+    /// Maximum number of files flushed concurrently across all partition writers
+    max_concurrent_flushes: Option<usize>,
+    /// Number of times an upload failing with a transient error is retried
+    upload_retries: usize,
+    /// Delay between the retries of an upload
+    upload_retry_backoff: RetryBackoff,
+    /// Write files to a staging prefix until they are published
+    staging: bool,
 }
 
 impl WriterConfig {
     /// Create a new instance of [WriterConfig].
+    ///
+    /// When no `writer_properties` are given, files are compressed with SNAPPY unless a different
+    /// default is configured through the `DELTARS_DEFAULT_COMPRESSION` environment variable.
     pub fn new(
         table_schema: ArrowSchemaRef,
         partition_columns: Vec<String>,
@@ -125,7 +624,7 @@ impl WriterConfig {
     ) -> Self {
         let writer_properties = writer_properties.unwrap_or_else(|| {
             WriterProperties::builder()
-                .set_compression(Compression::SNAPPY)
+                .set_compression(default_compression())
                 .build()
         });
         let target_file_size = target_file_size.unwrap_or(DEFAULT_TARGET_FILE_SIZE);
@@ -139,13 +638,519 @@ impl WriterConfig {
             write_batch_size,
             num_indexed_cols,
             stats_columns,
+            partition_flush_order: PartitionFlushOrder::default(),
+            field_ids: false,
+            oversized_chunk_behavior: OversizedChunkBehavior::default(),
+            write_batch_size_behavior: WriteBatchSizeBehavior::default(),
+            max_file_size: None,
+            min_file_size: None,
+            byte_stream_split: Vec::new(),
+            bloom_filter_columns: Vec::new(),
+            bloom_filter_options: BloomFilterOptions::default(),
+            column_encodings: Vec::new(),
+            compression: None,
+            writer_version: None,
+            data_path_fn: None,
+            partition_path_encoder: Arc::new(HivePartitionPathEncoder),
+            parquet_kv_metadata: Vec::new(),
+            schema_adaptation: false,
+            adaptive_chunk_size: false,
+            max_total_buffered_bytes: None,
+            upload_concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+            buffer_pooling: false,
+            multipart_threshold: 0,
+            naming_seed: None,
+            sort_columns: Vec::new(),
+            row_group_stats_in_tags: false,
+            uncompressed_size_in_tags: false,
+            max_rows_per_file: None,
+            max_concurrent_flushes: None,
+            upload_retries: 0,
+            upload_retry_backoff: RetryBackoff::default(),
+            staging: false,
+        }
+    }
+
+    /// Specify the order in which partitions are flushed to storage
+    pub fn with_partition_flush_order(mut self, order: PartitionFlushOrder) -> Self {
+        self.partition_flush_order = order;
+        self
+    }
+
+    /// Embed parquet field ids in the written files for readers resolving columns by id.
+    ///
+    /// Ids are taken from the `delta.columnMapping.id` field metadata where available.
+    pub fn with_field_ids(mut self, field_ids: bool) -> Self {
+        self.field_ids = field_ids;
+        self
+    }
+
+    /// Specify how to handle chunks of `write_batch_size` rows exceeding the target file size
+    pub fn with_oversized_chunk_behavior(mut self, behavior: OversizedChunkBehavior) -> Self {
+        self.oversized_chunk_behavior = behavior;
+        self
+    }
+
+    /// Specify how to handle a `write_batch_size` exceeding the maximum row group size of the
+    /// writer properties
+    pub fn with_write_batch_size_behavior(mut self, behavior: WriteBatchSizeBehavior) -> Self {
+        self.write_batch_size_behavior = behavior;
+        self
+    }
+
+    /// Specify a size written files are kept below.
+    ///
+    /// Before a chunk is written, the file is flushed if the data written so far plus the
+    /// in-memory size of the chunk, as an upper bound of its encoded size, would exceed this
+    /// size. Only a single row larger than the cap is written to a file exceeding it.
+    pub fn with_max_file_size(mut self, max_file_size: usize) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Specify a minimum file size. Files smaller than this, e.g. the remainders of sparsely
+    /// populated partitions flushed on close, are reported in the [WriterMetrics].
+    pub fn with_min_file_size(mut self, min_file_size: usize) -> Self {
+        self.min_file_size = Some(min_file_size);
+        self
+    }
+
+    /// Compress written files with the given codec and level, overriding the compression of
+    /// the writer properties.
+    ///
+    /// The default level of the codec is used if no level is given. Invalid levels fail the
+    /// first write.
+    pub fn with_compression(mut self, codec: CompressionCodec, level: Option<i32>) -> Self {
+        self.compression = Some((codec, level));
+        self
+    }
+
+    /// Write files with the given parquet writer version, overriding the one of the writer
+    /// properties.
+    ///
+    /// [`WriterVersion::PARQUET_2_0`] writes v2 data pages, which allow for more efficient
+    /// encodings of some types but are not supported by all readers.
+    pub fn with_writer_version(mut self, writer_version: WriterVersion) -> Self {
+        self.writer_version = Some(writer_version);
+        self
+    }
+
+    /// Write the given top-level floating point columns with byte stream split encoding,
+    /// which usually compresses floating point data better than plain encoding.
+    ///
+    /// Requires the parquet 2.0 writer version, see [WriterConfig::with_writer_version].
+    pub fn with_byte_stream_split(
+        mut self,
+        columns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.byte_stream_split = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Write bloom filters for the given columns, which allow readers to skip row groups not
+    /// containing a value in point lookups.
+    ///
+    /// Nested columns are referenced by their dot separated path. Partition columns are not
+    /// written to the files and can not have bloom filters.
+    pub fn with_bloom_filter_columns(
+        mut self,
+        columns: impl IntoIterator<Item = impl Into<String>>,
+        options: BloomFilterOptions,
+    ) -> Self {
+        self.bloom_filter_columns = columns.into_iter().map(Into::into).collect();
+        self.bloom_filter_options = options;
+        self
+    }
+
+    /// Write the given columns with a specific encoding, and whether dictionary encoding is
+    /// enabled for them, e.g. to disable dictionaries for high cardinality string columns.
+    ///
+    /// Nested columns are referenced by their dot separated path. The encoding is used for
+    /// pages which are not dictionary encoded, so it can not be a dictionary encoding
+    /// itself. These settings take precedence over the writer properties.
+    pub fn with_column_encoding(mut self, encodings: Vec<(String, Encoding, bool)>) -> Self {
+        self.column_encodings = encodings;
+        self
+    }
+
+    /// Generate the paths of written files with a custom strategy, see
+    /// [PartitionWriterConfig::with_data_path_fn]
+    pub fn with_data_path_fn(mut self, data_path_fn: DataPathFn) -> Self {
+        self.data_path_fn = Some(DataPathGenerator(data_path_fn));
+        self
+    }
+
+    /// Write the files of each partition to a directory with a custom layout instead of the
+    /// Hive style `<column>=<value>` directories, see [PartitionPathEncoder]
+    pub fn with_partition_path_encoder(mut self, encoder: Arc<dyn PartitionPathEncoder>) -> Self {
+        self.partition_path_encoder = encoder;
+        self
+    }
+
+    /// Embed key-value metadata in the footer of every written parquet file, in addition to
+    /// any key-value metadata of the writer properties.
+    ///
+    /// Unlike the tags of the [Add] action, this metadata lives in the files themselves.
+    pub fn with_parquet_kv_metadata(mut self, metadata: Vec<(String, Option<String>)>) -> Self {
+        self.parquet_kv_metadata = metadata;
+        self
+    }
+
+    /// Adapt written batches to the table schema instead of failing, as long as they only
+    /// differ in safe ways, see [PartitionWriterConfig::with_schema_adaptation]
+    pub fn with_schema_adaptation(mut self, schema_adaptation: bool) -> Self {
+        self.schema_adaptation = schema_adaptation;
+        self
+    }
+
+    /// Adjust the number of rows written between file size checks to the observed growth of
+    /// the buffered data, see [PartitionWriterConfig::with_adaptive_chunk_size]
+    pub fn with_adaptive_chunk_size(mut self, adaptive_chunk_size: bool) -> Self {
+        self.adaptive_chunk_size = adaptive_chunk_size;
+        self
+    }
+
+    /// Bound the data buffered in memory across all partitions.
+    ///
+    /// Without a budget, every partition buffers up to `target_file_size` bytes before its
+    /// file is written, so memory grows with the number of partitions written at once. Once
+    /// the budget is exceeded, the partitions buffering the most data are flushed early,
+    /// resulting in smaller files.
+    pub fn with_max_total_buffered_bytes(mut self, max_total_buffered_bytes: usize) -> Self {
+        self.max_total_buffered_bytes = Some(max_total_buffered_bytes);
+        self
+    }
+
+    /// Specify the maximum number of parts uploaded concurrently per file, see
+    /// [PartitionWriterConfig::with_upload_concurrency]
+    pub fn with_upload_concurrency(mut self, upload_concurrency: usize) -> Self {
+        self.upload_concurrency = upload_concurrency;
+        self
+    }
+
+    /// Share a [BufferPool] between the partition writers of a [DeltaWriter].
+    ///
+    /// By default every file is buffered in a newly allocated buffer. With pooling, the
+    /// buffers of uploaded files are handed to the next files written, which reduces
+    /// allocations when writing to many partitions. Pooled buffers are retained until the
+    /// writer is closed.
+    pub fn with_buffer_pooling(mut self, buffer_pooling: bool) -> Self {
+        self.buffer_pooling = buffer_pooling;
+        self
+    }
+
+    /// Write files smaller than the threshold with a single put, see
+    /// [PartitionWriterConfig::with_multipart_threshold]
+    pub fn with_multipart_threshold(mut self, multipart_threshold: usize) -> Self {
+        self.multipart_threshold = multipart_threshold;
+        self
+    }
+
+    /// Derive the writer ids, and thereby the file names, from a seed instead of generating
+    /// them randomly, so writing the same data with the same seed yields the same files.
+    ///
+    /// The writer id of a partition depends on the seed, the partition and the number of
+    /// writers previously opened for the partition, so names stay unique within a write.
+    /// Writes to the same table must use different seeds, as files with the same name
+    /// overwrite each other.
+    pub fn with_naming_seed(mut self, naming_seed: u64) -> Self {
+        self.naming_seed = Some(naming_seed);
+        self
+    }
+
+    /// Sort the rows of every written file by the given columns, see
+    /// [PartitionWriterConfig::with_sort_columns]
+    pub fn with_sort_columns(mut self, sort_columns: Vec<(String, bool)>) -> Self {
+        self.sort_columns = sort_columns;
+        self
+    }
+
+    /// Record the statistics of every row group in the [ROW_GROUP_STATS_TAG] tag of the
+    /// [Add] actions, for readers pruning individual row groups.
+    ///
+    /// The tag holds a json array with the statistics of each row group, in the format of
+    /// the file statistics and for the same columns. Disabled by default, since it enlarges
+    /// the commits.
+    pub fn with_row_group_stats_in_tags(mut self, row_group_stats_in_tags: bool) -> Self {
+        self.row_group_stats_in_tags = row_group_stats_in_tags;
+        self
+    }
+
+    /// Record the uncompressed size in bytes of the data of every file in the
+    /// [UNCOMPRESSED_SIZE_TAG] tag of the [Add] actions, e.g. for capacity planning.
+    ///
+    /// The size is the one reported in the parquet metadata of the file. Disabled by
+    /// default, for readers rejecting unknown tags.
+    pub fn with_uncompressed_size_in_tags(mut self, uncompressed_size_in_tags: bool) -> Self {
+        self.uncompressed_size_in_tags = uncompressed_size_in_tags;
+        self
+    }
+
+    /// Specify the maximum number of rows written to a single file, see
+    /// [PartitionWriterConfig::with_max_rows_per_file]
+    pub fn with_max_rows_per_file(mut self, max_rows_per_file: usize) -> Self {
+        self.max_rows_per_file = Some(max_rows_per_file);
+        self
+    }
+
+    /// Bound the number of files flushed to storage at once across all partitions.
+    ///
+    /// Partitions reaching their target file size wait for a running flush to complete
+    /// before their file is written, so writes slow down to the pace of the object store
+    /// instead of piling up buffered files in memory. Values below 1 are treated as 1.
+    pub fn with_max_concurrent_flushes(mut self, max_concurrent_flushes: usize) -> Self {
+        self.max_concurrent_flushes = Some(max_concurrent_flushes.max(1));
+        self
+    }
+
+    /// Retry uploads of files failing with a transient object store error, e.g. a timeout
+    /// or a dropped connection, up to `max_retries` times.
+    ///
+    /// Parts of a multipart upload can not be re-sent individually, so the whole upload is
+    /// aborted and restarted from the buffered file. Other errors fail the write immediately.
+    /// Defaults to no retries.
+    pub fn with_upload_retries(mut self, max_retries: usize, backoff: RetryBackoff) -> Self {
+        self.upload_retries = max_retries;
+        self.upload_retry_backoff = backoff;
+        self
+    }
+
+    /// Write all files below a `_staging/<uuid>/` prefix instead of their partition
+    /// directories, so no half-written partitions are visible until the files are moved to
+    /// their final paths with [DeltaWriter::publish].
+    ///
+    /// The staging directory is ignored by readers like all directories starting with an
+    /// underscore. Files of writers which are never published remain there.
+    pub fn with_staging(mut self, staging: bool) -> Self {
+        self.staging = staging;
+        self
+    }
+
+    /// Check that the config can be used to write to the table described by `metadata`.
+    ///
+    /// The partition columns have to be the ones of the table, in the same order, the table
+    /// schema has to contain the same columns with compatible types, and all stats columns
+    /// have to exist. This surfaces mismatches before any data is written.
+    pub fn validate_against(&self, metadata: &Metadata) -> DeltaResult<()> {
+        if self.partition_columns != metadata.partition_columns {
+            return Err(DeltaTableError::SchemaMismatch {
+                msg: format!(
+                    "partition columns {:?} do not match the table partition columns {:?}",
+                    self.partition_columns, metadata.partition_columns
+                ),
+            });
+        }
+
+        let expected_schema = ArrowSchema::try_from(&metadata.schema()?)?;
+        if self.table_schema.fields().len() != expected_schema.fields().len() {
+            return Err(DeltaTableError::SchemaMismatch {
+                msg: format!(
+                    "writer schema has {} columns, the table schema has {}",
+                    self.table_schema.fields().len(),
+                    expected_schema.fields().len()
+                ),
+            });
+        }
+        for expected in expected_schema.fields() {
+            let field = self
+                .table_schema
+                .field_with_name(expected.name())
+                .map_err(|_| DeltaTableError::SchemaMismatch {
+                    msg: format!(
+                        "column {} is missing from the writer schema",
+                        expected.name()
+                    ),
+                })?;
+            if !field.data_type().equals_datatype(expected.data_type())
+                && !is_safe_adaptation(field.data_type(), expected.data_type())
+            {
+                return Err(DeltaTableError::SchemaMismatch {
+                    msg: format!(
+                        "column {} has type {}, the table column has type {}",
+                        expected.name(),
+                        field.data_type(),
+                        expected.data_type()
+                    ),
+                });
+            }
+        }
+
+        for column in self.stats_columns.iter().flatten() {
+            if !has_column_path(&self.table_schema, column) {
+                return Err(DeltaTableError::Generic(format!(
+                    "Stats column {column} does not exist in the table schema"
+                )));
+            }
         }
+        Ok(())
     }
 
     /// Schema of files written to disk
     pub fn file_schema(&self) -> ArrowSchemaRef {
         arrow_schema_without_partitions(&self.table_schema, &self.partition_columns)
     }
+
+    /// Writer properties for the written files, including per column encodings
+    fn file_writer_properties(&self) -> Result<WriterProperties, WriteError> {
+        if self.byte_stream_split.is_empty()
+            && self.bloom_filter_columns.is_empty()
+            && self.column_encodings.is_empty()
+            && self.stats_columns.is_none()
+            && self.parquet_kv_metadata.is_empty()
+            && self.compression.is_none()
+            && self.writer_version.is_none()
+        {
+            return Ok(self.writer_properties.clone());
+        }
+
+        let schema = self.file_schema();
+        let mut builder = self.writer_properties.clone().into_builder();
+        if let Some((codec, level)) = self.compression {
+            builder = builder.set_compression(codec.try_compression(level)?);
+        }
+        let writer_version = self
+            .writer_version
+            .unwrap_or_else(|| self.writer_properties.writer_version());
+        builder = builder.set_writer_version(writer_version);
+        if !self.parquet_kv_metadata.is_empty() {
+            let mut kv_metadata = self
+                .writer_properties
+                .key_value_metadata()
+                .cloned()
+                .unwrap_or_default();
+            kv_metadata.extend(
+                self.parquet_kv_metadata
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+            );
+            builder = builder.set_key_value_metadata(Some(kv_metadata));
+        }
+        for column in &self.byte_stream_split {
+            let invalid = |reason| WriteError::ByteStreamSplit {
+                column: column.clone(),
+                reason,
+            };
+            if writer_version != WriterVersion::PARQUET_2_0 {
+                return Err(invalid("requires the parquet 2.0 writer version"));
+            }
+            let field = schema
+                .field_with_name(column)
+                .map_err(|_| invalid("column not found in the file schema"))?;
+            if !matches!(
+                field.data_type(),
+                DataType::Float16 | DataType::Float32 | DataType::Float64
+            ) {
+                return Err(invalid("not a floating point column"));
+            }
+            // dictionary encoding takes precedence over the column encoding
+            let path = ColumnPath::from(column.as_str());
+            builder = builder
+                .set_column_dictionary_enabled(path.clone(), false)
+                .set_column_encoding(path, Encoding::BYTE_STREAM_SPLIT);
+        }
+        for column in &self.bloom_filter_columns {
+            let invalid = |reason| WriteError::BloomFilter {
+                column: column.clone(),
+                reason,
+            };
+            if self.partition_columns.contains(column) {
+                return Err(invalid("partition columns are not written to the files"));
+            }
+            if !has_column_path(&self.table_schema, column) {
+                return Err(invalid("column not found in the table schema"));
+            }
+            let path = ColumnPath::new(column.split('.').map(String::from).collect());
+            builder = builder.set_column_bloom_filter_enabled(path.clone(), true);
+            if let Some(fpp) = self.bloom_filter_options.fpp {
+                if !(fpp > 0.0 && fpp < 1.0) {
+                    return Err(invalid(
+                        "false positive probability must be between 0 and 1 exclusive",
+                    ));
+                }
+                builder = builder.set_column_bloom_filter_fpp(path.clone(), fpp);
+            }
+            if let Some(ndv) = self.bloom_filter_options.ndv {
+                builder = builder.set_column_bloom_filter_ndv(path, ndv);
+            }
+        }
+        for (column, encoding, dictionary_enabled) in &self.column_encodings {
+            let invalid = |reason| WriteError::ColumnEncoding {
+                column: column.clone(),
+                reason,
+            };
+            if self.partition_columns.contains(column) {
+                return Err(invalid("partition columns are not written to the files"));
+            }
+            if !has_column_path(&self.table_schema, column) {
+                return Err(invalid("column not found in the table schema"));
+            }
+            if matches!(
+                encoding,
+                Encoding::PLAIN_DICTIONARY | Encoding::RLE_DICTIONARY
+            ) {
+                return Err(invalid(
+                    "dictionary encoding is enabled with the dictionary flag",
+                ));
+            }
+            let path = ColumnPath::new(column.split('.').map(String::from).collect());
+            builder = builder
+                .set_column_dictionary_enabled(path.clone(), *dictionary_enabled)
+                .set_column_encoding(path, *encoding);
+        }
+        if let Some(stats_columns) = &self.stats_columns {
+            // statistics of other columns are never collected into the add actions
+            let stats_columns = stats_columns
+                .iter()
+                .map(|column| {
+                    column
+                        .replace('`', "")
+                        .split('.')
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let descriptor = ArrowSchemaConverter::new()
+                .convert(&schema)
+                .map_err(|source| WriteError::ParquetSchema { source })?;
+            for column in descriptor.columns() {
+                // leaves of nested stats columns are collected as well
+                let collected = stats_columns
+                    .iter()
+                    .any(|stats_column| column.path().parts().starts_with(stats_column));
+                if !collected {
+                    builder = builder.set_column_statistics_enabled(
+                        column.path().clone(),
+                        EnabledStatistics::None,
+                    );
+                }
+            }
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Metrics of the files written by a [DeltaWriter]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WriterMetrics {
+    /// Number of files written
+    pub num_files: usize,
+    /// Number of files smaller than the configured minimum file size
+    pub num_files_below_min_size: usize,
+}
+
+/// Writer id derived from the naming seed, the partition and the number of writers previously
+/// opened for the partition.
+///
+/// Uses 64 bit FNV-1a hashes, which are stable across processes and releases. The id is
+/// formatted as a version 4 uuid, like random writer ids.
+fn seeded_writer_id(seed: u64, partition_key: &Path, generation: u64) -> uuid::Uuid {
+    let input = [partition_key.as_ref().as_bytes(), &generation.to_le_bytes()].concat();
+    // the halves of the id are hashed from different offsets to fill all 128 bits
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&fnv1a(FNV1A_OFFSET_BASIS ^ seed, &input).to_be_bytes());
+    bytes[8..]
+        .copy_from_slice(&fnv1a(FNV1A_OFFSET_BASIS.rotate_left(32) ^ seed, &input).to_be_bytes());
+    uuid::Builder::from_random_bytes(bytes).into_uuid()
 }
 
 /// A parquet writer implementation tailored to the needs of writing data to a delta table.
@@ -156,34 +1161,154 @@ pub struct DeltaWriter {
     config: WriterConfig,
     /// partition writers for individual partitions
     partition_writers: HashMap<Path, PartitionWriter>,
+    /// callback invoked for every file written by the partition writers
+    on_file_flushed: Option<FileFlushedFn>,
+    /// callback overriding the target file size of individual partitions
+    partition_target_size: Option<PartitionTargetSizeFn>,
+    /// buffers shared by the partition writers, if pooling is enabled
+    buffer_pool: Option<BufferPool>,
+    /// number of writers opened per partition, to derive unique seeded writer ids
+    writer_generations: HashMap<Path, u64>,
+    /// permits for flushing files shared by the partition writers, if flushes are bounded
+    flush_semaphore: Option<Arc<Semaphore>>,
+    /// id of the staging directory files are written to, if staging is enabled
+    staging_id: Option<uuid::Uuid>,
+    /// paths of the files written by partition writers closed with `flush_partition`
+    closed_paths: Vec<Path>,
 }
 
 impl DeltaWriter {
     /// Create a new instance of [`DeltaWriter`]
     pub fn new(object_store: ObjectStoreRef, config: WriterConfig) -> Self {
+        let buffer_pool = config.buffer_pooling.then(BufferPool::new);
+        let flush_semaphore = config
+            .max_concurrent_flushes
+            .map(|permits| Arc::new(Semaphore::new(permits)));
+        let staging_id = config.staging.then(uuid::Uuid::new_v4);
         Self {
             object_store,
             config,
             partition_writers: HashMap::new(),
+            on_file_flushed: None,
+            partition_target_size: None,
+            buffer_pool,
+            writer_generations: HashMap::new(),
+            flush_semaphore,
+            staging_id,
+            closed_paths: Vec::new(),
         }
     }
 
+    /// Register a callback invoked for every file as soon as it is written to storage
+    pub fn with_file_flushed_callback(mut self, callback: FileFlushedFn) -> Self {
+        self.on_file_flushed = Some(callback);
+        self
+    }
+
     /// Apply custom writer_properties to the underlying parquet writer
     pub fn with_writer_properties(mut self, writer_properties: WriterProperties) -> Self {
         self.config.writer_properties = writer_properties;
         self
     }
 
-    fn divide_by_partition_values(
-        &mut self,
-        values: &RecordBatch,
-    ) -> DeltaResult<Vec<PartitionResult>> {
-        Ok(divide_by_partition_values(
-            self.config.file_schema(),
-            self.config.partition_columns.clone(),
-            values,
-        )
-        .map_err(|err| WriteError::Partitioning(err.to_string()))?)
+    /// Override the target file size of individual partitions, e.g. to write larger files
+    /// for partitions receiving most of the data.
+    ///
+    /// The callback is consulted whenever a writer for a partition is opened. Partitions for
+    /// which it returns `None` use the target file size of the [WriterConfig].
+    pub fn with_partition_target_size(mut self, target_size: PartitionTargetSizeFn) -> Self {
+        self.partition_target_size = Some(target_size);
+        self
+    }
+
+    fn partition_writer_config(
+        &self,
+        partition_values: &IndexMap<String, Scalar>,
+    ) -> DeltaResult<PartitionWriterConfig> {
+        let target_file_size = self
+            .partition_target_size
+            .as_ref()
+            .and_then(|target_size| target_size(partition_values))
+            .unwrap_or(self.config.target_file_size);
+        let partition_path = self.config.partition_path_encoder.encode(partition_values);
+        let mut config = PartitionWriterConfig::try_new(
+            self.config.file_schema(),
+            partition_values.clone(),
+            Some(&partition_path),
+            Some(self.config.file_writer_properties()?),
+            Some(target_file_size),
+            Some(self.config.write_batch_size),
+        )?
+        .with_field_ids(self.config.field_ids)
+        .with_oversized_chunk_behavior(self.config.oversized_chunk_behavior)
+        .with_write_batch_size_behavior(self.config.write_batch_size_behavior)
+        .with_schema_adaptation(self.config.schema_adaptation)
+        .with_adaptive_chunk_size(self.config.adaptive_chunk_size)
+        .with_upload_concurrency(self.config.upload_concurrency)
+        .with_multipart_threshold(self.config.multipart_threshold)
+        .with_sort_columns(self.config.sort_columns.clone())
+        .with_row_group_stats_in_tags(self.config.row_group_stats_in_tags)
+        .with_uncompressed_size_in_tags(self.config.uncompressed_size_in_tags)
+        .with_upload_retries(
+            self.config.upload_retries,
+            self.config.upload_retry_backoff.clone(),
+        );
+        if let Some(max_file_size) = self.config.max_file_size {
+            config = config.with_max_file_size(max_file_size);
+        }
+        if let Some(max_rows_per_file) = self.config.max_rows_per_file {
+            config = config.with_max_rows_per_file(max_rows_per_file);
+        }
+        if let Some(DataPathGenerator(data_path_fn)) = &self.config.data_path_fn {
+            config = config.with_data_path_fn(data_path_fn.clone());
+        }
+        if let Some(buffer_pool) = &self.buffer_pool {
+            config = config.with_buffer_pool(buffer_pool.clone());
+        }
+        if let Some(flush_semaphore) = &self.flush_semaphore {
+            config = config.with_flush_semaphore(flush_semaphore.clone());
+        }
+        if let Some(staging_id) = self.staging_id {
+            let staging_prefix = Path::from_iter([STAGING_PREFIX, &staging_id.to_string()]);
+            config = config.with_staging_prefix(staging_prefix);
+        }
+        Ok(config)
+    }
+
+    /// Open a writer for a partition, `generation` counts the writers opened for the
+    /// partition before to derive unique seeded writer ids.
+    fn create_partition_writer(
+        &self,
+        partition_key: &Path,
+        partition_values: &IndexMap<String, Scalar>,
+        generation: u64,
+    ) -> DeltaResult<PartitionWriter> {
+        let mut config = self.partition_writer_config(partition_values)?;
+        if let Some(seed) = self.config.naming_seed {
+            config = config.with_writer_id(seeded_writer_id(seed, partition_key, generation));
+        }
+        let mut writer = PartitionWriter::try_with_config(
+            self.object_store.clone(),
+            config,
+            self.config.num_indexed_cols,
+            self.config.stats_columns.clone(),
+        )?;
+        if let Some(callback) = &self.on_file_flushed {
+            writer = writer.with_file_flushed_callback(callback.clone());
+        }
+        Ok(writer)
+    }
+
+    fn divide_by_partition_values(
+        &mut self,
+        values: &RecordBatch,
+    ) -> DeltaResult<Vec<PartitionResult>> {
+        Ok(divide_by_partition_values(
+            self.config.file_schema(),
+            self.config.partition_columns.clone(),
+            values,
+        )
+        .map_err(|err| WriteError::Partitioning(err.to_string()))?)
     }
 
     /// Write a batch to the partition induced by the partition_values. The record batch is expected
@@ -204,25 +1329,46 @@ impl DeltaWriter {
                 writer.write(&record_batch).await?;
             }
             None => {
-                let config = PartitionWriterConfig::try_new(
-                    self.config.file_schema(),
-                    partition_values.clone(),
-                    None,
-                    Some(self.config.writer_properties.clone()),
-                    Some(self.config.target_file_size),
-                    Some(self.config.write_batch_size),
-                )?;
-                let mut writer = PartitionWriter::try_with_config(
-                    self.object_store.clone(),
-                    config,
-                    self.config.num_indexed_cols,
-                    self.config.stats_columns.clone(),
-                )?;
+                let generation = self
+                    .writer_generations
+                    .get(&partition_key)
+                    .copied()
+                    .unwrap_or_default();
+                let mut writer =
+                    self.create_partition_writer(&partition_key, partition_values, generation)?;
+                self.writer_generations
+                    .insert(partition_key.clone(), generation + 1);
                 writer.write(&record_batch).await?;
                 let _ = self.partition_writers.insert(partition_key, writer);
             }
         }
 
+        self.enforce_buffer_budget().await
+    }
+
+    /// Flush the partitions buffering the most data until the buffered data fits into
+    /// `max_total_buffered_bytes` again
+    async fn enforce_buffer_budget(&mut self) -> DeltaResult<()> {
+        let Some(budget) = self.config.max_total_buffered_bytes else {
+            return Ok(());
+        };
+        let mut buffered = Vec::with_capacity(self.partition_writers.len());
+        for (partition_key, writer) in &self.partition_writers {
+            buffered.push((writer.buffered_bytes().await, partition_key.clone()));
+        }
+        let mut total: usize = buffered.iter().map(|(size, _)| size).sum();
+        buffered.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+        for (size, partition_key) in buffered {
+            if total <= budget || size == 0 {
+                break;
+            }
+            debug!("Buffered data of {total} bytes exceeds the budget, flushing partition {partition_key} with {size} bytes.");
+            if let Some(writer) = self.partition_writers.get_mut(&partition_key) {
+                writer.flush_arrow_writer().await?;
+            }
+            total -= size;
+        }
         Ok(())
     }
 
@@ -232,6 +1378,14 @@ impl DeltaWriter {
     /// The `close` method has to be invoked to write all data still buffered
     /// and get the list of all written files.
     pub async fn write(&mut self, batch: &RecordBatch) -> DeltaResult<()> {
+        // adapt the batch before partitioning, which relies on the table schema as well
+        let adapted;
+        let batch = if self.config.schema_adaptation && batch.schema() != self.config.table_schema {
+            adapted = adapt_batch(batch, &self.config.table_schema)?;
+            &adapted
+        } else {
+            batch
+        };
         for result in self.divide_by_partition_values(batch)? {
             self.write_partition(result.record_batch, &result.partition_values)
                 .await?;
@@ -239,16 +1393,54 @@ impl DeltaWriter {
         Ok(())
     }
 
-    /// Close the writer and get the new [Add] actions.
+    /// Write a batch of which all rows belong to the partition with the given values, without
+    /// dividing it by partition values first.
     ///
-    /// This will flush all remaining data.
-    pub async fn close(mut self) -> DeltaResult<Vec<Add>> {
-        let writers = std::mem::take(&mut self.partition_writers);
+    /// The partition columns are removed from the batch if present, the remaining columns
+    /// have to match the schema of the written files. The values must be given for exactly
+    /// the partition columns of the writer, the rows are not checked against them.
+    pub async fn write_pre_partitioned(
+        &mut self,
+        batch: RecordBatch,
+        partition_values: &IndexMap<String, Scalar>,
+    ) -> DeltaResult<()> {
+        self.check_partition_values(partition_values)?;
+        let batch = pre_partitioned_batch(
+            &batch,
+            &self.config.partition_columns,
+            self.config.file_schema(),
+            self.config.schema_adaptation,
+        )?;
+        self.write_partition(batch, partition_values).await
+    }
+
+    fn check_partition_values(
+        &self,
+        partition_values: &IndexMap<String, Scalar>,
+    ) -> DeltaResult<()> {
+        if !partition_values
+            .keys()
+            .eq(self.config.partition_columns.iter())
+        {
+            return Err(WriteError::Partitioning(format!(
+                "partition values for {:?} do not match the partition columns {:?}",
+                partition_values.keys().collect::<Vec<_>>(),
+                self.config.partition_columns
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Flush the data buffered by all partition writers to storage and get the new [Add] actions.
+    ///
+    /// Unlike `close`, the partition writers stay open and can be written to afterwards,
+    /// which allows releasing buffered memory on demand.
+    pub async fn flush_all(&mut self) -> DeltaResult<Vec<Add>> {
+        let mut writers = self.partition_writers.iter_mut().collect::<Vec<_>>();
+        self.config.partition_flush_order.sort(&mut writers);
         let actions = futures::stream::iter(writers)
-            .map(|(_, writer)| async move {
-                let writer_actions = writer.close().await?;
-                Ok::<_, DeltaTableError>(writer_actions)
-            })
+            .map(|(_, writer)| writer.flush())
             .buffered(num_cpus::get())
             .try_fold(Vec::new(), |mut acc, actions| {
                 acc.extend(actions);
@@ -258,6 +1450,455 @@ impl DeltaWriter {
 
         Ok(actions)
     }
+
+    /// Close the writer of a single partition and get its new [Add] actions.
+    ///
+    /// Use this once no more data is expected for a partition, to release its buffered
+    /// memory and emit its files early. Later writes to the same partition open a fresh
+    /// writer, which restarts the part counter with a new writer id, so file names stay
+    /// unique but part numbers of a partition are no longer contiguous across writers.
+    ///
+    /// Returns no actions if there is no open writer for the partition.
+    pub async fn flush_partition(
+        &mut self,
+        partition_values: &IndexMap<String, Scalar>,
+    ) -> DeltaResult<Vec<Add>> {
+        let partition_key = Path::parse(partition_values.hive_partition_path())?;
+        match self.partition_writers.remove(&partition_key) {
+            Some(writer) => {
+                let (actions, paths) = writer.close_with_paths().await?;
+                self.closed_paths
+                    .extend(paths.into_iter().map(|(path, _)| path));
+                Ok(actions)
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Close the writer and get the new [Add] actions.
+    ///
+    /// This will flush all remaining data.
+    pub async fn close(self) -> DeltaResult<Vec<Add>> {
+        Ok(self.close_with_metrics().await?.0)
+    }
+
+    /// Discard all buffered data and delete all files written so far, including the files
+    /// of partitions closed with `flush_partition`, see [PartitionWriter::abort].
+    pub async fn abort(self) -> DeltaResult<()> {
+        let object_store = self.object_store;
+        let paths = self
+            .partition_writers
+            .into_values()
+            .flat_map(|writer| writer.paths_written)
+            .chain(self.closed_paths);
+        futures::stream::iter(paths)
+            .map(|path| delete_written_file(object_store.as_ref(), path))
+            .buffer_unordered(num_cpus::get())
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+
+    /// The [Add] actions of the files flushed to storage so far by all open partition
+    /// writers, see [PartitionWriter::files_written].
+    ///
+    /// Files of partitions flushed with [flush_partition](Self::flush_partition) were already
+    /// handed to the caller and are not included.
+    pub fn files_written(&self) -> impl Iterator<Item = &Add> + '_ {
+        self.partition_writers
+            .values()
+            .flat_map(|writer| writer.files_written())
+    }
+
+    /// Close the writer and get the new [Add] actions along with metrics about the written files,
+    /// which can be used to decide whether the table should be compacted.
+    ///
+    /// This will flush all remaining data.
+    pub async fn close_with_metrics(self) -> DeltaResult<(Vec<Add>, WriterMetrics)> {
+        let min_file_size = self.config.min_file_size;
+        let (actions, _) = self.close_with_paths().await?;
+
+        let num_files_below_min_size = match min_file_size {
+            Some(min_file_size) => actions
+                .iter()
+                .filter(|add| (add.size as usize) < min_file_size)
+                .count(),
+            None => 0,
+        };
+        if num_files_below_min_size > 0 {
+            warn!("Wrote {num_files_below_min_size} files below the minimum file size, consider compacting the table");
+        }
+        let metrics = WriterMetrics {
+            num_files: actions.len(),
+            num_files_below_min_size,
+        };
+
+        Ok((actions, metrics))
+    }
+
+    /// Close the writer and get the new [Add] actions along with the object store paths of
+    /// their files and the paths they are published to.
+    async fn close_with_paths(mut self) -> DeltaResult<(Vec<Add>, Vec<(Path, Path)>)> {
+        let mut writers = std::mem::take(&mut self.partition_writers)
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.config.partition_flush_order.sort(&mut writers);
+        futures::stream::iter(writers)
+            .map(|(_, writer)| writer.close_with_paths())
+            .buffered(num_cpus::get())
+            .try_fold(
+                (Vec::new(), Vec::new()),
+                |(mut actions, mut paths), (writer_actions, writer_paths)| {
+                    actions.extend(writer_actions);
+                    paths.extend(writer_paths);
+                    futures::future::ready(Ok((actions, paths)))
+                },
+            )
+            .await
+    }
+
+    /// Close the writer and move the files written to the staging directory to their final
+    /// paths, see [WriterConfig::with_staging].
+    ///
+    /// Files are renamed if the store supports it, and copied and deleted otherwise. The
+    /// returned [Add] actions reference the final paths. Without staging, this is the same
+    /// as [close](Self::close).
+    ///
+    /// If any file can not be moved, the files already moved and the files still staged are
+    /// deleted before the error is returned, so a failed publish leaves no files behind.
+    pub async fn publish(self) -> DeltaResult<Vec<Add>> {
+        let object_store = self.object_store.clone();
+        let staging = self.staging_id.is_some();
+        let (adds, staged) = self.close_with_paths().await?;
+        if !staging {
+            return Ok(adds);
+        }
+        publish_staged_files(object_store.as_ref(), adds, staged).await
+    }
+}
+
+/// Move staged files to their final paths and point their [Add] actions to them, see
+/// [DeltaWriter::publish].
+///
+/// `staged` holds the staged paths of the files of the `adds` in the same order, each along
+/// with the path the file is published to. If any file can not be moved, all files are
+/// deleted before the error is returned.
+async fn publish_staged_files(
+    object_store: &dyn ObjectStore,
+    mut adds: Vec<Add>,
+    staged: Vec<(Path, Path)>,
+) -> DeltaResult<Vec<Add>> {
+    let results = futures::stream::iter(&staged)
+        .map(|(from, to)| move_file(object_store, from, to))
+        .buffered(num_cpus::get())
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut error = None;
+    let mut written = Vec::with_capacity(results.len());
+    for ((from, to), result) in staged.iter().zip(results) {
+        match result {
+            Ok(()) => written.push(to.clone()),
+            Err(err) => {
+                written.push(from.clone());
+                error.get_or_insert(err);
+            }
+        }
+    }
+    if let Some(err) = error {
+        let cleanup = futures::stream::iter(written)
+            .map(|path| delete_written_file(object_store, path))
+            .buffer_unordered(num_cpus::get())
+            .collect::<Vec<_>>()
+            .await;
+        for result in cleanup {
+            if let Err(cleanup_err) = result {
+                warn!("Failed to delete a file of a failed publish: {cleanup_err}");
+            }
+        }
+        return Err(err);
+    }
+
+    for (add, (_, path)) in adds.iter_mut().zip(staged) {
+        add.path = path.to_string();
+    }
+    Ok(adds)
+}
+
+/// Delete a file written by an aborted writer, warning if it no longer exists.
+async fn delete_written_file(object_store: &dyn ObjectStore, path: Path) -> DeltaResult<()> {
+    match object_store.delete(&path).await {
+        Ok(()) => Ok(()),
+        Err(ObjectStoreError::NotFound { .. }) => {
+            warn!("File {path} written by an aborted writer was already deleted");
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Remove the partition columns from a batch of a single partition and check it against the
+/// schema of the written files.
+fn pre_partitioned_batch(
+    batch: &RecordBatch,
+    partition_columns: &[String],
+    file_schema: ArrowSchemaRef,
+    schema_adaptation: bool,
+) -> DeltaResult<RecordBatch> {
+    let batch = record_batch_without_partitions(batch, partition_columns)?;
+    if !schema_adaptation && batch.schema() != file_schema {
+        return Err(WriteError::SchemaMismatch {
+            schema: batch.schema(),
+            expected_schema: file_schema,
+        }
+        .into());
+    }
+    Ok(batch)
+}
+
+type SharedPartitionWriter = Arc<tokio::sync::Mutex<Option<PartitionWriter>>>;
+
+/// A writer for delta tables whose partitions can be written concurrently from separate tasks.
+///
+/// Unlike [DeltaWriter], which requires exclusive access for every write, this writer hands
+/// out a [PartitionWriterHandle] per partition. Handles own the writer of their partition and
+/// can be moved into independent tasks, [close](Self::close) then closes all of them and
+/// merges their [Add] actions. With staging enabled, use [publish](Self::publish) instead.
+///
+/// Partitions are written by the same [PartitionWriter]s as with a [DeltaWriter], but the
+/// `max_total_buffered_bytes` budget of the [WriterConfig] is not enforced across them.
+pub struct ConcurrentDeltaWriter {
+    /// writer used to open partition writers with the shared configuration
+    writer: DeltaWriter,
+    /// partition writers handed out to tasks
+    partition_writers: parking_lot::Mutex<HashMap<Path, SharedPartitionWriter>>,
+}
+
+impl ConcurrentDeltaWriter {
+    /// Create a new instance of [`ConcurrentDeltaWriter`]
+    pub fn new(object_store: ObjectStoreRef, config: WriterConfig) -> Self {
+        Self {
+            writer: DeltaWriter::new(object_store, config),
+            partition_writers: Default::default(),
+        }
+    }
+
+    /// Register a callback invoked for every file as soon as it is written to storage
+    pub fn with_file_flushed_callback(mut self, callback: FileFlushedFn) -> Self {
+        self.writer = self.writer.with_file_flushed_callback(callback);
+        self
+    }
+
+    /// Apply custom writer_properties to the underlying parquet writer
+    pub fn with_writer_properties(mut self, writer_properties: WriterProperties) -> Self {
+        self.writer = self.writer.with_writer_properties(writer_properties);
+        self
+    }
+
+    /// Override the target file size of individual partitions, see
+    /// [DeltaWriter::with_partition_target_size].
+    pub fn with_partition_target_size(mut self, target_size: PartitionTargetSizeFn) -> Self {
+        self.writer = self.writer.with_partition_target_size(target_size);
+        self
+    }
+
+    /// Get a handle to write the partition with the given values.
+    ///
+    /// The writer of the partition is opened on first use. Handles of the same partition
+    /// share its writer, so writes through them are serialized.
+    pub fn partition_writer(
+        &self,
+        partition_values: &IndexMap<String, Scalar>,
+    ) -> DeltaResult<PartitionWriterHandle> {
+        self.writer.check_partition_values(partition_values)?;
+        let partition_key = Path::parse(partition_values.hive_partition_path())?;
+
+        let mut partition_writers = self.partition_writers.lock();
+        let writer = match partition_writers.get(&partition_key) {
+            Some(writer) => writer.clone(),
+            None => {
+                let writer =
+                    self.writer
+                        .create_partition_writer(&partition_key, partition_values, 0)?;
+                let writer = Arc::new(tokio::sync::Mutex::new(Some(writer)));
+                partition_writers.insert(partition_key.clone(), writer.clone());
+                writer
+            }
+        };
+
+        Ok(PartitionWriterHandle {
+            partition_key,
+            partition_columns: self.writer.config.partition_columns.clone(),
+            file_schema: self.writer.config.file_schema(),
+            schema_adaptation: self.writer.config.schema_adaptation,
+            writer,
+        })
+    }
+
+    /// Close the writers of all partitions and get the new [Add] actions.
+    ///
+    /// Waits for writes in progress to finish, later writes through the handles fail.
+    pub async fn close(self) -> DeltaResult<Vec<Add>> {
+        Ok(self.close_with_paths().await?.0)
+    }
+
+    /// Close the writers of all partitions and move the files written to the staging
+    /// directory to their final paths, see [DeltaWriter::publish].
+    pub async fn publish(self) -> DeltaResult<Vec<Add>> {
+        let object_store = self.writer.object_store.clone();
+        let staging = self.writer.staging_id.is_some();
+        let (adds, staged) = self.close_with_paths().await?;
+        if !staging {
+            return Ok(adds);
+        }
+        publish_staged_files(object_store.as_ref(), adds, staged).await
+    }
+
+    /// Close the writers of all partitions and get the new [Add] actions along with the
+    /// object store paths of their files and the paths they are published to.
+    async fn close_with_paths(self) -> DeltaResult<(Vec<Add>, Vec<(Path, Path)>)> {
+        let writers = self.partition_writers.into_inner();
+        futures::stream::iter(writers.into_values())
+            .map(|writer| async move {
+                match writer.lock().await.take() {
+                    Some(writer) => writer.close_with_paths().await,
+                    None => Ok((vec![], vec![])),
+                }
+            })
+            .buffered(num_cpus::get())
+            .try_fold(
+                (Vec::new(), Vec::new()),
+                |(mut actions, mut paths), (writer_actions, writer_paths)| {
+                    actions.extend(writer_actions);
+                    paths.extend(writer_paths);
+                    futures::future::ready(Ok((actions, paths)))
+                },
+            )
+            .await
+    }
+}
+
+/// Handle to write a single partition of a [ConcurrentDeltaWriter].
+#[derive(Clone)]
+pub struct PartitionWriterHandle {
+    partition_key: Path,
+    partition_columns: Vec<String>,
+    file_schema: ArrowSchemaRef,
+    schema_adaptation: bool,
+    writer: SharedPartitionWriter,
+}
+
+impl PartitionWriterHandle {
+    /// Write a batch of which all rows belong to the partition of the handle.
+    ///
+    /// The partition columns are removed from the batch if present, the remaining columns
+    /// have to match the schema of the written files, see [DeltaWriter::write_pre_partitioned].
+    pub async fn write(&self, batch: &RecordBatch) -> DeltaResult<()> {
+        let batch = pre_partitioned_batch(
+            batch,
+            &self.partition_columns,
+            self.file_schema.clone(),
+            self.schema_adaptation,
+        )?;
+        match self.writer.lock().await.as_mut() {
+            Some(writer) => writer.write(&batch).await,
+            None => Err(WriteError::PartitionWriterClosed(self.partition_key.clone()).into()),
+        }
+    }
+}
+
+/// Move a file within the store, falling back to copying and deleting it for stores which
+/// can not rename files
+async fn move_file(object_store: &dyn ObjectStore, from: &Path, to: &Path) -> DeltaResult<()> {
+    match object_store.rename_if_not_exists(from, to).await {
+        Ok(()) => Ok(()),
+        Err(ObjectStoreError::NotSupported { .. } | ObjectStoreError::NotImplemented) => {
+            object_store.copy(from, to).await?;
+            object_store.delete(from).await?;
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// In-memory size of the rows of a batch.
+///
+/// Unlike [RecordBatch::get_array_memory_size], only the part of the buffers referenced by a
+/// sliced batch is counted. Falls back to the size of the whole buffers for data types whose
+/// slice size can not be determined.
+fn slice_memory_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|column| {
+            column
+                .to_data()
+                .get_slice_memory_size()
+                .unwrap_or_else(|_| column.get_array_memory_size())
+        })
+        .sum()
+}
+
+/// Estimate the number of rows fitting into the `remaining` bytes of a file, given that the
+/// last chunk of `rows` rows grew the file by `growth` bytes.
+///
+/// Keeps the current chunk size if the growth could not be observed, e.g. since the data is
+/// still buffered by the parquet writer.
+fn adaptive_chunk_size(
+    chunk_size: usize,
+    max_chunk_size: usize,
+    rows: usize,
+    growth: usize,
+    remaining: usize,
+) -> usize {
+    if rows == 0 || growth == 0 {
+        return chunk_size;
+    }
+    let row_size = growth.div_ceil(rows);
+    (remaining / row_size).clamp(1, max_chunk_size)
+}
+
+/// Pool of reusable buffers for the files written by partition writers.
+///
+/// Writers take a buffer from the pool whenever they start a new file, and return it once
+/// the file is uploaded.
+#[derive(Clone, Default)]
+pub struct BufferPool {
+    buffers: Arc<parking_lot::Mutex<Vec<Vec<u8>>>>,
+}
+
+impl std::fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("num_available", &self.num_available())
+            .finish()
+    }
+}
+
+impl BufferPool {
+    /// Create an empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of buffers available for reuse
+    pub fn num_available(&self) -> usize {
+        self.buffers.lock().len()
+    }
+
+    fn take(&self) -> AsyncShareableBuffer {
+        match self.buffers.lock().pop() {
+            Some(buffer) => AsyncShareableBuffer::from_vec(buffer),
+            None => AsyncShareableBuffer::default(),
+        }
+    }
+
+    fn put(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+        if buffer.capacity() > 0 {
+            self.buffers.lock().push(buffer);
+        }
+    }
 }
 
 /// Write configuration for partition writers
@@ -276,6 +1917,45 @@ pub struct PartitionWriterConfig {
     /// Row chunks passed to parquet writer. This and the internal parquet writer settings
     /// determine how fine granular we can track / control the size of resulting files.
     write_batch_size: usize,
+    /// Embed parquet field ids in the written files
+    field_ids: bool,
+    /// Behavior when a chunk of `write_batch_size` rows exceeds the target file size
+    oversized_chunk_behavior: OversizedChunkBehavior,
+    /// Behavior when `write_batch_size` exceeds the maximum row group size
+    write_batch_size_behavior: WriteBatchSizeBehavior,
+    /// Size written files are kept below, based on the in-memory size of the written data.
+    /// Unlike `target_file_size`, this is checked before every chunk is written rather than after.
+    max_file_size: Option<usize>,
+    /// Custom strategy for the paths of written files
+    data_path_fn: Option<DataPathGenerator>,
+    /// Adapt batches to the file schema if they only differ in safe ways
+    schema_adaptation: bool,
+    /// Shrink chunks as the buffered data approaches the target file size
+    adaptive_chunk_size: bool,
+    /// Maximum number of parts uploaded concurrently per file
+    upload_concurrency: usize,
+    /// Pool the buffers of written files are taken from
+    buffer_pool: Option<BufferPool>,
+    /// Size below which files are written with a single put
+    multipart_threshold: usize,
+    /// Id of the writer used in file names, generated randomly if not set
+    writer_id: Option<uuid::Uuid>,
+    /// Columns the rows of each file are sorted by, and whether they are sorted ascending
+    sort_columns: Vec<(String, bool)>,
+    /// Record the statistics of every row group in the tags of the [Add] actions
+    row_group_stats_in_tags: bool,
+    /// Record the uncompressed size of the data in the tags of the [Add] actions
+    uncompressed_size_in_tags: bool,
+    /// Maximum number of rows written to a single file
+    max_rows_per_file: Option<usize>,
+    /// Permits for flushing files, shared with other partition writers
+    flush_semaphore: Option<Arc<Semaphore>>,
+    /// Number of times an upload failing with a transient error is retried
+    upload_retries: usize,
+    /// Delay between the retries of an upload
+    upload_retry_backoff: RetryBackoff,
+    /// Prefix files are written below until they are published to their paths
+    staging_prefix: Option<Path>,
 }
 
 impl PartitionWriterConfig {
@@ -290,10 +1970,7 @@ impl PartitionWriterConfig {
     ) -> DeltaResult<Self> {
         let prefix = match path_prefix {
             Some(prefix) => Path::parse(prefix),
-            None => {
-                let part_path = partition_values.hive_partition_path();
-                Path::parse(part_path)
-            }
+            None => Path::parse(HivePartitionPathEncoder.encode(&partition_values)),
         }?;
         let writer_properties = writer_properties.unwrap_or_else(|| {
             WriterProperties::builder()
@@ -310,59 +1987,365 @@ impl PartitionWriterConfig {
             writer_properties,
             target_file_size,
             write_batch_size,
+            field_ids: false,
+            oversized_chunk_behavior: OversizedChunkBehavior::default(),
+            write_batch_size_behavior: WriteBatchSizeBehavior::default(),
+            max_file_size: None,
+            data_path_fn: None,
+            schema_adaptation: false,
+            adaptive_chunk_size: false,
+            upload_concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+            buffer_pool: None,
+            multipart_threshold: 0,
+            writer_id: None,
+            sort_columns: Vec::new(),
+            row_group_stats_in_tags: false,
+            uncompressed_size_in_tags: false,
+            max_rows_per_file: None,
+            flush_semaphore: None,
+            upload_retries: 0,
+            upload_retry_backoff: RetryBackoff::default(),
+            staging_prefix: None,
         })
     }
-}
-
-/// Partition writer implementation
-/// This writer takes in table data as RecordBatches and writes it out to partitioned parquet files.
-/// It buffers data in memory until it reaches a certain size, then writes it out to optimize file sizes.
-/// When you complete writing you get back a list of Add actions that can be used to update the Delta table commit log.
-pub struct PartitionWriter {
-    object_store: ObjectStoreRef,
-    writer_id: uuid::Uuid,
-    config: PartitionWriterConfig,
-    buffer: AsyncShareableBuffer,
-    arrow_writer: AsyncArrowWriter<AsyncShareableBuffer>,
-    part_counter: usize,
-    files_written: Vec<Add>,
-    /// Num index cols to collect stats for
-    num_indexed_cols: i32,
-    /// Stats columns, specific columns to collect stats from, takes precedence over num_indexed_cols
-    stats_columns: Option<Vec<String>>,
-}
 
-impl PartitionWriter {
-    /// Create a new instance of [`PartitionWriter`] from [`PartitionWriterConfig`]
-    pub fn try_with_config(
-        object_store: ObjectStoreRef,
-        config: PartitionWriterConfig,
-        num_indexed_cols: i32,
-        stats_columns: Option<Vec<String>>,
+    /// Create a new instance of [PartitionWriterConfig], checking the partition values
+    /// against the schema of the table, which includes the partition columns.
+    ///
+    /// Fails with [DeltaTableError::SchemaMismatch] if a partition value is given for a
+    /// column missing from the table schema, or if its type does not match the column type,
+    /// before any files are written to a wrong partition.
+    pub fn try_new_checked(
+        table_schema: &ArrowSchema,
+        file_schema: ArrowSchemaRef,
+        partition_values: IndexMap<String, Scalar>,
+        path_prefix: Option<&str>,
+        writer_properties: Option<WriterProperties>,
+        target_file_size: Option<usize>,
+        write_batch_size: Option<usize>,
     ) -> DeltaResult<Self> {
-        let buffer = AsyncShareableBuffer::default();
-        let arrow_writer = AsyncArrowWriter::try_new(
-            buffer.clone(),
-            config.file_schema.clone(),
-            Some(config.writer_properties.clone()),
-        )?;
+        for (column, value) in &partition_values {
+            let field = table_schema.field_with_name(column).map_err(|_| {
+                DeltaTableError::SchemaMismatch {
+                    msg: format!("partition column {column} is missing from the table schema"),
+                }
+            })?;
+            let value_type = DataType::try_from(&value.data_type())?;
+            if !value_type.equals_datatype(field.data_type())
+                && !is_safe_adaptation(&value_type, field.data_type())
+            {
+                return Err(DeltaTableError::SchemaMismatch {
+                    msg: format!(
+                        "partition value {value} of type {value_type} does not match the type {} of partition column {column}",
+                        field.data_type()
+                    ),
+                });
+            }
+        }
+        Self::try_new(
+            file_schema,
+            partition_values,
+            path_prefix,
+            writer_properties,
+            target_file_size,
+            write_batch_size,
+        )
+    }
 
-        Ok(Self {
-            object_store,
-            writer_id: uuid::Uuid::new_v4(),
-            config,
-            buffer,
-            arrow_writer,
-            part_counter: 0,
-            files_written: Vec::new(),
+    /// Generate the paths of written files with a custom strategy instead of the default
+    /// `<prefix>/part-<part>-<writer id>-c000.<compression>.parquet` naming.
+    ///
+    /// The function receives the partition prefix, the part counter and the writer id. The
+    /// returned path must be relative to the table root, since it is recorded as the path of
+    /// the [Add] action, and unique across writers to not overwrite other files.
+    pub fn with_data_path_fn(mut self, data_path_fn: DataPathFn) -> Self {
+        self.data_path_fn = Some(DataPathGenerator(data_path_fn));
+        self
+    }
+
+    /// Specify a size written files are kept below.
+    ///
+    /// Before a chunk is written, the file is flushed if the data written so far plus the
+    /// in-memory size of the chunk, as an upper bound of its encoded size, would exceed this
+    /// size. Only a single row larger than the cap is written to a file exceeding it.
+    pub fn with_max_file_size(mut self, max_file_size: usize) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Specify how to handle chunks of `write_batch_size` rows exceeding the target file size
+    pub fn with_oversized_chunk_behavior(mut self, behavior: OversizedChunkBehavior) -> Self {
+        self.oversized_chunk_behavior = behavior;
+        self
+    }
+
+    /// Specify how to handle a `write_batch_size` exceeding the maximum row group size of the
+    /// writer properties
+    pub fn with_write_batch_size_behavior(mut self, behavior: WriteBatchSizeBehavior) -> Self {
+        self.write_batch_size_behavior = behavior;
+        self
+    }
+
+    /// Embed parquet field ids in the written files for readers resolving columns by id
+    pub fn with_field_ids(mut self, field_ids: bool) -> Self {
+        self.field_ids = field_ids;
+        self
+    }
+
+    /// Adapt written batches to the file schema instead of failing with a schema mismatch.
+    ///
+    /// Only safe differences of top-level columns are adapted: non-nullable columns are
+    /// written to nullable columns, and dictionary encoded columns are written to columns of
+    /// their value type and vice versa. Batches differing in any other way, e.g. in column
+    /// names or types, are still rejected.
+    pub fn with_schema_adaptation(mut self, schema_adaptation: bool) -> Self {
+        self.schema_adaptation = schema_adaptation;
+        self
+    }
+
+    /// Adjust the number of rows written between file size checks to the observed growth of
+    /// the buffered data.
+    ///
+    /// By default the file size is checked after every chunk of `write_batch_size` rows,
+    /// which overshoots the target file size for large rows. In adaptive mode, the size added
+    /// by each chunk is used to estimate how many more rows fit into the current file, and the
+    /// next chunk is shrunk accordingly, so the size is checked more often close to the
+    /// target. Chunks never grow beyond `write_batch_size` rows.
+    pub fn with_adaptive_chunk_size(mut self, adaptive_chunk_size: bool) -> Self {
+        self.adaptive_chunk_size = adaptive_chunk_size;
+        self
+    }
+
+    /// Specify the maximum number of parts uploaded concurrently when writing a file.
+    ///
+    /// Defaults to 10. Higher values can speed up uploads to high-latency object stores at
+    /// the cost of more memory and connections. Must be at least 1.
+    pub fn with_upload_concurrency(mut self, upload_concurrency: usize) -> Self {
+        self.upload_concurrency = upload_concurrency;
+        self
+    }
+
+    /// Take the buffers of written files from a pool, and return them once uploaded
+    pub fn with_buffer_pool(mut self, buffer_pool: BufferPool) -> Self {
+        self.buffer_pool = Some(buffer_pool);
+        self
+    }
+
+    /// Write files smaller than `multipart_threshold` bytes with a single put instead of a
+    /// multipart upload, which saves the round-trips to create and complete the upload.
+    ///
+    /// Defaults to 0, i.e. all files are written with multipart uploads.
+    pub fn with_multipart_threshold(mut self, multipart_threshold: usize) -> Self {
+        self.multipart_threshold = multipart_threshold;
+        self
+    }
+
+    /// Use a fixed writer id in the names of written files instead of a random one.
+    ///
+    /// Files are named after the writer id and the part counter, so the id must not be shared
+    /// with other writers of the same partition.
+    pub fn with_writer_id(mut self, writer_id: uuid::Uuid) -> Self {
+        self.writer_id = Some(writer_id);
+        self
+    }
+
+    /// Sort the rows of every written file by the given top-level columns, each sorted
+    /// ascending if its flag is set and descending otherwise. Nulls are sorted last.
+    ///
+    /// Sorting requires all rows of a file to be buffered unencoded until the file is
+    /// written. The encoded size of the buffered rows is estimated from the previously written
+    /// files, so the first file may be smaller than the target file size, and the buffer can
+    /// grow well beyond the target file size for data which compresses well. The buffered rows
+    /// are split into files like unsorted data, each file being sorted on its own.
+    pub fn with_sort_columns(mut self, sort_columns: Vec<(String, bool)>) -> Self {
+        self.sort_columns = sort_columns;
+        self
+    }
+
+    /// Record the statistics of every row group in the tags of the [Add] actions, see
+    /// [WriterConfig::with_row_group_stats_in_tags]
+    pub fn with_row_group_stats_in_tags(mut self, row_group_stats_in_tags: bool) -> Self {
+        self.row_group_stats_in_tags = row_group_stats_in_tags;
+        self
+    }
+
+    /// Record the uncompressed size of the data in the tags of the [Add] actions, see
+    /// [WriterConfig::with_uncompressed_size_in_tags]
+    pub fn with_uncompressed_size_in_tags(mut self, uncompressed_size_in_tags: bool) -> Self {
+        self.uncompressed_size_in_tags = uncompressed_size_in_tags;
+        self
+    }
+
+    /// Specify the maximum number of rows written to a single file.
+    ///
+    /// Files are written once either the target file size or the row limit is reached, which
+    /// bounds the number of rows of files of highly compressible data. Must be at least 1.
+    pub fn with_max_rows_per_file(mut self, max_rows_per_file: usize) -> Self {
+        self.max_rows_per_file = Some(max_rows_per_file);
+        self
+    }
+
+    /// Acquire a permit of the semaphore before flushing a file, to bound the number of
+    /// files flushed concurrently by all writers sharing the semaphore
+    pub fn with_flush_semaphore(mut self, flush_semaphore: Arc<Semaphore>) -> Self {
+        self.flush_semaphore = Some(flush_semaphore);
+        self
+    }
+
+    /// Write files below a staging prefix, followed by the path they are published to
+    pub(crate) fn with_staging_prefix(mut self, staging_prefix: Path) -> Self {
+        self.staging_prefix = Some(staging_prefix);
+        self
+    }
+
+    /// Retry uploads failing with a transient object store error by uploading the whole file
+    /// again, see [WriterConfig::with_upload_retries]
+    pub fn with_upload_retries(mut self, max_retries: usize, backoff: RetryBackoff) -> Self {
+        self.upload_retries = max_retries;
+        self.upload_retry_backoff = backoff;
+        self
+    }
+
+    fn buffer(&self) -> AsyncShareableBuffer {
+        match &self.buffer_pool {
+            Some(buffer_pool) => buffer_pool.take(),
+            None => AsyncShareableBuffer::default(),
+        }
+    }
+
+    fn arrow_writer(
+        &self,
+        buffer: AsyncShareableBuffer,
+    ) -> DeltaResult<AsyncArrowWriter<AsyncShareableBuffer>> {
+        let schema = if self.field_ids {
+            arrow_schema_with_field_ids(&self.file_schema)
+        } else {
+            self.file_schema.clone()
+        };
+        Ok(AsyncArrowWriter::try_new(
+            buffer,
+            schema,
+            Some(self.writer_properties.clone()),
+        )?)
+    }
+}
+
+/// Partition writer implementation
+/// This writer takes in table data as RecordBatches and writes it out to partitioned parquet files.
+/// It buffers data in memory until it reaches a certain size, then writes it out to optimize file sizes.
+/// When you complete writing you get back a list of Add actions that can be used to update the Delta table commit log.
+pub struct PartitionWriter {
+    object_store: ObjectStoreRef,
+    writer_id: uuid::Uuid,
+    config: PartitionWriterConfig,
+    buffer: AsyncShareableBuffer,
+    arrow_writer: AsyncArrowWriter<AsyncShareableBuffer>,
+    part_counter: usize,
+    files_written: Vec<Add>,
+    /// Parquet metadata of the written files, in the order of `files_written`
+    files_metadata: Vec<FileMetaData>,
+    /// Object store paths of the written files, in the order of `files_written`
+    paths_written: Vec<Path>,
+    /// Paths the written files are published to, in the order of `files_written`
+    published_paths: Vec<Path>,
+    /// Num index cols to collect stats for
+    num_indexed_cols: i32,
+    /// Stats columns, specific columns to collect stats from, takes precedence over num_indexed_cols
+    stats_columns: Option<Vec<String>>,
+    /// Callback invoked for every file written
+    on_file_flushed: Option<FileFlushedFn>,
+    /// Whether the oversized chunk warning was already logged
+    warned_oversized_chunk: bool,
+    /// Batches buffered until they are sorted
+    sort_buffer: Vec<RecordBatch>,
+    /// In-memory size of the batches in the sort buffer
+    sort_buffered_bytes: usize,
+    /// Number of rows in the sort buffer
+    sort_buffered_rows: usize,
+    /// Ratio of the encoded to the in-memory size of the previously sorted data, used to
+    /// estimate the encoded size of the sort buffer
+    sorted_size_ratio: f64,
+    /// Number of rows written to the current file
+    buffered_rows: usize,
+}
+
+impl PartitionWriter {
+    /// Create a new instance of [`PartitionWriter`] from [`PartitionWriterConfig`]
+    pub fn try_with_config(
+        object_store: ObjectStoreRef,
+        mut config: PartitionWriterConfig,
+        num_indexed_cols: i32,
+        stats_columns: Option<Vec<String>>,
+    ) -> DeltaResult<Self> {
+        if config.upload_concurrency == 0 {
+            return Err(WriteError::InvalidUploadConcurrency.into());
+        }
+        if config.max_rows_per_file == Some(0) {
+            return Err(WriteError::InvalidMaxRowsPerFile.into());
+        }
+        let max_row_group_size = config.writer_properties.max_row_group_size();
+        if config.write_batch_size > max_row_group_size {
+            let write_batch_size = config.write_batch_size;
+            match config.write_batch_size_behavior {
+                WriteBatchSizeBehavior::Clamp => {
+                    warn!("write_batch_size of {write_batch_size} rows exceeds the max_row_group_size of {max_row_group_size} rows, writing chunks of {max_row_group_size} rows");
+                    config.write_batch_size = max_row_group_size;
+                }
+                WriteBatchSizeBehavior::Error => {
+                    return Err(WriteError::WriteBatchSizeExceedsRowGroupSize {
+                        write_batch_size,
+                        max_row_group_size,
+                    }
+                    .into());
+                }
+            }
+        }
+        for (column, _) in &config.sort_columns {
+            if config.file_schema.index_of(column).is_err() {
+                return Err(WriteError::SortColumn {
+                    column: column.clone(),
+                }
+                .into());
+            }
+        }
+        let buffer = config.buffer();
+        let arrow_writer = config.arrow_writer(buffer.clone())?;
+
+        Ok(Self {
+            object_store,
+            writer_id: config.writer_id.unwrap_or_else(uuid::Uuid::new_v4),
+            config,
+            buffer,
+            arrow_writer,
+            part_counter: 0,
+            files_written: Vec::new(),
+            files_metadata: Vec::new(),
+            paths_written: Vec::new(),
+            published_paths: Vec::new(),
             num_indexed_cols,
             stats_columns,
+            on_file_flushed: None,
+            warned_oversized_chunk: false,
+            sort_buffer: Vec::new(),
+            sort_buffered_bytes: 0,
+            sort_buffered_rows: 0,
+            sorted_size_ratio: 1.0,
+            buffered_rows: 0,
         })
     }
 
+    /// Register a callback invoked for every file as soon as it is written to storage
+    pub fn with_file_flushed_callback(mut self, callback: FileFlushedFn) -> Self {
+        self.on_file_flushed = Some(callback);
+        self
+    }
+
     fn next_data_path(&mut self) -> Path {
         self.part_counter += 1;
 
+        if let Some(DataPathGenerator(data_path_fn)) = &self.config.data_path_fn {
+            return data_path_fn(&self.config.prefix, self.part_counter, &self.writer_id);
+        }
         next_data_path(
             &self.config.prefix,
             self.part_counter,
@@ -374,199 +2357,2364 @@ impl PartitionWriter {
     fn reset_writer(
         &mut self,
     ) -> DeltaResult<(AsyncArrowWriter<AsyncShareableBuffer>, AsyncShareableBuffer)> {
-        let new_buffer = AsyncShareableBuffer::default();
-        let arrow_writer = AsyncArrowWriter::try_new(
-            new_buffer.clone(),
-            self.config.file_schema.clone(),
-            Some(self.config.writer_properties.clone()),
-        )?;
+        let new_buffer = self.config.buffer();
+        let arrow_writer = self.config.arrow_writer(new_buffer.clone())?;
         Ok((
             std::mem::replace(&mut self.arrow_writer, arrow_writer),
             std::mem::replace(&mut self.buffer, new_buffer),
         ))
     }
 
+    /// Number of rows passed to the parquet writer at once.
+    ///
+    /// Based on the in-memory size of the batch, which overestimates the encoded size, so
+    /// splitting errs on the side of checking the file size too often.
+    fn chunk_size(&mut self, batch: &RecordBatch) -> usize {
+        let write_batch_size = self.config.write_batch_size;
+        if batch.num_rows() == 0 {
+            return write_batch_size;
+        }
+        let row_size = usize::max(1, batch.get_array_memory_size() / batch.num_rows());
+        if write_batch_size.saturating_mul(row_size) <= self.config.target_file_size {
+            return write_batch_size;
+        }
+        match self.config.oversized_chunk_behavior {
+            OversizedChunkBehavior::Warn => {
+                if !self.warned_oversized_chunk {
+                    warn!(
+                        "write_batch_size of {write_batch_size} rows exceeds the target_file_size of {} bytes, files may be larger than the target",
+                        self.config.target_file_size
+                    );
+                    self.warned_oversized_chunk = true;
+                }
+                write_batch_size
+            }
+            OversizedChunkBehavior::Split => {
+                (self.config.target_file_size / row_size).clamp(1, write_batch_size)
+            }
+        }
+    }
+
+    /// Number of rows which can still be written to the current file
+    fn remaining_rows(&self) -> usize {
+        self.config
+            .max_rows_per_file
+            .map_or(usize::MAX, |max_rows| {
+                max_rows.saturating_sub(self.buffered_rows)
+            })
+    }
+
+    /// Estimated size of the data buffered for the current file
+    async fn buffered_bytes(&self) -> usize {
+        self.buffer.len().await + self.arrow_writer.in_progress_size() + self.sort_buffered_bytes
+    }
+
+    /// Whether the sort buffer holds enough data for a file
+    fn sort_buffer_full(&self) -> bool {
+        let estimated_size = self.sort_buffered_bytes as f64 * self.sorted_size_ratio;
+        estimated_size >= self.config.target_file_size as f64
+            || self
+                .config
+                .max_rows_per_file
+                .is_some_and(|max_rows| self.sort_buffered_rows >= max_rows)
+    }
+
+    /// Sort the rows of the sort buffer and write them to new files.
+    ///
+    /// The sorted rows are passed to the parquet writer in chunks of `write_batch_size` rows
+    /// taken from the buffered batches, so the rows are not copied at once. The files are
+    /// split like unsorted data, and the last file is flushed so every file is sorted on its own.
+    async fn write_sorted(&mut self) -> DeltaResult<()> {
+        let batches = std::mem::take(&mut self.sort_buffer);
+        let in_memory_size = std::mem::take(&mut self.sort_buffered_bytes);
+        self.sort_buffered_rows = 0;
+        if batches.is_empty() {
+            return Ok(());
+        }
+
+        let schema = &self.config.file_schema;
+        let (columns, fields): (Vec<_>, Vec<_>) = self
+            .config
+            .sort_columns
+            .iter()
+            .map(|(column, ascending)| {
+                let index = schema.index_of(column)?;
+                let options = SortOptions {
+                    descending: !ascending,
+                    nulls_first: false,
+                };
+                let data_type = schema.field(index).data_type().clone();
+                Ok((index, SortField::new_with_options(data_type, options)))
+            })
+            .collect::<Result<Vec<_>, ArrowError>>()
+            .map_err(WriteError::from)?
+            .into_iter()
+            .unzip();
+        let converter = RowConverter::new(fields).map_err(WriteError::from)?;
+        let rows = batches
+            .iter()
+            .map(|batch| {
+                let columns = columns
+                    .iter()
+                    .map(|index| batch.column(*index).clone())
+                    .collect::<Vec<_>>();
+                converter.convert_columns(&columns)
+            })
+            .collect::<Result<Vec<_>, ArrowError>>()
+            .map_err(WriteError::from)?;
+        let mut indices = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(batch, rows)| (0..rows.num_rows()).map(move |row| (batch, row)))
+            .collect::<Vec<_>>();
+        indices.sort_by(|(a_batch, a_row), (b_batch, b_row)| {
+            rows[*a_batch].row(*a_row).cmp(&rows[*b_batch].row(*b_row))
+        });
+        drop(rows);
+
+        let num_files_written = self.files_written.len();
+        let batches = batches.iter().collect::<Vec<_>>();
+        for chunk in indices.chunks(self.config.write_batch_size) {
+            let sorted = interleave_record_batch(&batches, chunk).map_err(WriteError::from)?;
+            self.write_chunked(&sorted).await?;
+        }
+        self.flush_file().await?;
+
+        let encoded_size: i64 = self.files_written[num_files_written..]
+            .iter()
+            .map(|add| add.size)
+            .sum();
+        if encoded_size > 0 && in_memory_size > 0 {
+            self.sorted_size_ratio = encoded_size as f64 / in_memory_size as f64;
+        }
+        Ok(())
+    }
+
     async fn write_batch(&mut self, batch: &RecordBatch) -> DeltaResult<()> {
+        if let Some(max_file_size) = self.config.max_file_size {
+            // The in-memory size of the data is used as upper bound for its encoded size,
+            // flush first if writing the chunk could push the file beyond the cap.
+            let estimated_size = self.buffer.len().await + self.arrow_writer.in_progress_size();
+            if estimated_size > 0 && estimated_size + slice_memory_size(batch) > max_file_size {
+                debug!("Writing file with estimated size {estimated_size:?} to disk to honor the maximum file size.");
+                self.flush_file().await?;
+            }
+        }
         Ok(self.arrow_writer.write(batch).await?)
     }
 
+    /// Write a file to the object store, retrying the whole upload on transient errors
+    async fn upload(&self, path: &Path, buffer: Bytes) -> DeltaResult<()> {
+        let mut retry = 0;
+        loop {
+            match self.upload_once(path, buffer.clone()).await {
+                Err(DeltaTableError::ObjectStore { source })
+                    if retry < self.config.upload_retries && is_transient_upload_error(&source) =>
+                {
+                    retry += 1;
+                    let delay = self.config.upload_retry_backoff.delay(retry);
+                    warn!(
+                        "Retrying upload of {path} in {delay:?} after a transient error: {source}"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Write a file to the object store, using a multipart upload unless the file is smaller
+    /// than the multipart threshold
+    async fn upload_once(&self, path: &Path, mut buffer: Bytes) -> DeltaResult<()> {
+        if buffer.len() < self.config.multipart_threshold {
+            self.object_store.put(path, buffer.into()).await?;
+            return Ok(());
+        }
+
+        let mut multi_part_upload = self.object_store.put_multipart(path).await?;
+        let part_size = upload_part_size();
+        let result = async {
+            let mut tasks = JoinSet::new();
+
+            while !buffer.is_empty() {
+                let part = buffer.split_to(usize::min(part_size, buffer.len()));
+                let upload_future = multi_part_upload.put_part(part.into());
+
+                // wait until one spot frees up before spawning new task
+                if tasks.len() >= self.config.upload_concurrency {
+                    if let Some(result) = tasks.join_next().await {
+                        result.map_err(|e| DeltaTableError::generic(e.to_string()))??;
+                    }
+                }
+                tasks.spawn(upload_future);
+            }
+
+            // wait for all remaining tasks to complete
+            while let Some(result) = tasks.join_next().await {
+                result.map_err(|e| DeltaTableError::generic(e.to_string()))??;
+            }
+
+            multi_part_upload.complete().await?;
+            Ok::<_, DeltaTableError>(())
+        }
+        .await;
+
+        // don't leave incomplete uploads behind, stores keep their parts until aborted
+        if let Err(err) = result {
+            if let Err(abort_err) = multi_part_upload.abort().await {
+                warn!("Failed to abort multipart upload of {path}: {abort_err}");
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Write all buffered data to storage
     async fn flush_arrow_writer(&mut self) -> DeltaResult<()> {
+        if self.sort_buffer.is_empty() {
+            self.flush_file().await
+        } else {
+            self.write_sorted().await
+        }
+    }
+
+    /// Write the data passed to the parquet writer to a new file
+    async fn flush_file(&mut self) -> DeltaResult<()> {
+        // held until the file is uploaded, so writers wait for a slow store
+        let _permit = match &self.config.flush_semaphore {
+            Some(flush_semaphore) => Some(
+                flush_semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|err| DeltaTableError::generic(err.to_string()))?,
+            ),
+            None => None,
+        };
         // replace counter / buffers and close the current writer
         let (writer, buffer) = self.reset_writer()?;
+        self.buffered_rows = 0;
         let metadata = writer.close().await?;
         // don't write empty file
         if metadata.num_rows == 0 {
             return Ok(());
         }
 
-        let mut buffer = match buffer.into_inner().await {
+        let buffer = match buffer.into_inner().await {
             Some(buffer) => Bytes::from(buffer),
             None => return Ok(()), // Nothing to write
         };
+        // keep a handle on the allocation to return it to the pool once uploaded
+        let pooled = self.config.buffer_pool.as_ref().map(|_| buffer.clone());
+
+        // collect metadata
+        let published_path = self.next_data_path();
+        let path = match &self.config.staging_prefix {
+            Some(prefix) => Path::from_iter(prefix.parts().chain(published_path.parts())),
+            None => published_path.clone(),
+        };
+        let file_size = buffer.len() as i64;
+
+        self.upload(&path, buffer).await?;
+        if let (Some(buffer_pool), Some(pooled)) = (&self.config.buffer_pool, pooled) {
+            // only reusable if the store released all parts of the upload
+            if let Ok(pooled) = pooled.try_into_mut() {
+                buffer_pool.put(pooled.into());
+            }
+        }
+
+        let mut add = create_add(
+            &self.config.partition_values,
+            path.to_string(),
+            file_size,
+            &metadata,
+            self.num_indexed_cols,
+            &self.stats_columns,
+        )
+        .map_err(|err| WriteError::CreateAdd {
+            source: Box::new(err),
+        })?;
+        if self.config.row_group_stats_in_tags {
+            let row_group_stats = row_group_stats_from_file_metadata(
+                &self.config.partition_values,
+                &metadata,
+                self.num_indexed_cols,
+                &self.stats_columns,
+            )
+            .map_err(|err| WriteError::CreateAdd {
+                source: Box::new(err),
+            })?;
+            add.tags.get_or_insert_with(HashMap::new).insert(
+                ROW_GROUP_STATS_TAG.to_string(),
+                Some(serde_json::to_string(&row_group_stats)?),
+            );
+        }
+        if self.config.uncompressed_size_in_tags {
+            let uncompressed_size: i64 = metadata
+                .row_groups
+                .iter()
+                .map(|row_group| row_group.total_byte_size)
+                .sum();
+            add.tags.get_or_insert_with(HashMap::new).insert(
+                UNCOMPRESSED_SIZE_TAG.to_string(),
+                Some(uncompressed_size.to_string()),
+            );
+        }
+        if let Some(callback) = &self.on_file_flushed {
+            callback(&add);
+        }
+        self.files_written.push(add);
+        self.files_metadata.push(metadata);
+        self.paths_written.push(path);
+        self.published_paths.push(published_path);
+
+        Ok(())
+    }
+
+    /// Buffers record batches in-memory up to appx. `target_file_size`.
+    /// Flushes data to storage once a full file can be written.
+    ///
+    /// The `close` method has to be invoked to write all data still buffered
+    /// and get the list of all written files.
+    pub async fn write(&mut self, batch: &RecordBatch) -> DeltaResult<()> {
+        let adapted;
+        let batch = if batch.schema() == self.config.file_schema {
+            batch
+        } else if self.config.schema_adaptation {
+            adapted = adapt_batch(batch, &self.config.file_schema)?;
+            &adapted
+        } else {
+            return Err(WriteError::SchemaMismatch {
+                schema: batch.schema(),
+                expected_schema: self.config.file_schema.clone(),
+            }
+            .into());
+        };
+
+        if !self.config.sort_columns.is_empty() {
+            // the rows of a file can only be sorted once all of them are buffered
+            self.sort_buffered_bytes += batch.get_array_memory_size();
+            self.sort_buffered_rows += batch.num_rows();
+            self.sort_buffer.push(batch.clone());
+            if self.sort_buffer_full() {
+                debug!(
+                    "Writing sorted data with in-memory size {} and {} rows to disk.",
+                    self.sort_buffered_bytes, self.sort_buffered_rows
+                );
+                self.write_sorted().await?;
+            }
+            return Ok(());
+        }
+        self.write_chunked(batch).await
+    }
+
+    /// Pass the batch to the parquet writer in chunks, writing a new file whenever the
+    /// buffered data reaches the target file size or the maximum number of rows
+    async fn write_chunked(&mut self, batch: &RecordBatch) -> DeltaResult<()> {
+        let max_offset = batch.num_rows();
+        let mut chunk_size = self.chunk_size(batch);
+        if let Some(max_file_size) = self.config.max_file_size {
+            let row_size = usize::max(1, slice_memory_size(batch) / usize::max(1, max_offset));
+            chunk_size = chunk_size.min(usize::max(1, max_file_size / row_size));
+        }
+        let max_chunk_size = chunk_size;
+        let mut offset = 0;
+        while offset < max_offset {
+            let length = usize::min(chunk_size, max_offset - offset).min(self.remaining_rows());
+            let previous_size = self.buffer.len().await + self.arrow_writer.in_progress_size();
+            self.write_batch(&batch.slice(offset, length)).await?;
+            self.buffered_rows += length;
+            offset += length;
+            // flush currently buffered data to disk once we meet or exceed the target file size
+            // or the maximum number of rows.
+            let estimated_size = self.buffer.len().await + self.arrow_writer.in_progress_size();
+            if estimated_size >= self.config.target_file_size || self.remaining_rows() == 0 {
+                debug!(
+                    "Writing file with estimated size {estimated_size:?} and {} rows to disk.",
+                    self.buffered_rows
+                );
+                self.flush_file().await?;
+                chunk_size = max_chunk_size;
+            } else if self.config.adaptive_chunk_size {
+                chunk_size = adaptive_chunk_size(
+                    chunk_size,
+                    max_chunk_size,
+                    length,
+                    estimated_size.saturating_sub(previous_size),
+                    self.config.target_file_size - estimated_size,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flush the currently buffered data to storage without closing the writer.
+    ///
+    /// Returns the [Add] actions for the files written by this flush. Those files are
+    /// still included in the actions returned by `close`.
+    pub async fn flush(&mut self) -> DeltaResult<Vec<Add>> {
+        let num_files_written = self.files_written.len();
+        self.flush_arrow_writer().await?;
+        Ok(self.files_written[num_files_written..].to_vec())
+    }
+
+    /// The [Add] actions of the files flushed to storage so far, e.g. to report the progress
+    /// of long running writes. Data still buffered in memory is not included.
+    pub fn files_written(&self) -> &[Add] {
+        &self.files_written
+    }
+
+    /// Close the writer and get the new [Add] actions.
+    pub async fn close(mut self) -> DeltaResult<Vec<Add>> {
+        self.flush_arrow_writer().await?;
+        Ok(self.files_written)
+    }
+
+    /// Close the writer and get the new [Add] actions along with the object store paths of
+    /// their files and the paths they are published to.
+    async fn close_with_paths(mut self) -> DeltaResult<(Vec<Add>, Vec<(Path, Path)>)> {
+        self.flush_arrow_writer().await?;
+        let paths = self
+            .paths_written
+            .into_iter()
+            .zip(self.published_paths)
+            .collect();
+        Ok((self.files_written, paths))
+    }
+
+    /// Discard the buffered data and delete all files written so far.
+    ///
+    /// Use this when a write fails partway, so the files which will never be committed are
+    /// not left behind in storage. Files deleted by someone else in the meantime are logged.
+    pub async fn abort(self) -> DeltaResult<()> {
+        for path in self.paths_written {
+            delete_written_file(self.object_store.as_ref(), path).await?;
+        }
+        Ok(())
+    }
+
+    /// Close the writer and get the new [Add] actions along with the parquet metadata of
+    /// their files, e.g. to build external indexes without reading the files again.
+    pub async fn close_with_metadata(mut self) -> DeltaResult<Vec<(Add, FileMetaData)>> {
+        self.flush_arrow_writer().await?;
+        Ok(self
+            .files_written
+            .into_iter()
+            .zip(self.files_metadata)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::scalars::ScalarExt;
+    use crate::logstore::tests::flatten_list_stream as list;
+    use crate::table::config::DEFAULT_NUM_INDEX_COLS;
+    use crate::test_utils::TestObjectStore;
+    use crate::writer::test_utils::*;
+    use crate::DeltaTableBuilder;
+    use arrow::array::{Int32Array, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema as ArrowSchema};
+    use std::sync::Arc;
+
+    fn get_delta_writer(
+        object_store: ObjectStoreRef,
+        batch: &RecordBatch,
+        writer_properties: Option<WriterProperties>,
+        target_file_size: Option<usize>,
+        write_batch_size: Option<usize>,
+    ) -> DeltaWriter {
+        let config = WriterConfig::new(
+            batch.schema(),
+            vec![],
+            writer_properties,
+            target_file_size,
+            write_batch_size,
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        );
+        DeltaWriter::new(object_store, config)
+    }
+
+    fn get_partition_writer(
+        object_store: ObjectStoreRef,
+        batch: &RecordBatch,
+        writer_properties: Option<WriterProperties>,
+        target_file_size: Option<usize>,
+        write_batch_size: Option<usize>,
+    ) -> PartitionWriter {
+        let config = PartitionWriterConfig::try_new(
+            batch.schema(),
+            IndexMap::new(),
+            None,
+            writer_properties,
+            target_file_size,
+            write_batch_size,
+        )
+        .unwrap();
+        PartitionWriter::try_with_config(object_store, config, DEFAULT_NUM_INDEX_COLS, None)
+            .unwrap()
+    }
+
+    fn memory_store() -> ObjectStoreRef {
+        DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap()
+            .object_store(None)
+    }
+
+    /// Writer config with the default options, partitioned by the given columns
+    fn writer_config(schema: ArrowSchemaRef, partition_columns: &[&str]) -> WriterConfig {
+        WriterConfig::new(
+            schema,
+            partition_columns.iter().map(|c| c.to_string()).collect(),
+            None,
+            None,
+            None,
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        )
+    }
+
+    /// Partition writer config with the default options for an unpartitioned table
+    fn partition_writer_config(schema: ArrowSchemaRef) -> PartitionWriterConfig {
+        PartitionWriterConfig::try_new(schema, IndexMap::new(), None, None, None, None).unwrap()
+    }
+
+    fn partition_writer(
+        object_store: ObjectStoreRef,
+        config: PartitionWriterConfig,
+    ) -> PartitionWriter {
+        PartitionWriter::try_with_config(object_store, config, DEFAULT_NUM_INDEX_COLS, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compression_from_env() {
+        // a dedicated variable, as the process wide default is only read once
+        const VAR: &str = "DELTARS_DEFAULT_COMPRESSION_TEST";
+        std::env::set_var(VAR, "zstd(3)");
+        assert_eq!(
+            compression_from_env(VAR),
+            Compression::ZSTD(parquet::basic::ZstdLevel::try_new(3).unwrap())
+        );
+        std::env::set_var(VAR, "fast");
+        assert_eq!(compression_from_env(VAR), Compression::SNAPPY);
+        std::env::remove_var(VAR);
+        assert_eq!(compression_from_env(VAR), Compression::SNAPPY);
+    }
+
+    #[tokio::test]
+    async fn test_write_partition() {
+        let log_store = DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap();
+        let object_store = log_store.object_store(None);
+        let batch = get_record_batch(None, false);
+
+        // write single un-partitioned batch
+        let mut writer = get_partition_writer(object_store.clone(), &batch, None, None, None);
+        writer.write(&batch).await.unwrap();
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), 0);
+        let adds = writer.close().await.unwrap();
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files.len(), adds.len());
+        let head = object_store
+            .head(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap();
+        assert_eq!(head.size, adds[0].size as u64)
+    }
+
+    #[tokio::test]
+    async fn test_write_partition_with_parts() {
+        let base_int = Arc::new(Int32Array::from((0..10000).collect::<Vec<i32>>()));
+        let base_str = Arc::new(StringArray::from(vec!["A"; 10000]));
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(schema, vec![base_str, base_int]).unwrap();
+
+        let object_store = DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap()
+            .object_store(None);
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(1024)
+            .build();
+        // configure small target file size and and row group size so we can observe multiple files written
+        let mut writer =
+            get_partition_writer(object_store, &batch, Some(properties), Some(10_000), None);
+        writer.write(&batch).await.unwrap();
+
+        // check that we have written more then once file, and no more then 1 is below target size
+        let adds = writer.close().await.unwrap();
+        assert!(adds.len() > 1);
+        let target_file_count = adds
+            .iter()
+            .fold(0, |acc, add| acc + (add.size > 10_000) as i32);
+        assert!(target_file_count >= adds.len() as i32 - 1)
+    }
+
+    #[tokio::test]
+    async fn test_unflushed_row_group_size() {
+        let base_int = Arc::new(Int32Array::from((0..10000).collect::<Vec<i32>>()));
+        let base_str = Arc::new(StringArray::from(vec!["A"; 10000]));
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(schema, vec![base_str, base_int]).unwrap();
+
+        let object_store = DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap()
+            .object_store(None);
+        // configure small target file size so we can observe multiple files written
+        let mut writer = get_partition_writer(object_store, &batch, None, Some(10_000), None);
+        writer.write(&batch).await.unwrap();
+
+        // check that we have written more then once file, and no more then 1 is below target size
+        let adds = writer.close().await.unwrap();
+        assert!(adds.len() > 1);
+        let target_file_count = adds
+            .iter()
+            .fold(0, |acc, add| acc + (add.size > 10_000) as i32);
+        assert!(target_file_count >= adds.len() as i32 - 1)
+    }
+
+    #[tokio::test]
+    async fn test_do_not_write_empty_file_on_close() {
+        let base_int = Arc::new(Int32Array::from((0..10000_i32).collect::<Vec<i32>>()));
+        let base_str = Arc::new(StringArray::from(vec!["A"; 10000]));
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(schema, vec![base_str, base_int]).unwrap();
+
+        let object_store = DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap()
+            .object_store(None);
+        // configure high batch size and low file size to observe one file written and flushed immediately
+        // upon writing batch, then ensures the buffer is empty upon closing writer
+        let mut writer = get_partition_writer(object_store, &batch, None, Some(9000), Some(10000));
+        writer.write(&batch).await.unwrap();
+
+        let adds = writer.close().await.unwrap();
+        assert!(adds.len() == 1);
+    }
+
+    #[tokio::test]
+    async fn test_flush_all_keeps_writers_open() {
+        let log_store = DeltaTableBuilder::from_uri("memory:///")
+            .build_storage()
+            .unwrap();
+        let object_store = log_store.object_store(None);
+        let batch = get_record_batch(None, false);
+
+        let mut writer = get_delta_writer(object_store.clone(), &batch, None, None, None);
+        writer.write(&batch).await.unwrap();
+        let flushed = writer.flush_all().await.unwrap();
+        assert_eq!(flushed.len(), 1);
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), 1);
+
+        // flushing again without new data must not write empty files
+        assert!(writer.flush_all().await.unwrap().is_empty());
+
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 2);
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_byte_stream_split() {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Float64,
+            true,
+        )]));
+        let values = (0..10_000).map(|i| (i as f64 * 0.001).sin() * 100.0);
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(arrow::array::Float64Array::from_iter_values(
+                values,
+            ))],
+        )
+        .unwrap();
+        let writer_properties = WriterProperties::builder()
+            .set_writer_version(WriterVersion::PARQUET_2_0)
+            .set_compression(Compression::ZSTD(Default::default()))
+            .set_dictionary_enabled(false)
+            .build();
+
+        let mut sizes = Vec::new();
+        for byte_stream_split in [false, true] {
+            let object_store = memory_store();
+            let mut config = WriterConfig::new(
+                schema.clone(),
+                vec![],
+                Some(writer_properties.clone()),
+                None,
+                None,
+                DEFAULT_NUM_INDEX_COLS,
+                None,
+            );
+            if byte_stream_split {
+                config = config.with_byte_stream_split(["value"]);
+            }
+            let mut writer = DeltaWriter::new(object_store, config);
+            writer.write(&batch).await.unwrap();
+            let adds = writer.close().await.unwrap();
+            sizes.push(adds.iter().map(|add| add.size).sum::<i64>());
+        }
+        assert!(sizes[1] < sizes[0]);
+
+        // byte stream split requires the parquet 2.0 writer version
+        let object_store = memory_store();
+        let config = writer_config(schema, &[]).with_byte_stream_split(["value"]);
+        let mut writer = DeltaWriter::new(object_store, config);
+        assert!(writer.write(&batch).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_close_with_metrics() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &["modified"]).with_min_file_size(1024 * 1024);
+        let mut writer = DeltaWriter::new(object_store, config);
+        writer.write(&batch).await.unwrap();
+
+        let (adds, metrics) = writer.close_with_metrics().await.unwrap();
+        assert_eq!(metrics.num_files, adds.len());
+        assert_eq!(metrics.num_files_below_min_size, adds.len());
+    }
+
+    #[tokio::test]
+    async fn test_data_path_fn() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let data_path_fn: DataPathFn = Arc::new(|prefix, part, writer_id| {
+            prefix
+                .child("tenant=a")
+                .child(format!("{writer_id}-{part}.parquet"))
+        });
+        let config = writer_config(batch.schema(), &["modified"]).with_data_path_fn(data_path_fn);
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 2);
+        for add in adds {
+            assert!(add.path.starts_with("modified=2021-02-0"));
+            assert!(add.path.contains("/tenant=a/"));
+            assert!(add.path.ends_with("-1.parquet"));
+            let path = Path::parse(&add.path).unwrap();
+            assert_eq!(
+                object_store.head(&path).await.unwrap().size as i64,
+                add.size
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partition_path_encoder() {
+        #[derive(Debug)]
+        struct FlatEncoder;
+
+        impl PartitionPathEncoder for FlatEncoder {
+            fn encode(&self, partition_values: &IndexMap<String, Scalar>) -> String {
+                let fields = partition_values
+                    .iter()
+                    .map(|(column, value)| format!("{column}={}", value.serialize_encoded()))
+                    .collect::<Vec<_>>();
+                format!("flat/{}", fields.join("_"))
+            }
+        }
+
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &["modified"])
+            .with_partition_path_encoder(Arc::new(FlatEncoder));
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 2);
+        for add in adds {
+            let modified = add.partition_values["modified"].clone().unwrap();
+            assert!(add
+                .path
+                .starts_with(&format!("flat/modified={modified}/part-")));
+            let path = Path::parse(&add.path).unwrap();
+            assert!(object_store.head(&path).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_files_written_while_writing() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+
+        // every chunk exceeds the tiny target file size and is flushed right away
+        let mut writer = get_partition_writer(object_store.clone(), &batch, None, Some(1), Some(4));
+        assert!(writer.files_written().is_empty());
+        writer.write(&batch).await.unwrap();
+        let num_files = writer.files_written().len();
+        assert!(num_files > 0);
+        writer.write(&batch).await.unwrap();
+        assert!(writer.files_written().len() > num_files);
+        let files_written = writer.files_written().to_vec();
+        assert_eq!(writer.close().await.unwrap(), files_written);
+
+        let config = WriterConfig::new(
+            batch.schema(),
+            vec!["modified".to_string()],
+            None,
+            Some(1),
+            Some(4),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        );
+        let mut writer = DeltaWriter::new(object_store, config);
+        writer.write(&batch).await.unwrap();
+        let num_bytes: i64 = writer.files_written().map(|add| add.size).sum();
+        assert!(num_bytes > 0);
+        let files_written = writer.files_written().count();
+        assert_eq!(writer.close().await.unwrap().len(), files_written);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_delta_writer() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let partition_columns = vec!["modified".to_string()];
+        let config = writer_config(batch.schema(), &["modified"]);
+        let writer = ConcurrentDeltaWriter::new(object_store.clone(), config);
+
+        let partitions = divide_by_partition_values(
+            arrow_schema_without_partitions(&batch.schema(), &partition_columns),
+            partition_columns,
+            &batch,
+        )
+        .unwrap();
+        assert!(partitions.len() > 1);
+
+        let mut tasks = JoinSet::new();
+        let mut handles = Vec::new();
+        for partition in partitions.iter() {
+            let handle = writer
+                .partition_writer(&partition.partition_values)
+                .unwrap();
+            handles.push(handle.clone());
+            let record_batch = partition.record_batch.clone();
+            tasks.spawn(async move { handle.write(&record_batch).await });
+        }
+        while let Some(result) = tasks.join_next().await {
+            result.unwrap().unwrap();
+        }
+
+        let adds = writer.close().await.unwrap();
+        let mut written_partitions = adds
+            .iter()
+            .map(|add| add.partition_values["modified"].clone().unwrap())
+            .collect::<Vec<_>>();
+        written_partitions.sort();
+        written_partitions.dedup();
+        let mut expected_partitions = partitions
+            .iter()
+            .map(|partition| partition.partition_values["modified"].serialize())
+            .collect::<Vec<_>>();
+        expected_partitions.sort();
+        assert_eq!(written_partitions, expected_partitions);
+        let num_records: i64 = adds
+            .iter()
+            .map(|add| add.get_stats().unwrap().unwrap().num_records)
+            .sum();
+        assert_eq!(num_records, batch.num_rows() as i64);
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), adds.len());
+
+        // the writers of the handles were closed along with the writer
+        let result = handles[0].write(&partitions[0].record_batch).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_delta_writer_publish() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let partition_columns = vec!["modified".to_string()];
+        let config = writer_config(batch.schema(), &["modified"]).with_staging(true);
+        let writer = ConcurrentDeltaWriter::new(object_store.clone(), config);
+
+        let partitions = divide_by_partition_values(
+            arrow_schema_without_partitions(&batch.schema(), &partition_columns),
+            partition_columns,
+            &batch,
+        )
+        .unwrap();
+        for partition in partitions.iter() {
+            let handle = writer
+                .partition_writer(&partition.partition_values)
+                .unwrap();
+            handle.write(&partition.record_batch).await.unwrap();
+        }
+
+        let adds = writer.publish().await.unwrap();
+        assert_eq!(adds.len(), partitions.len());
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), adds.len());
+        for add in &adds {
+            assert!(add.path.starts_with("modified="));
+            assert!(files.contains(&Path::parse(&add.path).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_adaptive_chunk_size() {
+        // 100 rows grew the file by 1000 bytes, leaving room for 50 more rows
+        assert_eq!(adaptive_chunk_size(100, 100, 100, 1000, 500), 50);
+        // chunks never grow beyond the maximum
+        assert_eq!(adaptive_chunk_size(50, 100, 50, 50, 10_000), 100);
+        // at least one row is written per chunk
+        assert_eq!(adaptive_chunk_size(100, 100, 100, 1000, 5), 1);
+        // without observed growth the chunk size is kept
+        assert_eq!(adaptive_chunk_size(20, 100, 20, 0, 500), 20);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_chunk_size_write() {
+        let object_store = memory_store();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Utf8,
+            false,
+        )]));
+        let values = (0..10_000).map(|i| format!("{i:0>64}")).collect::<Vec<_>>();
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(StringArray::from(values))])
+            .unwrap();
+        let target_file_size = 100_000;
+        let write = |adaptive: bool| {
+            let object_store = object_store.clone();
+            let batch = batch.clone();
+            async move {
+                let config = PartitionWriterConfig::try_new(
+                    batch.schema(),
+                    IndexMap::new(),
+                    None,
+                    None,
+                    Some(target_file_size),
+                    Some(1024),
+                )
+                .unwrap()
+                .with_adaptive_chunk_size(adaptive);
+                let mut writer = partition_writer(object_store, config);
+                writer.write(&batch).await.unwrap();
+                writer.close().await.unwrap()
+            }
+        };
+
+        let fixed = write(false).await;
+        let adaptive = write(true).await;
+        let num_records = |adds: &[Add]| {
+            adds.iter()
+                .map(|add| add.get_stats().unwrap().unwrap().num_records)
+                .sum::<i64>()
+        };
+        assert_eq!(num_records(&fixed), 10_000);
+        assert_eq!(num_records(&adaptive), 10_000);
+        // adaptive chunks overshoot the target file size by less than fixed chunks
+        let max_size = |adds: &[Add]| adds.iter().map(|add| add.size).max().unwrap();
+        assert!(max_size(&adaptive) <= max_size(&fixed));
+    }
+
+    #[tokio::test]
+    async fn test_schema_adaptation() {
+        let object_store = memory_store();
+        let table_schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let dictionary_type =
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("id", dictionary_type.clone(), false),
+                Field::new("value", DataType::Int32, false),
+            ])),
+            vec![
+                Arc::new(
+                    vec!["a", "b", "a"]
+                        .into_iter()
+                        .collect::<arrow::array::DictionaryArray<arrow::datatypes::Int32Type>>(),
+                ),
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+        let config = || writer_config(table_schema.clone(), &[]);
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config());
+        let result = writer.write(&batch).await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::SchemaMismatch { .. })
+        ));
+
+        let mut writer =
+            DeltaWriter::new(object_store.clone(), config().with_schema_adaptation(true));
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+        assert_eq!(adds[0].get_stats().unwrap().unwrap().num_records, 3);
+
+        // changing the type of a column is not safe
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("id", DataType::Utf8, true),
+                Field::new("value", DataType::Int64, true),
+            ])),
+            vec![
+                Arc::new(StringArray::from(vec!["a"])),
+                Arc::new(arrow::array::Int64Array::from(vec![1])),
+            ],
+        )
+        .unwrap();
+        let mut writer =
+            DeltaWriter::new(object_store.clone(), config().with_schema_adaptation(true));
+        let result = writer.write(&batch).await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::SchemaMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_flush_partition() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &["modified"]);
+        let partition_values = IndexMap::from([(
+            "modified".to_string(),
+            Scalar::String("2021-02-02".to_string()),
+        )]);
+
+        let mut writer = DeltaWriter::new(object_store, config);
+        writer.write(&batch).await.unwrap();
+        assert_eq!(writer.partition_writers.len(), 2);
+
+        let flushed = writer.flush_partition(&partition_values).await.unwrap();
+        assert_eq!(flushed.len(), 1);
+        assert!(flushed[0].path.starts_with("modified=2021-02-02/"));
+        assert_eq!(writer.partition_writers.len(), 1);
+        assert!(writer
+            .flush_partition(&partition_values)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // writing to the flushed partition again opens a fresh writer
+        writer.write(&batch).await.unwrap();
+        assert_eq!(writer.partition_writers.len(), 2);
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 2);
+        assert!(adds.iter().all(|add| add.path != flushed[0].path));
+    }
+
+    #[tokio::test]
+    async fn test_max_total_buffered_bytes() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let target_file_size = 1024 * 1024;
+        let config = WriterConfig::new(
+            batch.schema(),
+            vec!["modified".to_string()],
+            None,
+            Some(target_file_size),
+            None,
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        )
+        .with_max_total_buffered_bytes(1);
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        for num_writes in 1..=3 {
+            writer.write(&batch).await.unwrap();
+            // every partition exceeds the budget of a single byte and is flushed right away
+            let files = list(object_store.as_ref(), None).await.unwrap();
+            assert_eq!(files.len(), num_writes * writer.partition_writers.len());
+        }
+
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 6);
+        assert!(adds
+            .iter()
+            .all(|add| (add.size as usize) < target_file_size));
+    }
+
+    #[test]
+    fn test_partition_values_checked() {
+        let table_schema = ArrowSchema::new(vec![
+            Field::new("value", DataType::Int64, false),
+            Field::new("part", DataType::Utf8, true),
+        ]);
+        let file_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Int64,
+            false,
+        )]));
+        let config = |column: &str, value: Scalar| {
+            PartitionWriterConfig::try_new_checked(
+                &table_schema,
+                file_schema.clone(),
+                IndexMap::from([(column.to_string(), value)]),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+
+        let valid = config("part", Scalar::String("a".into())).unwrap();
+        assert_eq!(valid.prefix, Path::from("part=a"));
+
+        let unknown = config("prat", Scalar::String("a".into()));
+        assert!(matches!(
+            unknown,
+            Err(DeltaTableError::SchemaMismatch { msg }) if msg.contains("prat")
+        ));
+
+        let mismatched = config("part", Scalar::Integer(1));
+        assert!(matches!(
+            mismatched,
+            Err(DeltaTableError::SchemaMismatch { msg }) if msg.contains("part")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_upload_concurrency() {
+        let store = Arc::new(TestObjectStore::new());
+        let object_store: ObjectStoreRef = store.clone();
+
+        // plain encoded values, so the file spans several upload parts
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(0..3_000_000))],
+        )
+        .unwrap();
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::UNCOMPRESSED)
+            .set_dictionary_enabled(false)
+            .build();
+        let config = |upload_concurrency| {
+            PartitionWriterConfig::try_new(
+                schema.clone(),
+                IndexMap::new(),
+                None,
+                Some(properties.clone()),
+                None,
+                None,
+            )
+            .unwrap()
+            .with_upload_concurrency(upload_concurrency)
+        };
+        let mut writer = partition_writer(object_store.clone(), config(2));
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+        assert!(store.num_parts() > 2);
+        assert!(store.max_in_flight_parts() <= 2);
+
+        let result =
+            PartitionWriter::try_with_config(object_store, config(0), DEFAULT_NUM_INDEX_COLS, None);
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_retries() {
+        let batch = get_record_batch(None, false);
+        let config = || writer_config(batch.schema(), &[]);
+
+        // the parts of the first two uploads fail, the third one succeeds
+        let store = Arc::new(TestObjectStore::new().with_failing_parts(2));
+        let object_store: ObjectStoreRef = store.clone();
+        let mut writer = DeltaWriter::new(
+            object_store.clone(),
+            config().with_upload_retries(3, RetryBackoff::default()),
+        );
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+        assert_eq!(store.num_uploads(), 3);
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(bytes.len() as i64, adds[0].size);
+        let reader = parquet::file::reader::SerializedFileReader::new(bytes).unwrap();
+        assert_eq!(
+            parquet::file::reader::FileReader::metadata(&reader)
+                .file_metadata()
+                .num_rows(),
+            batch.num_rows() as i64
+        );
+
+        // without retries, the first transient error fails the write
+        let store = Arc::new(TestObjectStore::new().with_failing_parts(1));
+        let mut writer = DeltaWriter::new(store.clone(), config());
+        writer.write(&batch).await.unwrap();
+        assert!(writer.close().await.is_err());
+        assert_eq!(store.num_uploads(), 1);
+    }
+
+    #[test]
+    fn test_transient_upload_errors() {
+        let timeout = ObjectStoreError::Generic {
+            store: "test",
+            source: Box::new(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "request failed",
+            )),
+        };
+        assert!(is_transient_upload_error(&timeout));
+        let connect = ObjectStoreError::Generic {
+            store: "test",
+            source: Box::new(HttpError::new(
+                HttpErrorKind::Connect,
+                std::io::Error::other("connection refused"),
+            )),
+        };
+        assert!(is_transient_upload_error(&connect));
+        // the kind of an error decides, not its message
+        let message = ObjectStoreError::Generic {
+            store: "test",
+            source: "connection reset".into(),
+        };
+        assert!(!is_transient_upload_error(&message));
+        let decode = ObjectStoreError::Generic {
+            store: "test",
+            source: Box::new(HttpError::new(
+                HttpErrorKind::Decode,
+                std::io::Error::other("invalid body"),
+            )),
+        };
+        assert!(!is_transient_upload_error(&decode));
+        let denied = ObjectStoreError::PermissionDenied {
+            path: "file.parquet".to_string(),
+            source: Box::new(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "request failed",
+            )),
+        };
+        assert!(!is_transient_upload_error(&denied));
+        let not_found = ObjectStoreError::NotFound {
+            path: "file.parquet".to_string(),
+            source: "not found".into(),
+        };
+        assert!(!is_transient_upload_error(&not_found));
+    }
+
+    #[tokio::test]
+    async fn test_publish_staged_files() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &["modified"])
+            .with_max_rows_per_file(2)
+            .with_staging(true);
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        // files are flushed while writing, but only to the staging prefix
+        let staged = list(object_store.as_ref(), None).await.unwrap();
+        assert!(!staged.is_empty());
+        assert!(staged
+            .iter()
+            .all(|path| path.as_ref().starts_with(&format!("{STAGING_PREFIX}/"))));
+
+        let adds = writer.publish().await.unwrap();
+        assert!(!adds.is_empty());
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), adds.len());
+        for add in &adds {
+            assert!(add.path.starts_with("modified="));
+            assert!(files.contains(&Path::parse(&add.path).unwrap()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_staged_files_with_data_path_fn() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        // files are nested below a directory in front of the partition prefix
+        let data_path_fn: DataPathFn = Arc::new(|prefix, part, writer_id| {
+            Path::from(format!("data/{prefix}")).child(format!("{writer_id}-{part}.parquet"))
+        });
+        let config = writer_config(batch.schema(), &["modified"])
+            .with_data_path_fn(data_path_fn)
+            .with_staging(true);
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let staged = list(object_store.as_ref(), None).await.unwrap();
+        assert!(staged
+            .iter()
+            .all(|path| path.as_ref().starts_with(&format!("{STAGING_PREFIX}/"))));
+
+        let adds = writer.publish().await.unwrap();
+        assert_eq!(adds.len(), 2);
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), adds.len());
+        for add in &adds {
+            assert!(add.path.starts_with("data/modified="));
+            assert!(files.contains(&Path::parse(&add.path).unwrap()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_publish_deletes_files() {
+        let store = Arc::new(TestObjectStore::new().with_failing_copies(1));
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &["modified"])
+            .with_max_rows_per_file(2)
+            .with_staging(true);
+
+        let mut writer = DeltaWriter::new(store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        // a single file fails to move, the files moved before or after it are deleted as well
+        assert!(writer.publish().await.is_err());
+        assert!(list(store.as_ref(), None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_flushes() {
+        let store = Arc::new(TestObjectStore::new());
+        let object_store: ObjectStoreRef = store.clone();
+
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("value", DataType::Int64, false),
+            Field::new("part", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from_iter_values(0..800)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..800).map(|i| format!("p{}", i % 8)),
+                )),
+            ],
+        )
+        .unwrap();
+        // a single part per file, so the parts in flight are the files being flushed
+        let config = writer_config(schema, &["part"])
+            .with_upload_concurrency(1)
+            .with_max_concurrent_flushes(2);
+
+        let mut writer = DeltaWriter::new(object_store, config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 8);
+        assert!(store.max_in_flight_parts() <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_buffer_pooling() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &["modified"]).with_buffer_pooling(true);
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        let buffer_pool = writer.buffer_pool.clone().unwrap();
+        writer.write(&batch).await.unwrap();
+        assert_eq!(buffer_pool.num_available(), 0);
+        let flushed = writer.flush_all().await.unwrap();
+        assert_eq!(flushed.len(), 2);
+        // the buffers of the uploaded files are returned to the pool
+        assert_eq!(buffer_pool.num_available(), 2);
+
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 4);
+        let files = list(object_store.as_ref(), None).await.unwrap();
+        assert_eq!(files.len(), 4);
+        for add in adds {
+            let size = object_store.head(&Path::from(add.path)).await.unwrap().size;
+            assert_eq!(size, add.size as u64);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_threshold() {
+        let store = Arc::new(TestObjectStore::new());
+        let object_store: ObjectStoreRef = store.clone();
+        let batch = get_record_batch(None, false);
+        let write = |multipart_threshold| {
+            let object_store = object_store.clone();
+            let batch = batch.clone();
+            async move {
+                let config = partition_writer_config(batch.schema())
+                    .with_multipart_threshold(multipart_threshold);
+                let mut writer = partition_writer(object_store, config);
+                writer.write(&batch).await.unwrap();
+                writer.close().await.unwrap().pop().unwrap()
+            }
+        };
+
+        // below the threshold
+        let single_put = write(1024 * 1024).await;
+        assert_eq!(store.num_puts(), 1);
+        assert_eq!(store.num_uploads(), 0);
+
+        // above the threshold
+        let multipart = write(1).await;
+        assert_eq!(store.num_puts(), 1);
+        assert_eq!(store.num_uploads(), 1);
+
+        assert_eq!(single_put.size, multipart.size);
+        assert_eq!(single_put.stats, multipart.stats);
+        assert_eq!(single_put.partition_values, multipart.partition_values);
+        for add in [single_put, multipart] {
+            let size = object_store.head(&Path::from(add.path)).await.unwrap().size;
+            assert_eq!(size, add.size as u64);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sort_columns() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let object_store = memory_store();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let batches = [vec![5, 1, 9], vec![3, 7], vec![2, 8, 4, 6]].map(|values| {
+            let ids = values
+                .iter()
+                .map(|value| format!("id-{value}"))
+                .collect::<Vec<_>>();
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(ids)),
+                    Arc::new(Int32Array::from(values)),
+                ],
+            )
+            .unwrap()
+        });
+
+        let config = WriterConfig::new(
+            schema.clone(),
+            vec![],
+            None,
+            None,
+            Some(2),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        )
+        .with_sort_columns(vec![("value".to_string(), false)]);
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        for batch in &batches {
+            writer.write(batch).await.unwrap();
+        }
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let read = ParquetRecordBatchReaderBuilder::try_new(bytes)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let read = arrow_select::concat::concat_batches(&read[0].schema(), &read).unwrap();
+        let values = read
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap()
+            .values()
+            .to_vec();
+        assert_eq!(values, vec![9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        // rows are moved as a whole
+        let ids = read
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(ids.value(0), "id-9");
+        assert_eq!(ids.value(8), "id-1");
+
+        let stats = adds[0].get_stats().unwrap().unwrap();
+        assert_eq!(stats.num_records, 9);
+        assert_eq!(
+            stats.min_values["value"].as_value(),
+            Some(&serde_json::json!(1))
+        );
+        assert_eq!(
+            stats.max_values["value"].as_value(),
+            Some(&serde_json::json!(9))
+        );
+
+        // buffered rows exceeding the maximum are split into files sorted on their own
+        let config = WriterConfig::new(
+            schema.clone(),
+            vec![],
+            None,
+            None,
+            Some(2),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        )
+        .with_sort_columns(vec![("value".to_string(), true)])
+        .with_max_rows_per_file(4);
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        for batch in &batches {
+            writer.write(batch).await.unwrap();
+        }
+        let mut ranges = Vec::new();
+        for add in writer.close().await.unwrap() {
+            let stats = add.get_stats().unwrap().unwrap();
+            assert!(stats.num_records <= 4);
+            ranges.push((
+                stats.min_values["value"].as_value().cloned(),
+                stats.max_values["value"].as_value().cloned(),
+                stats.num_records,
+            ));
+        }
+        assert_eq!(ranges.iter().map(|(_, _, rows)| rows).sum::<i64>(), 9);
+        // the first five rows are sorted together, the remaining four on their own
+        assert_eq!(
+            ranges[0],
+            (Some(serde_json::json!(1)), Some(serde_json::json!(7)), 4)
+        );
+        assert_eq!(
+            ranges[1],
+            (Some(serde_json::json!(9)), Some(serde_json::json!(9)), 1)
+        );
+
+        // partition columns are not written to the files
+        let config =
+            partition_writer_config(schema).with_sort_columns(vec![("modified".to_string(), true)]);
+        assert!(PartitionWriter::try_with_config(
+            object_store,
+            config,
+            DEFAULT_NUM_INDEX_COLS,
+            None
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_uncompressed_size_in_tags() {
+        let object_store = memory_store();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![0; 100_000]))],
+        )
+        .unwrap();
+        // plain encoded zeros, which compress well
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_dictionary_enabled(false)
+            .build();
+        let config = |uncompressed_size_in_tags| {
+            WriterConfig::new(
+                schema.clone(),
+                vec![],
+                Some(properties.clone()),
+                None,
+                None,
+                DEFAULT_NUM_INDEX_COLS,
+                None,
+            )
+            .with_uncompressed_size_in_tags(uncompressed_size_in_tags)
+        };
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config(true));
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+        let uncompressed_size: i64 = adds[0].tags.as_ref().unwrap()[UNCOMPRESSED_SIZE_TAG]
+            .as_ref()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(uncompressed_size > adds[0].size);
+
+        let mut writer = DeltaWriter::new(object_store, config(false));
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert!(adds[0]
+            .tags
+            .as_ref()
+            .is_none_or(|tags| !tags.contains_key(UNCOMPRESSED_SIZE_TAG)));
+    }
+
+    #[tokio::test]
+    async fn test_row_group_stats_in_tags() {
+        use crate::protocol::Stats;
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::file::statistics::Statistics;
+
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(4)
+            .build();
+        let config = WriterConfig::new(
+            batch.schema(),
+            vec![],
+            Some(properties),
+            None,
+            Some(4),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        )
+        .with_row_group_stats_in_tags(true);
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(bytes).unwrap();
+        let row_groups = reader.metadata().row_groups();
+        assert!(row_groups.len() > 1);
+
+        let tag = adds[0].tags.as_ref().unwrap()[ROW_GROUP_STATS_TAG]
+            .as_ref()
+            .unwrap();
+        let row_group_stats: Vec<Stats> = serde_json::from_str(tag).unwrap();
+        assert_eq!(row_group_stats.len(), row_groups.len());
+        let value_idx = batch.schema().index_of("value").unwrap();
+        for (stats, row_group) in row_group_stats.iter().zip(row_groups) {
+            assert_eq!(stats.num_records, row_group.num_rows());
+            let Some(Statistics::Int32(expected)) = row_group.column(value_idx).statistics() else {
+                panic!("expected int32 statistics");
+            };
+            assert_eq!(
+                stats.min_values["value"].as_value(),
+                Some(&serde_json::json!(expected.min_opt().unwrap()))
+            );
+            assert_eq!(
+                stats.max_values["value"].as_value(),
+                Some(&serde_json::json!(expected.max_opt().unwrap()))
+            );
+        }
+        let num_records: i64 = row_group_stats.iter().map(|stats| stats.num_records).sum();
+        assert_eq!(num_records, batch.num_rows() as i64);
+
+        // disabled by default
+        let mut writer = get_delta_writer(object_store, &batch, None, None, None);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert!(adds.iter().all(|add| add.tags.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_close_with_metadata() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(4)
+            .build();
+        let mut writer =
+            get_partition_writer(object_store, &batch, Some(properties), Some(1), Some(5));
+        writer.write(&batch).await.unwrap();
+        let files = writer.close_with_metadata().await.unwrap();
+        assert!(files.len() > 1);
+
+        let num_rows: i64 = files.iter().map(|(_, metadata)| metadata.num_rows).sum();
+        assert_eq!(num_rows, batch.num_rows() as i64);
+        for (add, metadata) in &files {
+            let stats = add.get_stats().unwrap().unwrap();
+            assert_eq!(stats.num_records, metadata.num_rows);
+            let row_group_rows: i64 = metadata.row_groups.iter().map(|rg| rg.num_rows).sum();
+            assert_eq!(row_group_rows, metadata.num_rows);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_rows_per_file() {
+        let object_store = memory_store();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Int32,
+            true,
+        )]));
+        // constant values compress to a few bytes, far below the target file size
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![7; 500]))],
+        )
+        .unwrap();
+
+        let config = WriterConfig::new(
+            schema.clone(),
+            vec![],
+            None,
+            None,
+            Some(128),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        )
+        .with_max_rows_per_file(300);
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        let num_records = adds
+            .iter()
+            .map(|add| add.get_stats().unwrap().unwrap().num_records)
+            .collect::<Vec<_>>();
+        assert_eq!(num_records, vec![300, 300, 300, 100]);
+
+        let config = partition_writer_config(schema).with_max_rows_per_file(0);
+        assert!(PartitionWriter::try_with_config(
+            object_store,
+            config,
+            DEFAULT_NUM_INDEX_COLS,
+            None
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_abort() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = WriterConfig::new(
+            batch.schema(),
+            vec!["modified".to_string()],
+            None,
+            Some(1),
+            Some(2),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        );
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        writer.flush_all().await.unwrap();
+        // files of closed partitions and of partition values which need escaping are deleted too
+        let partition_values = IndexMap::from([(
+            "modified".to_string(),
+            Scalar::String("2021 02/03%".to_string()),
+        )]);
+        writer
+            .write_pre_partitioned(batch.clone(), &partition_values)
+            .await
+            .unwrap();
+        let closed = writer.flush_partition(&partition_values).await.unwrap();
+        assert!(!closed.is_empty());
+
+        writer.abort().await.unwrap();
+        assert!(list(object_store.as_ref(), None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_partition_target_size() {
+        let object_store = memory_store();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let ids = (0..20)
+            .map(|i| if i % 2 == 0 { "hot" } else { "cold" })
+            .collect::<Vec<_>>();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(ids)),
+                Arc::new(Int32Array::from((0..20).collect::<Vec<_>>())),
+            ],
+        )
+        .unwrap();
+        let config = WriterConfig::new(
+            schema,
+            vec!["id".to_string()],
+            None,
+            None,
+            Some(2),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        );
+
+        let mut writer = DeltaWriter::new(object_store, config).with_partition_target_size(
+            Arc::new(|partition_values: &IndexMap<String, Scalar>| {
+                (partition_values["id"] == Scalar::from("hot")).then_some(1)
+            }),
+        );
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        let num_files = |id: &str| {
+            adds.iter()
+                .filter(|add| add.partition_values["id"].as_deref() == Some(id))
+                .count()
+        };
+        // each chunk of two rows exceeds the tiny target of the hot partition
+        assert_eq!(num_files("hot"), 5);
+        assert_eq!(num_files("cold"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_pre_partitioned() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+            Field::new("modified", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["A", "B", "A"])),
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["2021-02-01"; 3])),
+            ],
+        )
+        .unwrap();
+        let partition_values =
+            IndexMap::from([("modified".to_string(), Scalar::from("2021-02-01"))]);
+        let writer = || {
+            let object_store = memory_store();
+            let config = writer_config(schema.clone(), &["modified"]).with_naming_seed(42);
+            DeltaWriter::new(object_store, config)
+        };
+
+        let mut divided = writer();
+        divided.write(&batch).await.unwrap();
+        let expected = divided.close().await.unwrap();
+
+        let mut pre_partitioned = writer();
+        pre_partitioned
+            .write_pre_partitioned(batch.clone(), &partition_values)
+            .await
+            .unwrap();
+        let adds = pre_partitioned.close().await.unwrap();
+
+        assert_eq!(adds.len(), 1);
+        assert_eq!(adds.len(), expected.len());
+        // only the modification times differ
+        assert_eq!(adds[0].path, expected[0].path);
+        assert_eq!(adds[0].size, expected[0].size);
+        assert_eq!(adds[0].partition_values, expected[0].partition_values);
+        assert_eq!(adds[0].stats, expected[0].stats);
+
+        // the partition values have to match the partition columns
+        let mut invalid = writer();
+        let result = invalid
+            .write_pre_partitioned(
+                batch.clone(),
+                &IndexMap::from([("id".to_string(), Scalar::from("A"))]),
+            )
+            .await;
+        assert!(result.is_err());
+        // as do the remaining columns
+        let result = invalid
+            .write_pre_partitioned(batch.project(&[0, 2]).unwrap(), &partition_values)
+            .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::SchemaMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_naming_seed() {
+        let batch = get_record_batch(None, false);
+        let write = |naming_seed: Option<u64>| {
+            let batch = batch.clone();
+            async move {
+                let object_store = memory_store();
+                let mut config = writer_config(batch.schema(), &["modified"]);
+                if let Some(naming_seed) = naming_seed {
+                    config = config.with_naming_seed(naming_seed);
+                }
+                let mut writer = DeltaWriter::new(object_store, config);
+                writer.write(&batch).await.unwrap();
+                let mut paths = writer
+                    .close()
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|add| add.path)
+                    .collect::<Vec<_>>();
+                paths.sort();
+                paths
+            }
+        };
+
+        let seeded = write(Some(42)).await;
+        assert!(seeded.len() > 1);
+        assert_eq!(write(Some(42)).await, seeded);
+        assert_ne!(write(Some(7)).await, seeded);
+        assert_ne!(write(None).await, write(None).await);
+    }
+
+    #[tokio::test]
+    async fn test_naming_seed_unique_across_writers() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &[]).with_naming_seed(42);
+
+        // reopening the writer of a partition must not reuse the names of its files
+        let mut writer = DeltaWriter::new(object_store, config);
+        writer.write(&batch).await.unwrap();
+        let first = writer.flush_partition(&IndexMap::new()).await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let second = writer.close().await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_ne!(first[0].path, second[0].path);
+    }
+
+    #[tokio::test]
+    async fn test_partition_flush_order() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &["modified"])
+            .with_partition_flush_order(PartitionFlushOrder::Descending);
+
+        let mut writer = DeltaWriter::new(object_store, config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert!(adds.len() > 1);
+        let paths = adds.iter().map(|add| add.path.clone()).collect::<Vec<_>>();
+        let mut expected = paths.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(paths, expected);
+    }
+
+    #[tokio::test]
+    async fn test_file_flushed_callback() {
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+
+        let flushed = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let callback_flushed = flushed.clone();
+        let mut writer = get_partition_writer(object_store, &batch, None, None, None)
+            .with_file_flushed_callback(Arc::new(move |add: &Add| {
+                callback_flushed.lock().push(add.path.clone())
+            }));
+        writer.write(&batch).await.unwrap();
+        assert!(flushed.lock().is_empty());
+
+        let adds = writer.close().await.unwrap();
+        let paths = adds.into_iter().map(|add| add.path).collect::<Vec<_>>();
+        assert_eq!(*flushed.lock(), paths);
+    }
+
+    #[tokio::test]
+    async fn test_write_compression() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = || writer_config(batch.schema(), &[]);
+
+        let mut writer = DeltaWriter::new(
+            object_store.clone(),
+            config().with_compression(CompressionCodec::Zstd, Some(7)),
+        );
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(bytes).unwrap();
+        for column in reader.metadata().row_group(0).columns() {
+            assert!(matches!(column.compression(), Compression::ZSTD(_)));
+        }
 
-        // collect metadata
-        let path = self.next_data_path();
-        let file_size = buffer.len() as i64;
+        for (codec, level) in [
+            (CompressionCodec::Zstd, 23),
+            (CompressionCodec::Gzip, -1),
+            (CompressionCodec::Snappy, 1),
+        ] {
+            let mut writer = DeltaWriter::new(
+                object_store.clone(),
+                config().with_compression(codec, Some(level)),
+            );
+            assert!(writer.write(&batch).await.is_err());
+        }
+    }
 
-        // write file to object store
-        let mut multi_part_upload = self.object_store.put_multipart(&path).await?;
-        let part_size = upload_part_size();
-        let mut tasks = JoinSet::new();
-        let max_concurrent_tasks = 10; // TODO: make configurable
+    #[tokio::test]
+    async fn test_writer_version() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
 
-        while buffer.len() > part_size {
-            let part = buffer.split_to(part_size);
-            let upload_future = multi_part_upload.put_part(part.into());
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
 
-            // wait until one spot frees up before spawning new task
-            if tasks.len() >= max_concurrent_tasks {
-                tasks.join_next().await;
+        for (writer_version, expected) in [
+            (None, 1),
+            (Some(WriterVersion::PARQUET_1_0), 1),
+            (Some(WriterVersion::PARQUET_2_0), 2),
+        ] {
+            let mut config = writer_config(batch.schema(), &[]);
+            if let Some(writer_version) = writer_version {
+                config = config.with_writer_version(writer_version);
             }
-            tasks.spawn(upload_future);
+            let mut writer = DeltaWriter::new(object_store.clone(), config);
+            writer.write(&batch).await.unwrap();
+            let adds = writer.close().await.unwrap();
+            assert_eq!(adds.len(), 1);
+            let bytes = object_store
+                .get(&Path::from(adds[0].path.clone()))
+                .await
+                .unwrap()
+                .bytes()
+                .await
+                .unwrap();
+            let reader = SerializedFileReader::new(bytes).unwrap();
+            assert_eq!(reader.metadata().file_metadata().version(), expected);
         }
+    }
 
-        if !buffer.is_empty() {
-            let upload_future = multi_part_upload.put_part(buffer.into());
-            tasks.spawn(upload_future);
-        }
+    #[tokio::test]
+    async fn test_column_encoding() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
 
-        // wait for all remaining tasks to complete
-        while let Some(result) = tasks.join_next().await {
-            result.map_err(|e| DeltaTableError::generic(e.to_string()))??;
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        // the column settings take precedence over the base writer properties
+        let writer_properties = WriterProperties::builder()
+            .set_dictionary_enabled(false)
+            .build();
+        let config = WriterConfig::new(
+            batch.schema(),
+            vec![],
+            Some(writer_properties),
+            None,
+            None,
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        )
+        .with_column_encoding(vec![
+            ("id".to_string(), Encoding::PLAIN, false),
+            ("modified".to_string(), Encoding::PLAIN, true),
+        ]);
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(bytes).unwrap();
+        let dictionary_encoded = |name: &str| {
+            let row_group = reader.metadata().row_group(0);
+            let column = row_group
+                .columns()
+                .iter()
+                .find(|column| column.column_path().string() == name)
+                .unwrap();
+            column.encodings().iter().any(|encoding| {
+                matches!(
+                    encoding,
+                    Encoding::PLAIN_DICTIONARY | Encoding::RLE_DICTIONARY
+                )
+            })
+        };
+        assert!(!dictionary_encoded("id"));
+        assert!(dictionary_encoded("modified"));
+
+        for encodings in [
+            vec![("missing".to_string(), Encoding::PLAIN, false)],
+            vec![("id".to_string(), Encoding::RLE_DICTIONARY, true)],
+        ] {
+            let config = writer_config(batch.schema(), &[]).with_column_encoding(encodings);
+            let mut writer = DeltaWriter::new(object_store.clone(), config);
+            assert!(writer.write(&batch).await.is_err());
         }
+    }
 
-        multi_part_upload.complete().await?;
+    #[tokio::test]
+    async fn test_parquet_kv_metadata() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
 
-        self.files_written.push(
-            create_add(
-                &self.config.partition_values,
-                path.to_string(),
-                file_size,
-                &metadata,
-                self.num_indexed_cols,
-                &self.stats_columns,
-            )
-            .map_err(|err| WriteError::CreateAdd {
-                source: Box::new(err),
-            })?,
-        );
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = writer_config(batch.schema(), &[]).with_parquet_kv_metadata(vec![
+            ("source_batch".to_string(), Some("42".to_string())),
+            ("reprocessed".to_string(), None),
+        ]);
 
-        Ok(())
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(bytes).unwrap();
+        let kv_metadata = reader
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .unwrap();
+        assert!(kv_metadata.contains(&KeyValue::new(
+            "source_batch".to_string(),
+            Some("42".to_string())
+        )));
+        assert!(kv_metadata.contains(&KeyValue::new("reprocessed".to_string(), None)));
     }
 
-    /// Buffers record batches in-memory up to appx. `target_file_size`.
-    /// Flushes data to storage once a full file can be written.
-    ///
-    /// The `close` method has to be invoked to write all data still buffered
-    /// and get the list of all written files.
-    pub async fn write(&mut self, batch: &RecordBatch) -> DeltaResult<()> {
-        if batch.schema() != self.config.file_schema {
-            return Err(WriteError::SchemaMismatch {
-                schema: batch.schema(),
-                expected_schema: self.config.file_schema.clone(),
-            }
-            .into());
-        }
+    #[tokio::test]
+    async fn test_bloom_filter_columns() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
 
-        let max_offset = batch.num_rows();
-        for offset in (0..max_offset).step_by(self.config.write_batch_size) {
-            let length = usize::min(self.config.write_batch_size, max_offset - offset);
-            self.write_batch(&batch.slice(offset, length)).await?;
-            // flush currently buffered data to disk once we meet or exceed the target file size.
-            let estimated_size = self.buffer.len().await + self.arrow_writer.in_progress_size();
-            if estimated_size >= self.config.target_file_size {
-                debug!("Writing file with estimated size {estimated_size:?} to disk.");
-                self.flush_arrow_writer().await?;
-            }
-        }
+        let object_store = memory_store();
+        let batch = get_record_batch(None, false);
+        let config = |columns: &[&str], options| {
+            writer_config(batch.schema(), &[])
+                .with_bloom_filter_columns(columns.iter().copied(), options)
+        };
 
-        Ok(())
-    }
+        let options = BloomFilterOptions {
+            fpp: Some(0.01),
+            ndv: Some(100),
+        };
+        let mut writer = DeltaWriter::new(object_store.clone(), config(&["id"], options));
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
 
-    /// Close the writer and get the new [Add] actions.
-    pub async fn close(mut self) -> DeltaResult<Vec<Add>> {
-        self.flush_arrow_writer().await?;
-        Ok(self.files_written)
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(bytes).unwrap();
+        let row_group = reader.metadata().row_group(0);
+        for column in row_group.columns() {
+            assert_eq!(
+                column.bloom_filter_offset().is_some(),
+                column.column_path().string() == "id"
+            );
+        }
+
+        for (columns, options) in [
+            (["missing"], BloomFilterOptions::default()),
+            (
+                ["id"],
+                BloomFilterOptions {
+                    fpp: Some(1.5),
+                    ndv: None,
+                },
+            ),
+        ] {
+            let mut writer = DeltaWriter::new(object_store.clone(), config(&columns, options));
+            assert!(writer.write(&batch).await.is_err());
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::logstore::tests::flatten_list_stream as list;
-    use crate::table::config::DEFAULT_NUM_INDEX_COLS;
-    use crate::writer::test_utils::*;
-    use crate::DeltaTableBuilder;
-    use arrow::array::{Int32Array, StringArray};
-    use arrow::datatypes::{DataType, Field, Schema as ArrowSchema};
-    use std::sync::Arc;
+    #[tokio::test]
+    async fn test_stats_columns_statistics() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
 
-    fn get_delta_writer(
-        object_store: ObjectStoreRef,
-        batch: &RecordBatch,
-        writer_properties: Option<WriterProperties>,
-        target_file_size: Option<usize>,
-        write_batch_size: Option<usize>,
-    ) -> DeltaWriter {
+        let object_store = memory_store();
+        // a nested column with the name of a stats column is not collected
+        let nested = Field::new("col_3", DataType::Int32, true);
+        let schema = Arc::new(ArrowSchema::new(
+            (0..50)
+                .map(|i| Field::new(format!("col_{i}"), DataType::Int32, true))
+                .chain([Field::new(
+                    "nested",
+                    DataType::Struct(vec![nested.clone()].into()),
+                    true,
+                )])
+                .collect::<Vec<_>>(),
+        ));
+        let columns = (0..50)
+            .map(|i| Arc::new(Int32Array::from_iter_values(i..i + 10)) as arrow::array::ArrayRef)
+            .chain([Arc::new(arrow::array::StructArray::from(vec![(
+                Arc::new(nested),
+                Arc::new(Int32Array::from_iter_values(0..10)) as arrow::array::ArrayRef,
+            )])) as arrow::array::ArrayRef])
+            .collect();
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
         let config = WriterConfig::new(
-            batch.schema(),
+            schema,
             vec![],
-            writer_properties,
-            target_file_size,
-            write_batch_size,
-            DEFAULT_NUM_INDEX_COLS,
             None,
+            None,
+            None,
+            DEFAULT_NUM_INDEX_COLS,
+            Some(vec!["col_3".to_string(), "col_42".to_string()]),
         );
-        DeltaWriter::new(object_store, config)
+
+        let mut writer = DeltaWriter::new(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+        assert_eq!(adds.len(), 1);
+
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(bytes).unwrap();
+        let with_statistics = reader
+            .metadata()
+            .row_group(0)
+            .columns()
+            .iter()
+            .filter(|column| column.statistics().is_some())
+            .map(|column| column.column_path().string())
+            .collect::<Vec<_>>();
+        assert_eq!(with_statistics, vec!["col_3", "col_42"]);
+
+        let stats = adds[0].get_stats().unwrap().unwrap();
+        let mut stats_columns = stats.min_values.keys().cloned().collect::<Vec<_>>();
+        stats_columns.sort();
+        assert_eq!(stats_columns, vec!["col_3", "col_42"]);
     }
 
-    fn get_partition_writer(
-        object_store: ObjectStoreRef,
-        batch: &RecordBatch,
-        writer_properties: Option<WriterProperties>,
-        target_file_size: Option<usize>,
-        write_batch_size: Option<usize>,
-    ) -> PartitionWriter {
-        let config = PartitionWriterConfig::try_new(
-            batch.schema(),
-            IndexMap::new(),
-            None,
-            writer_properties,
-            target_file_size,
-            write_batch_size,
+    #[tokio::test]
+    async fn test_write_field_ids() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let object_store = memory_store();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true).with_metadata(HashMap::from([(
+                "delta.columnMapping.id".to_string(),
+                "5".to_string(),
+            )])),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["A", "B"])),
+                Arc::new(Int32Array::from(vec![1, 2])),
+            ],
         )
         .unwrap();
-        PartitionWriter::try_with_config(object_store, config, DEFAULT_NUM_INDEX_COLS, None)
+
+        let config = partition_writer_config(batch.schema()).with_field_ids(true);
+        let mut writer = partition_writer(object_store.clone(), config);
+        writer.write(&batch).await.unwrap();
+        let adds = writer.close().await.unwrap();
+
+        let bytes = object_store
+            .get(&Path::from(adds[0].path.clone()))
+            .await
             .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = SerializedFileReader::new(bytes).unwrap();
+        let ids = reader
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .root_schema()
+            .get_fields()
+            .iter()
+            .map(|f| f.get_basic_info().id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![5, 6]);
     }
 
     #[tokio::test]
-    async fn test_write_partition() {
-        let log_store = DeltaTableBuilder::from_uri("memory:///")
-            .build_storage()
-            .unwrap();
-        let object_store = log_store.object_store(None);
+    async fn test_write_batch_size_exceeding_row_group_size() {
+        let object_store = memory_store();
         let batch = get_record_batch(None, false);
+        let config = || {
+            let properties = WriterProperties::builder()
+                .set_max_row_group_size(4)
+                .build();
+            PartitionWriterConfig::try_new(
+                batch.schema(),
+                IndexMap::new(),
+                None,
+                Some(properties),
+                None,
+                Some(8),
+            )
+            .unwrap()
+        };
 
-        // write single un-partitioned batch
-        let mut writer = get_partition_writer(object_store.clone(), &batch, None, None, None);
+        let result = PartitionWriter::try_with_config(
+            object_store.clone(),
+            config().with_write_batch_size_behavior(WriteBatchSizeBehavior::Error),
+            DEFAULT_NUM_INDEX_COLS,
+            None,
+        );
+        let Err(err) = result else {
+            panic!("expected the mismatched sizes to be rejected");
+        };
+        assert!(err
+            .to_string()
+            .contains("write_batch_size of 8 rows exceeds the max_row_group_size of 4 rows"));
+
+        // by default the batch size is clamped to the row group size
+        let mut writer = partition_writer(object_store, config());
+        assert_eq!(writer.config.write_batch_size, 4);
         writer.write(&batch).await.unwrap();
-        let files = list(object_store.as_ref(), None).await.unwrap();
-        assert_eq!(files.len(), 0);
-        let adds = writer.close().await.unwrap();
-        let files = list(object_store.as_ref(), None).await.unwrap();
-        assert_eq!(files.len(), 1);
-        assert_eq!(files.len(), adds.len());
-        let head = object_store
-            .head(&Path::from(adds[0].path.clone()))
-            .await
-            .unwrap();
-        assert_eq!(head.size, adds[0].size as u64)
+        let files = writer.close_with_metadata().await.unwrap();
+        for (_, metadata) in &files {
+            assert!(metadata.row_groups.iter().all(|rg| rg.num_rows <= 4));
+        }
     }
 
     #[tokio::test]
-    async fn test_write_partition_with_parts() {
-        let base_int = Arc::new(Int32Array::from((0..10000).collect::<Vec<i32>>()));
+    async fn test_split_oversized_chunks() {
+        let base_int = Arc::new(Int32Array::from((0..10000_i32).collect::<Vec<i32>>()));
         let base_str = Arc::new(StringArray::from(vec!["A"; 10000]));
         let schema = Arc::new(ArrowSchema::new(vec![
             Field::new("id", DataType::Utf8, true),
@@ -574,30 +4722,33 @@ mod tests {
         ]));
         let batch = RecordBatch::try_new(schema, vec![base_str, base_int]).unwrap();
 
-        let object_store = DeltaTableBuilder::from_uri("memory:///")
-            .build_storage()
-            .unwrap()
-            .object_store(None);
-        let properties = WriterProperties::builder()
-            .set_max_row_group_size(1024)
-            .build();
-        // configure small target file size and and row group size so we can observe multiple files written
-        let mut writer =
-            get_partition_writer(object_store, &batch, Some(properties), Some(10_000), None);
+        let object_store = memory_store();
+        // a single chunk of 10000 rows would exceed the tiny target file size by far
+        let config = PartitionWriterConfig::try_new(
+            batch.schema(),
+            IndexMap::new(),
+            None,
+            None,
+            Some(9000),
+            Some(10000),
+        )
+        .unwrap()
+        .with_oversized_chunk_behavior(OversizedChunkBehavior::Split);
+        let mut writer = partition_writer(object_store, config);
         writer.write(&batch).await.unwrap();
 
-        // check that we have written more then once file, and no more then 1 is below target size
         let adds = writer.close().await.unwrap();
         assert!(adds.len() > 1);
-        let target_file_count = adds
+        let num_rows = adds
             .iter()
-            .fold(0, |acc, add| acc + (add.size > 10_000) as i32);
-        assert!(target_file_count >= adds.len() as i32 - 1)
+            .map(|add| add.get_stats().unwrap().unwrap().num_records)
+            .sum::<i64>();
+        assert_eq!(num_rows, 10000);
     }
 
     #[tokio::test]
-    async fn test_unflushed_row_group_size() {
-        let base_int = Arc::new(Int32Array::from((0..10000).collect::<Vec<i32>>()));
+    async fn test_max_file_size() {
+        let base_int = Arc::new(Int32Array::from((0..10000_i32).collect::<Vec<i32>>()));
         let base_str = Arc::new(StringArray::from(vec!["A"; 10000]));
         let schema = Arc::new(ArrowSchema::new(vec![
             Field::new("id", DataType::Utf8, true),
@@ -605,44 +4756,61 @@ mod tests {
         ]));
         let batch = RecordBatch::try_new(schema, vec![base_str, base_int]).unwrap();
 
-        let object_store = DeltaTableBuilder::from_uri("memory:///")
-            .build_storage()
-            .unwrap()
-            .object_store(None);
-        // configure small target file size so we can observe multiple files written
-        let mut writer = get_partition_writer(object_store, &batch, None, Some(10_000), None);
+        let object_store = memory_store();
+        let config = PartitionWriterConfig::try_new(
+            batch.schema(),
+            IndexMap::new(),
+            None,
+            None,
+            None,
+            Some(10000),
+        )
+        .unwrap()
+        .with_max_file_size(20_000);
+        let mut writer = partition_writer(object_store, config);
         writer.write(&batch).await.unwrap();
 
-        // check that we have written more then once file, and no more then 1 is below target size
         let adds = writer.close().await.unwrap();
         assert!(adds.len() > 1);
-        let target_file_count = adds
-            .iter()
-            .fold(0, |acc, add| acc + (add.size > 10_000) as i32);
-        assert!(target_file_count >= adds.len() as i32 - 1)
+        assert!(adds.iter().all(|add| add.size <= 20_000));
     }
 
     #[tokio::test]
-    async fn test_do_not_write_empty_file_on_close() {
-        let base_int = Arc::new(Int32Array::from((0..10000_i32).collect::<Vec<i32>>()));
-        let base_str = Arc::new(StringArray::from(vec!["A"; 10000]));
+    async fn test_max_file_size_of_large_batch() {
+        // values which neither compress nor dictionary encode well, so files fill up
         let schema = Arc::new(ArrowSchema::new(vec![
-            Field::new("id", DataType::Utf8, true),
-            Field::new("value", DataType::Int32, true),
+            Field::new("id", DataType::Utf8, false),
+            Field::new("value", DataType::Int64, false),
         ]));
-        let batch = RecordBatch::try_new(schema, vec![base_str, base_int]).unwrap();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    (0..10_000_i64).map(|i| format!("{:x}", i.wrapping_mul(2_654_435_761))),
+                )),
+                Arc::new(Int64Array::from_iter_values(
+                    (0..10_000_i64).map(|i| i.wrapping_mul(6_364_136_223_846_793_005)),
+                )),
+            ],
+        )
+        .unwrap();
+        let max_file_size = 50_000;
 
-        let object_store = DeltaTableBuilder::from_uri("memory:///")
-            .build_storage()
-            .unwrap()
-            .object_store(None);
-        // configure high batch size and low file size to observe one file written and flushed immediately
-        // upon writing batch, then ensures the buffer is empty upon closing writer
-        let mut writer = get_partition_writer(object_store, &batch, None, Some(9000), Some(10000));
+        let object_store = memory_store();
+        let config = partition_writer_config(batch.schema()).with_max_file_size(max_file_size);
+        let mut writer = partition_writer(object_store, config);
         writer.write(&batch).await.unwrap();
 
+        // the chunks are sized by the rows they hold, not by the buffers of the whole batch,
+        // so files hold several chunks instead of one each
         let adds = writer.close().await.unwrap();
-        assert!(adds.len() == 1);
+        let num_chunks = 10_000usize.div_ceil(DEFAULT_WRITE_BATCH_SIZE);
+        assert!(adds.len() > 1);
+        assert!(adds.len() < num_chunks);
+        assert!(adds.iter().all(|add| add.size as usize <= max_file_size));
+        assert!(adds[..adds.len() - 1]
+            .iter()
+            .all(|add| add.size as usize > max_file_size / 4));
     }
 
     #[tokio::test]
@@ -693,4 +4861,40 @@ mod tests {
             }
         };
     }
+
+    #[test]
+    fn test_validate_against_metadata() {
+        let partition_cols = vec!["modified".to_string()];
+        let metadata = get_delta_metadata(&partition_cols);
+        let config = |partition_columns: Vec<String>, stats_columns: Option<Vec<String>>| {
+            WriterConfig::new(
+                get_arrow_schema(&None),
+                partition_columns,
+                None,
+                None,
+                None,
+                DEFAULT_NUM_INDEX_COLS,
+                stats_columns,
+            )
+        };
+
+        config(partition_cols.clone(), Some(vec!["value".to_string()]))
+            .validate_against(&metadata)
+            .unwrap();
+
+        let result = config(vec!["id".to_string()], None).validate_against(&metadata);
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::SchemaMismatch { .. })
+        ));
+        let result = config(vec![], None).validate_against(&metadata);
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::SchemaMismatch { .. })
+        ));
+
+        let result =
+            config(partition_cols, Some(vec!["missing".to_string()])).validate_against(&metadata);
+        assert!(matches!(result, Err(DeltaTableError::Generic(_))));
+    }
 }
@@ -569,6 +569,22 @@ impl DeltaOperation {
             _ => false,
         }
     }
+
+    /// Denotes if the operation is a blind append, i.e. it only adds data without reading
+    /// the table, so it commutes with the data changes of concurrent commits.
+    ///
+    /// This holds for appending writes without a predicate and for streaming updates in
+    /// append mode. A commit of such an operation is only a blind append as long as its
+    /// actions do not remove files.
+    pub fn is_blind_append(&self) -> bool {
+        match self {
+            Self::Write {
+                mode, predicate, ..
+            } => *mode == SaveMode::Append && predicate.is_none(),
+            Self::StreamingUpdate { output_mode, .. } => matches!(output_mode, OutputMode::Append),
+            _ => false,
+        }
+    }
 }
 
 /// The SaveMode used when performing a DeltaOperation
@@ -691,6 +707,42 @@ mod tests {
     use super::*;
     use crate::kernel::Action;
 
+    #[test]
+    fn test_is_blind_append() {
+        let write = |mode, predicate: Option<&str>| DeltaOperation::Write {
+            mode,
+            partition_by: None,
+            predicate: predicate.map(String::from),
+        };
+        assert!(write(SaveMode::Append, None).is_blind_append());
+        assert!(!write(SaveMode::Append, Some("value > 1")).is_blind_append());
+        assert!(!write(SaveMode::Overwrite, None).is_blind_append());
+        assert!(!write(SaveMode::ErrorIfExists, None).is_blind_append());
+
+        assert!(!DeltaOperation::Delete { predicate: None }.is_blind_append());
+        assert!(!DeltaOperation::Delete {
+            predicate: Some("value > 1".into())
+        }
+        .is_blind_append());
+
+        let merge = DeltaOperation::Merge {
+            predicate: None,
+            merge_predicate: Some("source.id = target.id".into()),
+            matched_predicates: vec![],
+            not_matched_predicates: vec![],
+            not_matched_by_source_predicates: vec![],
+        };
+        assert!(!merge.is_blind_append());
+
+        let streaming_update = |output_mode| DeltaOperation::StreamingUpdate {
+            output_mode,
+            query_id: "query".into(),
+            epoch_id: 0,
+        };
+        assert!(streaming_update(OutputMode::Append).is_blind_append());
+        assert!(!streaming_update(OutputMode::Complete).is_blind_append());
+    }
+
     #[test]
     fn test_load_table_stats() {
         let action = Add {
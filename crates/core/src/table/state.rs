@@ -182,6 +182,23 @@ impl DeltaTableState {
         self.snapshot.files_count()
     }
 
+    /// Fill in missing partition values and sizes of remove actions from the active files
+    /// they remove, see [EagerSnapshot::complete_removes]
+    pub fn complete_removes(&self, removes: &mut [Remove]) -> Vec<String> {
+        self.snapshot.complete_removes(removes)
+    }
+
+    /// Remove actions for all active files, used to overwrite or truncate the table
+    pub(crate) fn remove_all_files(&self) -> Vec<Remove> {
+        let mut removes: Vec<Remove> = self
+            .log_data()
+            .into_iter()
+            .map(|file| file.remove_action(true))
+            .collect();
+        self.complete_removes(&mut removes);
+        removes
+    }
+
     /// Full list of all of the CDC files added as part of the changeDataFeed feature
     pub fn cdc_files(&self) -> DeltaResult<impl Iterator<Item = AddCDCFile> + '_> {
         self.snapshot.cdc_files()
@@ -1,5 +1,7 @@
 mod factories;
+mod stores;
 
 pub use factories::*;
+pub use stores::*;
 
 pub type TestResult<T = ()> = Result<T, Box<dyn std::error::Error + 'static>>;
@@ -0,0 +1,494 @@
+//! Log and object stores misbehaving in configurable ways, and a table to commit to
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::memory::InMemory;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, UploadPart,
+};
+use uuid::Uuid;
+
+use crate::kernel::transaction::{CommitData, TransactionError};
+use crate::kernel::{DataType, PrimitiveType};
+use crate::logstore::{CommitOrBytes, LogStore, LogStoreConfig, LogStoreRef, ObjectStoreRef};
+use crate::protocol::{DeltaOperation, SaveMode};
+use crate::{DeltaOps, DeltaResult, DeltaTable};
+
+/// Create an empty in-memory table with a single nullable integer column `value`
+pub async fn create_test_table() -> DeltaTable {
+    DeltaOps::new_in_memory()
+        .create()
+        .with_column(
+            "value",
+            DataType::Primitive(PrimitiveType::Integer),
+            true,
+            None,
+        )
+        .await
+        .unwrap()
+}
+
+/// Operation of an unpartitioned blind append
+pub fn append_operation() -> DeltaOperation {
+    DeltaOperation::Write {
+        mode: SaveMode::Append,
+        partition_by: None,
+        predicate: None,
+    }
+}
+
+/// Decrement a counter of remaining failures, returns whether a failure was left
+fn take_one(counter: &AtomicUsize) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+/// Log store wrapping the log store of a test table, which can be configured to lose races
+/// for table versions, reject commits or respond slowly.
+///
+/// Commits are staged as temporary files unless conditional puts are enabled with
+/// [`with_conditional_put`](Self::with_conditional_put).
+#[derive(Debug)]
+pub struct TestLogStore {
+    inner: LogStoreRef,
+    conditional_put: bool,
+    /// Reject every commit entry
+    rejecting: bool,
+    /// Number of attempts still to fail as if another writer won, without writing anything
+    lost_races: AtomicUsize,
+    /// Number of attempts a competing writer still wins by committing first
+    races: AtomicUsize,
+    /// Number of written commit entries still to be reported as failed
+    lost_acks: AtomicUsize,
+    /// Time to wait after writing a commit entry before returning
+    write_delay: Duration,
+    /// Time reading a commit entry takes
+    read_delay: Duration,
+    attempts: parking_lot::Mutex<Vec<Instant>>,
+    in_flight_reads: AtomicUsize,
+    max_in_flight_reads: AtomicUsize,
+}
+
+impl TestLogStore {
+    /// Wrap the given log store without changing its behavior
+    pub fn new(inner: LogStoreRef) -> Self {
+        Self {
+            inner,
+            conditional_put: false,
+            rejecting: false,
+            lost_races: AtomicUsize::new(0),
+            races: AtomicUsize::new(0),
+            lost_acks: AtomicUsize::new(0),
+            write_delay: Duration::ZERO,
+            read_delay: Duration::ZERO,
+            attempts: Default::default(),
+            in_flight_reads: AtomicUsize::new(0),
+            max_in_flight_reads: AtomicUsize::new(0),
+        }
+    }
+
+    /// Advertise conditional puts, so commits are passed as bytes instead of temporary files
+    pub fn with_conditional_put(mut self, conditional_put: bool) -> Self {
+        self.conditional_put = conditional_put;
+        self
+    }
+
+    /// Reject every commit with a [`TransactionError::LogStoreError`]
+    pub fn rejecting(mut self) -> Self {
+        self.rejecting = true;
+        self
+    }
+
+    /// Lose every race for the next table version, without another commit being written
+    pub fn losing_every_race(self) -> Self {
+        self.lost_races.store(usize::MAX, Ordering::SeqCst);
+        self
+    }
+
+    /// Let a competing writer win the first `races` commit attempts
+    pub fn with_races(self, races: usize) -> Self {
+        self.races.store(races, Ordering::SeqCst);
+        self
+    }
+
+    /// Report the next `lost_acks` commits as failed after their log entry was written
+    pub fn with_lost_acks(self, lost_acks: usize) -> Self {
+        self.lost_acks.store(lost_acks, Ordering::SeqCst);
+        self
+    }
+
+    /// Only return from writing a commit entry `delay` after it was written
+    pub fn with_write_delay(mut self, delay: Duration) -> Self {
+        self.write_delay = delay;
+        self
+    }
+
+    /// Take `delay` to read a commit entry
+    pub fn with_read_delay(mut self, delay: Duration) -> Self {
+        self.read_delay = delay;
+        self
+    }
+
+    /// Start times of all attempts to write a commit entry so far
+    pub fn attempts(&self) -> Vec<Instant> {
+        self.attempts.lock().clone()
+    }
+
+    /// Forget the attempts recorded so far
+    pub fn clear_attempts(&self) {
+        self.attempts.lock().clear();
+    }
+
+    /// Maximum number of commit entries read concurrently so far
+    pub fn max_in_flight_reads(&self) -> usize {
+        self.max_in_flight_reads.load(Ordering::SeqCst)
+    }
+
+    async fn write_competing_commit(&self, version: i64) -> Result<(), TransactionError> {
+        let competing = CommitData::new(vec![], append_operation(), Default::default(), vec![]);
+        self.inner
+            .write_commit_entry(
+                version,
+                CommitOrBytes::LogBytes(competing.get_bytes()?),
+                Uuid::new_v4(),
+            )
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl LogStore for TestLogStore {
+    fn name(&self) -> String {
+        "TestLogStore".into()
+    }
+
+    fn supports_conditional_put(&self) -> bool {
+        self.conditional_put
+    }
+
+    async fn read_commit_entry(&self, version: i64) -> DeltaResult<Option<Bytes>> {
+        let in_flight = self.in_flight_reads.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight_reads
+            .fetch_max(in_flight, Ordering::SeqCst);
+        if !self.read_delay.is_zero() {
+            tokio::time::sleep(self.read_delay).await;
+        }
+        self.in_flight_reads.fetch_sub(1, Ordering::SeqCst);
+        self.inner.read_commit_entry(version).await
+    }
+
+    async fn write_commit_entry(
+        &self,
+        version: i64,
+        commit_or_bytes: CommitOrBytes,
+        operation_id: Uuid,
+    ) -> Result<(), TransactionError> {
+        self.attempts.lock().push(Instant::now());
+        if self.rejecting {
+            return Err(TransactionError::LogStoreError {
+                msg: "commit rejected".into(),
+                source: "rejected".into(),
+            });
+        }
+        if take_one(&self.lost_races) {
+            return Err(TransactionError::VersionAlreadyExists(version));
+        }
+        if take_one(&self.races) {
+            self.write_competing_commit(version).await?;
+            return Err(TransactionError::VersionAlreadyExists(version));
+        }
+        match commit_or_bytes {
+            CommitOrBytes::TmpCommit(tmp_commit) => {
+                crate::logstore::write_commit_entry(
+                    self.object_store(None).as_ref(),
+                    version,
+                    &tmp_commit,
+                )
+                .await?
+            }
+            commit_or_bytes => {
+                self.inner
+                    .write_commit_entry(version, commit_or_bytes, operation_id)
+                    .await?
+            }
+        }
+        if !self.write_delay.is_zero() {
+            tokio::time::sleep(self.write_delay).await;
+        }
+        if take_one(&self.lost_acks) {
+            return Err(TransactionError::ObjectStore {
+                source: object_store::Error::Generic {
+                    store: "TestLogStore",
+                    source: "connection reset".into(),
+                },
+            });
+        }
+        Ok(())
+    }
+
+    async fn abort_commit_entry(
+        &self,
+        version: i64,
+        commit_or_bytes: CommitOrBytes,
+        operation_id: Uuid,
+    ) -> Result<(), TransactionError> {
+        match commit_or_bytes {
+            CommitOrBytes::TmpCommit(tmp_commit) => {
+                crate::logstore::abort_commit_entry(
+                    self.object_store(None).as_ref(),
+                    version,
+                    &tmp_commit,
+                )
+                .await
+            }
+            commit_or_bytes => {
+                self.inner
+                    .abort_commit_entry(version, commit_or_bytes, operation_id)
+                    .await
+            }
+        }
+    }
+
+    async fn get_latest_version(&self, start_version: i64) -> DeltaResult<i64> {
+        self.inner.get_latest_version(start_version).await
+    }
+
+    async fn get_earliest_version(&self, start_version: i64) -> DeltaResult<i64> {
+        self.inner.get_earliest_version(start_version).await
+    }
+
+    fn object_store(&self, operation_id: Option<Uuid>) -> Arc<dyn ObjectStore> {
+        self.inner.object_store(operation_id)
+    }
+
+    fn config(&self) -> &LogStoreConfig {
+        self.inner.config()
+    }
+}
+
+/// In-memory object store counting writes, which can be configured to fail writes to
+/// matching paths or individual upload parts.
+#[derive(Debug)]
+pub struct TestObjectStore {
+    inner: ObjectStoreRef,
+    /// Writes to paths containing this pattern fail while `failing_writes` is positive
+    failing_pattern: String,
+    /// Number of writes to matching paths still to fail
+    failing_writes: AtomicUsize,
+    /// Number of copies still to fail
+    failing_copies: AtomicUsize,
+    counters: Arc<UploadCounters>,
+}
+
+/// Counters shared by a [TestObjectStore] and its multipart uploads
+#[derive(Debug, Default)]
+struct UploadCounters {
+    puts: AtomicUsize,
+    uploads: AtomicUsize,
+    parts: AtomicUsize,
+    in_flight_parts: AtomicUsize,
+    max_in_flight_parts: AtomicUsize,
+    /// Number of parts still to fail with a transient error
+    failing_parts: AtomicUsize,
+}
+
+impl Default for TestObjectStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestObjectStore {
+    /// Create an empty store which does not fail any writes
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(InMemory::new()),
+            failing_pattern: String::new(),
+            failing_writes: AtomicUsize::new(0),
+            failing_copies: AtomicUsize::new(0),
+            counters: Default::default(),
+        }
+    }
+
+    /// Fail the next `failures` writes to paths containing `pattern`
+    pub fn with_failing_writes(mut self, pattern: impl Into<String>, failures: usize) -> Self {
+        self.failing_pattern = pattern.into();
+        self.failing_writes.store(failures, Ordering::SeqCst);
+        self
+    }
+
+    /// Fail the next `failures` copies of files, including the copies done to rename them
+    pub fn with_failing_copies(self, failures: usize) -> Self {
+        self.failing_copies.store(failures, Ordering::SeqCst);
+        self
+    }
+
+    /// Fail the next `failures` upload parts with a transient error
+    pub fn with_failing_parts(self, failures: usize) -> Self {
+        self.counters
+            .failing_parts
+            .store(failures, Ordering::SeqCst);
+        self
+    }
+
+    /// Number of writes to matching paths still to fail
+    pub fn failing_writes(&self) -> usize {
+        self.failing_writes.load(Ordering::SeqCst)
+    }
+
+    /// Fail the next `failures` writes to paths matching the configured pattern
+    pub fn set_failing_writes(&self, failures: usize) {
+        self.failing_writes.store(failures, Ordering::SeqCst);
+    }
+
+    /// Number of files written with a single put
+    pub fn num_puts(&self) -> usize {
+        self.counters.puts.load(Ordering::SeqCst)
+    }
+
+    /// Number of multipart uploads started
+    pub fn num_uploads(&self) -> usize {
+        self.counters.uploads.load(Ordering::SeqCst)
+    }
+
+    /// Number of upload parts written successfully or still in progress
+    pub fn num_parts(&self) -> usize {
+        self.counters.parts.load(Ordering::SeqCst)
+    }
+
+    /// Maximum number of parts uploaded concurrently so far
+    pub fn max_in_flight_parts(&self) -> usize {
+        self.counters.max_in_flight_parts.load(Ordering::SeqCst)
+    }
+
+    fn check(&self, location: &Path) -> object_store::Result<()> {
+        if location.as_ref().contains(&self.failing_pattern) && take_one(&self.failing_writes) {
+            return Err(object_store::Error::Generic {
+                store: "TestObjectStore",
+                source: format!("writing {location} is not allowed").into(),
+            });
+        }
+        Ok(())
+    }
+
+    fn check_copy(&self, to: &Path) -> object_store::Result<()> {
+        if take_one(&self.failing_copies) {
+            return Err(object_store::Error::Generic {
+                store: "TestObjectStore",
+                source: format!("copying to {to} is not allowed").into(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for TestObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TestObjectStore({})", self.inner)
+    }
+}
+
+#[derive(Debug)]
+struct TestUpload {
+    inner: Box<dyn MultipartUpload>,
+    counters: Arc<UploadCounters>,
+}
+
+#[async_trait::async_trait]
+impl MultipartUpload for TestUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        if take_one(&self.counters.failing_parts) {
+            return Box::pin(async {
+                Err(object_store::Error::Generic {
+                    store: "TestObjectStore",
+                    source: Box::new(std::io::Error::from(std::io::ErrorKind::ConnectionReset)),
+                })
+            });
+        }
+        let upload = self.inner.put_part(data);
+        let counters = self.counters.clone();
+        counters.parts.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            let current = counters.in_flight_parts.fetch_add(1, Ordering::SeqCst) + 1;
+            counters
+                .max_in_flight_parts
+                .fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let result = upload.await;
+            counters.in_flight_parts.fetch_sub(1, Ordering::SeqCst);
+            result
+        })
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        self.inner.complete().await
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.inner.abort().await
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for TestObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.check(location)?;
+        self.counters.puts.fetch_add(1, Ordering::SeqCst);
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.check(location)?;
+        self.counters.uploads.fetch_add(1, Ordering::SeqCst);
+        Ok(Box::new(TestUpload {
+            inner: self.inner.put_multipart_opts(location, opts).await?,
+            counters: self.counters.clone(),
+        }))
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.check_copy(to)?;
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.check_copy(to)?;
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
@@ -174,6 +174,34 @@ fn stats_from_file_metadata(
     )
 }
 
+/// Statistics of every row group of a file, in the same format as the file statistics
+pub(crate) fn row_group_stats_from_file_metadata(
+    partition_values: &IndexMap<String, Scalar>,
+    file_metadata: &FileMetaData,
+    num_indexed_cols: i32,
+    stats_columns: &Option<Vec<impl AsRef<str>>>,
+) -> Result<Vec<Stats>, DeltaWriterError> {
+    let type_ptr = parquet::schema::types::from_thrift(file_metadata.schema.as_slice());
+    let schema_descriptor = type_ptr.map(|type_| Arc::new(SchemaDescriptor::new(type_)))?;
+
+    file_metadata
+        .row_groups
+        .iter()
+        .map(|rg| {
+            let row_group = RowGroupMetaData::from_thrift(schema_descriptor.clone(), rg.clone())?;
+            let num_rows = row_group.num_rows();
+            stats_from_metadata(
+                partition_values,
+                schema_descriptor.clone(),
+                vec![row_group],
+                num_rows,
+                num_indexed_cols,
+                stats_columns,
+            )
+        })
+        .collect()
+}
+
 fn stats_from_metadata(
     partition_values: &IndexMap<String, Scalar>,
     schema_descriptor: Arc<SchemaDescriptor>,
@@ -155,7 +155,7 @@ async fn test_merge_concurrent_conflict() {
         DeltaTableError::Transaction { .. }
     ));
     if let DeltaTableError::Transaction { source } = result.unwrap_err() {
-        assert!(matches!(source, TransactionError::CommitConflict(_)));
+        assert!(matches!(source, TransactionError::CommitConflict { .. }));
     }
 }
 
@@ -227,6 +227,6 @@ async fn test_merge_concurrent_with_overlapping_files() {
         DeltaTableError::Transaction { .. }
     ));
     if let DeltaTableError::Transaction { source } = result.unwrap_err() {
-        assert!(matches!(source, TransactionError::CommitConflict(_)));
+        assert!(matches!(source, TransactionError::CommitConflict { .. }));
     }
 }
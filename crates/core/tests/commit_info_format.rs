@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 mod fs_common;
 
-use deltalake_core::kernel::transaction::CommitBuilder;
+use deltalake_core::kernel::transaction::{CommitBuilder, CommitProperties};
 use deltalake_core::kernel::Action;
 use deltalake_core::protocol::{DeltaOperation, SaveMode};
 use serde_json::json;
@@ -35,3 +35,60 @@ async fn test_operational_parameters() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_operational_parameters_predicate() -> Result<(), Box<dyn Error>> {
+    let path = tempfile::tempdir().unwrap();
+    let mut table = fs_common::create_table(path.path().to_str().unwrap(), None).await;
+
+    let operation = DeltaOperation::Delete {
+        predicate: Some("id = 1".to_string()),
+    };
+    CommitBuilder::default()
+        .build(Some(table.snapshot()?), table.log_store(), operation)
+        .await?;
+    table.update().await?;
+
+    let commit_info = table.history(Some(1)).await?;
+    let parameters = commit_info[0].operation_parameters.clone().unwrap();
+    assert_eq!(parameters["predicate"], json!("id = 1"));
+
+    let operation = DeltaOperation::Delete {
+        predicate: Some("id = 2".to_string()),
+    };
+    CommitBuilder::from(CommitProperties::default().with_operation_predicate("id IN (2)"))
+        .build(Some(table.snapshot()?), table.log_store(), operation)
+        .await?;
+    table.update().await?;
+
+    let commit_info = table.history(Some(1)).await?;
+    let parameters = commit_info[0].operation_parameters.clone().unwrap();
+    assert_eq!(parameters["predicate"], json!("id IN (2)"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_engine_info() -> Result<(), Box<dyn Error>> {
+    let path = tempfile::tempdir().unwrap();
+    let mut table = fs_common::create_table(path.path().to_str().unwrap(), None).await;
+
+    let operation = DeltaOperation::Write {
+        mode: SaveMode::Append,
+        partition_by: None,
+        predicate: None,
+    };
+    CommitBuilder::from(CommitProperties::default().with_engine_info("myengine/1.2.3"))
+        .with_actions(vec![Action::Add(fs_common::add(0))])
+        .build(Some(table.snapshot()?), table.log_store(), operation)
+        .await?;
+    table.update().await?;
+
+    let commit_info = table.history(Some(1)).await?;
+    let last_commit = &commit_info[0];
+    assert_eq!(last_commit.engine_info.as_deref(), Some("myengine/1.2.3"));
+    let client_version = last_commit.info["clientVersion"].as_str().unwrap();
+    assert!(client_version.starts_with("myengine/1.2.3 (delta-rs."));
+
+    Ok(())
+}
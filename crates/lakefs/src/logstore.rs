@@ -191,6 +191,10 @@ impl LogStore for LakeFSLogStore {
         "LakeFSLogStore".into()
     }
 
+    fn supports_conditional_put(&self) -> bool {
+        true
+    }
+
     async fn read_commit_entry(&self, version: i64) -> DeltaResult<Option<Bytes>> {
         read_commit_entry(&self.storage.get_store(&self.config.location)?, version).await
     }